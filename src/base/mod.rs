@@ -32,6 +32,12 @@ pub fn show_hii(table: &ScopedProtocol<HiiDatabaseProtocol>) -> Result {
                     package.package_type(),
                     package.len(),
                 );
+
+                if let DefinedStruct::DevicePathPackage(dp) = DefinedStruct::from(&package)
+                    && let Some(text) = dp.to_text()
+                {
+                    println!("            DevicePath: {}", text);
+                }
             }
         }
         return Status::SUCCESS.to_result();
@@ -59,15 +65,174 @@ pub fn hii_strings_uni(table: &ScopedProtocol<HiiDatabaseProtocol>, package_guid
 pub fn show_dump_vfr_form(table: &ScopedProtocol<HiiDatabaseProtocol>, package_guid: Guid) -> Result {
     if let Some(package_form) = table.get_package::<HiiFormPackageHdr>(package_guid) {
         println!("Form package Guid: {}\n", package_guid);
+        let strings = table
+            .get_string_table(package_guid)
+            .unwrap_or_else(|| StringTable::new(String::new()));
 
         for fph in package_form {
             println!("// PACKAGE HEADER\n");
             println!("{}", fph.header());
 
-            println!("// PACKAGE DATA");
-            print_hex_dump(fph.as_data());
+            match ifr::decompile_package(&fph, &strings) {
+                Ok(vfr) => println!("\n{}", vfr),
+                Err(err) => {
+                    println!("// DECOMPILE FAILED: {}", err);
+                    println!("// PACKAGE DATA");
+                    print_hex_dump(fph.as_data());
+                }
+            }
         }
         return Status::SUCCESS.to_result();
     }
     Status::NOT_FOUND.to_result()
 }
+
+// Print validation diagnostics of the form packages of a package list
+pub fn show_form_diagnostics(table: &ScopedProtocol<HiiDatabaseProtocol>, package_guid: Guid) -> Result {
+    let Some(package_form) = table.get_package::<HiiFormPackageHdr>(package_guid) else {
+        return Status::NOT_FOUND.to_result();
+    };
+    let strings = table.get_string_table(package_guid);
+
+    for fph in package_form {
+        let diagnostics = ifr::validate_package(&fph, strings.as_ref());
+        println!("Form package Guid: {}; {} diagnostics", package_guid, diagnostics.len());
+        for diagnostic in diagnostics {
+            println!("    {}", diagnostic);
+        }
+    }
+    Status::SUCCESS.to_result()
+}
+
+// Print all GUID packages, decoding those with a registered decoder
+pub fn show_guid_packages(
+    table: &ScopedProtocol<HiiDatabaseProtocol>,
+    registry: &GuidPackageRegistry,
+) -> Result {
+    if let Some(list_pack) = table.get_hii_package_lists() {
+        for package_list in list_pack {
+            let list_guid = package_list.header().package_list_guid;
+
+            for package in package_list {
+                match DefinedStruct::with_registry(&package, registry) {
+                    DefinedStruct::VendorPackage(view) => {
+                        println!("PackageList GUID={}; package GUID={}", list_guid, view.guid());
+                        println!("{}", view);
+                    }
+                    DefinedStruct::GuidPackage(gph) => {
+                        let Some(guid) = gph.guid() else {
+                            println!("PackageList GUID={}; truncated GUID package", list_guid);
+                            continue;
+                        };
+                        println!("PackageList GUID={}; package GUID={}", list_guid, guid);
+                        print_hex_dump(gph.as_data());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        return Status::SUCCESS.to_result();
+    }
+    Status::NOT_FOUND.to_result()
+}
+
+// Print the current value of every question stored in an EFI varstore
+pub fn show_question_values(table: &ScopedProtocol<HiiDatabaseProtocol>, package_guid: Guid) -> Result {
+    let Some(form_sets) = table.get_form_sets(package_guid) else {
+        return Status::NOT_FOUND.to_result();
+    };
+
+    for form_set in &form_sets {
+        println!("FormSet {}: GUID={}", form_set.title, form_set.guid);
+        let contents = config::read_efi_varstores(form_set);
+        for value in form_set.question_values(&contents) {
+            println!("    {} = {}", value.question.prompt, value);
+        }
+    }
+    Status::SUCCESS.to_result()
+}
+
+// Set a question found by its prompt or UEFI keyword and print the bytes changed
+pub fn set_setup_option(
+    table: &ScopedProtocol<HiiDatabaseProtocol>,
+    routing: Option<&ScopedProtocol<config::HiiConfigRoutingProtocol>>,
+    package_guid: Guid,
+    key: &str,
+    value: &str,
+    dry_run: bool,
+) -> Result {
+    let Some(form_sets) = table.get_form_sets(package_guid) else {
+        return Status::NOT_FOUND.to_result();
+    };
+    let keywords = table.get_keyword_table(package_guid);
+
+    for form_set in &form_sets {
+        let question = form_set.question_by_prompt(key).or_else(|| {
+            keywords
+                .as_ref()
+                .and_then(|keywords| form_set.question_by_keyword(keywords, key))
+        });
+        let Some(question) = question else {
+            continue;
+        };
+
+        let value = match question.parse_value(value) {
+            Ok(value) => value,
+            Err(err) => {
+                println!("{}: {}", key, err);
+                return Status::INVALID_PARAMETER.to_result();
+            }
+        };
+        let routing = routing.map(|routing| &**routing);
+        return match config::set_question_value(form_set, question, &value, routing, dry_run) {
+            Ok(change) => {
+                println!("{} = {}", question.prompt, question.format_value(&value));
+                println!("{}", change);
+                Status::SUCCESS.to_result()
+            }
+            Err(err) => {
+                match err.data() {
+                    Some(reason) => println!("{}: {}", key, reason),
+                    None => println!("{}: {:?}", key, err.status()),
+                }
+                err.status().to_result()
+            }
+        };
+    }
+    Status::NOT_FOUND.to_result()
+}
+
+// Print the questions of EFI varstores whose current value differs from the default of `default_id`
+pub fn show_default_diff(
+    table: &ScopedProtocol<HiiDatabaseProtocol>,
+    package_guid: Guid,
+    default_id: u16,
+) -> Result {
+    let Some(form_sets) = table.get_form_sets(package_guid) else {
+        return Status::NOT_FOUND.to_result();
+    };
+
+    for form_set in &form_sets {
+        println!("FormSet {}: GUID={}; default store 0x{:04X}", form_set.title, form_set.guid, default_id);
+        let contents = config::read_efi_varstores(form_set);
+        let current = form_set.question_values(&contents);
+        let values = ifr::expr_values(&current);
+
+        for default in form_set.defaults(default_id, &values) {
+            let Some(data) = default.question.varstore.and_then(|id| contents.get(&id)) else {
+                continue;
+            };
+            if !default.differs_from(data) {
+                continue;
+            }
+            let value = current
+                .iter()
+                .find(|value| core::ptr::eq(value.question, default.question));
+            match value {
+                Some(value) => println!("    {} = {}; default {}", default.question.prompt, value, default),
+                None => println!("    {}: default {}", default.question.prompt, default),
+            }
+        }
+    }
+    Status::SUCCESS.to_result()
+}
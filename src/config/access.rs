@@ -0,0 +1,354 @@
+use uefi::boot::{self, MemoryType};
+use uefi::proto::unsafe_protocol;
+use uefi::{CStr16, Char16, Handle, Identify, Result, Status};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use core::ffi::c_void;
+use core::{ptr, slice};
+
+use super::*;
+use crate::ifr::IfrTypeValue;
+
+/// EFI_BROWSER_ACTION.
+pub type EfiBrowserAction = usize;
+
+pub const EFI_BROWSER_ACTION_CHANGING: EfiBrowserAction = 0;
+pub const EFI_BROWSER_ACTION_CHANGED: EfiBrowserAction = 1;
+pub const EFI_BROWSER_ACTION_RETRIEVE: EfiBrowserAction = 2;
+pub const EFI_BROWSER_ACTION_FORM_OPEN: EfiBrowserAction = 3;
+pub const EFI_BROWSER_ACTION_FORM_CLOSE: EfiBrowserAction = 4;
+pub const EFI_BROWSER_ACTION_SUBMITTED: EfiBrowserAction = 5;
+pub const EFI_BROWSER_ACTION_DEFAULT_STANDARD: EfiBrowserAction = 0x1000;
+pub const EFI_BROWSER_ACTION_DEFAULT_MANUFACTURING: EfiBrowserAction = 0x1001;
+pub const EFI_BROWSER_ACTION_DEFAULT_SAFE: EfiBrowserAction = 0x1002;
+pub const EFI_BROWSER_ACTION_DEFAULT_PLATFORM: EfiBrowserAction = 0x2000;
+pub const EFI_BROWSER_ACTION_DEFAULT_HARDWARE: EfiBrowserAction = 0x3000;
+pub const EFI_BROWSER_ACTION_DEFAULT_FIRMWARE: EfiBrowserAction = 0x4000;
+
+/// EFI_BROWSER_ACTION_REQUEST.
+pub type EfiBrowserActionRequest = usize;
+
+pub const EFI_BROWSER_ACTION_REQUEST_NONE: EfiBrowserActionRequest = 0;
+pub const EFI_BROWSER_ACTION_REQUEST_RESET: EfiBrowserActionRequest = 1;
+pub const EFI_BROWSER_ACTION_REQUEST_SUBMIT: EfiBrowserActionRequest = 2;
+pub const EFI_BROWSER_ACTION_REQUEST_EXIT: EfiBrowserActionRequest = 3;
+pub const EFI_BROWSER_ACTION_REQUEST_FORM_SUBMIT_EXIT: EfiBrowserActionRequest = 4;
+pub const EFI_BROWSER_ACTION_REQUEST_FORM_DISCARD_EXIT: EfiBrowserActionRequest = 5;
+pub const EFI_BROWSER_ACTION_REQUEST_FORM_APPLY: EfiBrowserActionRequest = 6;
+pub const EFI_BROWSER_ACTION_REQUEST_FORM_DISCARD: EfiBrowserActionRequest = 7;
+pub const EFI_BROWSER_ACTION_REQUEST_RECONNECT: EfiBrowserActionRequest = 8;
+pub const EFI_BROWSER_ACTION_REQUEST_QUESTION_APPLY: EfiBrowserActionRequest = 9;
+
+/// Size of the EFI_IFR_TYPE_VALUE union, EFI_HII_REF is its largest member
+const EFI_IFR_TYPE_VALUE_SIZE: usize = 22;
+
+/// HII Configuration Access Protocol.
+#[derive(Debug)]
+#[repr(C)]
+#[unsafe_protocol("330d4706-f2a0-4e4f-a369-b66fa8d54385")]
+pub struct HiiConfigAccessProtocol {
+    extract_config: unsafe extern "efiapi" fn(
+        *const Self,
+        request: *const Char16,
+        progress: *mut *const Char16,
+        results: *mut *mut Char16,
+    ) -> Status,
+    route_config: unsafe extern "efiapi" fn(
+        *const Self,
+        configuration: *const Char16,
+        progress: *mut *const Char16,
+    ) -> Status,
+    callback: unsafe extern "efiapi" fn(
+        *const Self,
+        action: EfiBrowserAction,
+        question_id: u16,
+        r#type: u8,
+        value: *mut u8,
+        action_request: *mut EfiBrowserActionRequest,
+    ) -> Status,
+}
+
+/// Buffer storage of a driver, served to the form browser through
+/// [`HiiConfigAccessProtocol`] once installed with [`install_config_access`].
+///
+/// Configuration strings are translated to reads and writes of the whole
+/// storage, the driver only handles the buffer.
+pub trait ConfigAccess {
+    /// Header of the storage, requests for other storages are NOT_FOUND
+    fn config_hdr(&self) -> ConfigHdr;
+
+    /// Current contents of the storage
+    fn read(&mut self) -> Result<Vec<u8>>;
+
+    /// Store the contents of the storage after the browser changed it
+    fn write(&mut self, block: &[u8]) -> Result;
+
+    /// Browser callback of a question with EFI_IFR_FLAG_CALLBACK.
+    ///
+    /// `value` may be updated for RETRIEVE and CHANGING; buffer values of ordered
+    /// lists have an unknown width and are passed empty.
+    fn callback(
+        &mut self,
+        action: EfiBrowserAction,
+        question_id: u16,
+        value: &mut IfrTypeValue,
+    ) -> Result<EfiBrowserActionRequest> {
+        let _ = (action, question_id, value);
+        Err(Status::UNSUPPORTED.into())
+    }
+}
+
+/// Protocol instance followed by the driver it dispatches to
+#[repr(C)]
+struct ConfigAccessInstance {
+    protocol: HiiConfigAccessProtocol,
+    access: Box<dyn ConfigAccess>,
+}
+
+/// Config Access installed on a handle, uninstalled with [`InstalledConfigAccess::uninstall`].
+#[derive(Debug)]
+pub struct InstalledConfigAccess {
+    handle: Handle,
+    instance: *mut ConfigAccessInstance,
+}
+
+impl InstalledConfigAccess {
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Remove the protocol from the handle and drop the driver
+    pub fn uninstall(self) -> Result {
+        unsafe {
+            boot::uninstall_protocol_interface(
+                self.handle,
+                &HiiConfigAccessProtocol::GUID,
+                self.instance as *const c_void,
+            )?;
+            drop(Box::from_raw(self.instance));
+        }
+        Ok(())
+    }
+}
+
+/// Install [`HiiConfigAccessProtocol`] for `access` on `handle`, a new handle
+/// is created without one. The handle is usually the driver handle given to
+/// [`HiiDatabaseProtocol::add_packages`](crate::HiiDatabaseProtocol::add_packages).
+pub fn install_config_access(
+    handle: Option<Handle>,
+    access: impl ConfigAccess + 'static,
+) -> Result<InstalledConfigAccess> {
+    let instance = Box::into_raw(Box::new(ConfigAccessInstance {
+        protocol: HiiConfigAccessProtocol {
+            extract_config,
+            route_config,
+            callback,
+        },
+        access: Box::new(access),
+    }));
+
+    let installed = unsafe {
+        boot::install_protocol_interface(
+            handle,
+            &HiiConfigAccessProtocol::GUID,
+            instance as *const c_void,
+        )
+    };
+    match installed {
+        Ok(handle) => Ok(InstalledConfigAccess { handle, instance }),
+        Err(err) => {
+            drop(unsafe { Box::from_raw(instance) });
+            Err(err)
+        }
+    }
+}
+
+/// Same storage, the device path is not compared
+fn is_config_hdr_match(header: &ConfigHdr, other: &ConfigHdr) -> bool {
+    header.guid == other.guid && header.name == other.name
+}
+
+unsafe fn instance<'a>(this: *const HiiConfigAccessProtocol) -> &'a mut ConfigAccessInstance {
+    unsafe { &mut *(this as *mut ConfigAccessInstance) }
+}
+
+unsafe fn input_string(text: *const Char16) -> Option<String> {
+    match text.is_null() {
+        true => None,
+        false => Some(unsafe { CStr16::from_ptr(text) }.to_string()),
+    }
+}
+
+/// Pointer to the character at `offset` of `text`
+unsafe fn at(text: *const Char16, offset: usize) -> *const Char16 {
+    unsafe { text.add(offset) }
+}
+
+/// Offset of the most recent '&' before `offset` of `text`, where parsing failed
+fn error_progress(text: &str, offset: usize) -> usize {
+    text.encode_utf16()
+        .take(offset)
+        .enumerate()
+        .filter(|(_, unit)| *unit == b'&' as u16)
+        .last()
+        .map_or(0, |(amp, _)| amp)
+}
+
+/// Pool allocated copy of `text`, freed by the caller of ExtractConfig
+fn pool_string(text: &str) -> Result<*mut Char16> {
+    let units: Vec<u16> = text.encode_utf16().chain([0]).collect();
+    let buffer = boot::allocate_pool(MemoryType::BOOT_SERVICES_DATA, units.len() * 2)?;
+    unsafe {
+        ptr::copy_nonoverlapping(units.as_ptr(), buffer.as_ptr() as *mut u16, units.len());
+    }
+    Ok(buffer.as_ptr() as *mut Char16)
+}
+
+unsafe extern "efiapi" fn extract_config(
+    this: *const HiiConfigAccessProtocol,
+    request: *const Char16,
+    progress: *mut *const Char16,
+    results: *mut *mut Char16,
+) -> Status {
+    if this.is_null() || progress.is_null() || results.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    let instance = unsafe { instance(this) };
+    let header = instance.access.config_hdr();
+    unsafe { *progress = request };
+
+    let text = unsafe { input_string(request) };
+    let mut parsed = match &text {
+        Some(text) => match text.parse::<ConfigRequest>() {
+            Ok(parsed) => parsed,
+            Err(ConfigStringError::Missing { offset: 0, .. }) => return Status::NOT_FOUND,
+            Err(err) => {
+                let offset = error_progress(text, err.offset());
+                unsafe { *progress = at(request, offset) };
+                return Status::INVALID_PARAMETER;
+            }
+        },
+        // No request, the whole storage
+        None => ConfigRequest::new(header.clone()),
+    };
+    let request_end = text.map_or(0, |text| text.encode_utf16().count());
+    if !is_config_hdr_match(&parsed.header, &header) {
+        return Status::NOT_FOUND;
+    }
+
+    let block = match instance.access.read() {
+        Ok(block) => block,
+        Err(err) => return err.status(),
+    };
+    // A request without elements is for the whole storage
+    if parsed.elements.is_empty() {
+        parsed.block(0, block.len());
+    }
+    let response = match parsed.block_to_config(&block) {
+        Ok(response) => response,
+        Err(err) => return err.status(),
+    };
+    match pool_string(&response.to_string()) {
+        Ok(text) => unsafe { *results = text },
+        Err(err) => return err.status(),
+    }
+    if !request.is_null() {
+        unsafe { *progress = at(request, request_end) };
+    }
+    Status::SUCCESS
+}
+
+unsafe extern "efiapi" fn route_config(
+    this: *const HiiConfigAccessProtocol,
+    configuration: *const Char16,
+    progress: *mut *const Char16,
+) -> Status {
+    if this.is_null() || configuration.is_null() || progress.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    let instance = unsafe { instance(this) };
+    unsafe { *progress = configuration };
+
+    let text = unsafe { input_string(configuration) }.unwrap_or_default();
+    let response = match text.parse::<ConfigResp>() {
+        Ok(response) => response,
+        Err(ConfigStringError::Missing { offset: 0, .. }) => return Status::NOT_FOUND,
+        Err(err) => {
+            let offset = error_progress(&text, err.offset());
+            unsafe { *progress = at(configuration, offset) };
+            return Status::INVALID_PARAMETER;
+        }
+    };
+    if !is_config_hdr_match(&response.header, &instance.access.config_hdr()) {
+        return Status::NOT_FOUND;
+    }
+
+    let mut block = match instance.access.read() {
+        Ok(block) => block,
+        Err(err) => return err.status(),
+    };
+    if let Err(err) = response.config_to_block(&mut block) {
+        return err.status();
+    }
+    if let Err(err) = instance.access.write(&block) {
+        return err.status();
+    }
+    unsafe { *progress = at(configuration, text.encode_utf16().count()) };
+    Status::SUCCESS
+}
+
+unsafe extern "efiapi" fn callback(
+    this: *const HiiConfigAccessProtocol,
+    action: EfiBrowserAction,
+    question_id: u16,
+    r#type: u8,
+    value: *mut u8,
+    action_request: *mut EfiBrowserActionRequest,
+) -> Status {
+    if this.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    let instance = unsafe { instance(this) };
+
+    let mut typed = match (value.is_null(), r#type) {
+        (true, _) => IfrTypeValue::Undefined,
+        (false, crate::ifr::EFI_IFR_TYPE_BUFFER) => IfrTypeValue::Buffer(Vec::new()),
+        (false, _) => {
+            let data = unsafe { slice::from_raw_parts(value, EFI_IFR_TYPE_VALUE_SIZE) };
+            IfrTypeValue::from_bytes(r#type, data).unwrap_or(IfrTypeValue::Undefined)
+        }
+    };
+    let original = typed.clone();
+
+    let request = match instance.access.callback(action, question_id, &mut typed) {
+        Ok(request) => request,
+        Err(err) => return err.status(),
+    };
+
+    if typed != original && !value.is_null() && r#type != crate::ifr::EFI_IFR_TYPE_BUFFER {
+        let data = typed.to_bytes();
+        let length = data.len().min(EFI_IFR_TYPE_VALUE_SIZE);
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), value, length) };
+    }
+    if !action_request.is_null() {
+        unsafe { *action_request = request };
+    }
+    Status::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_progress_counts_characters() {
+        assert_eq!(error_progress("ab&cé&d", 6), 5);
+        assert_eq!(error_progress("ab&cé&d", 5), 2);
+        assert_eq!(error_progress("a😀&b&c", 5), 3);
+        assert_eq!(error_progress("abc", 10), 0);
+    }
+}
@@ -0,0 +1,203 @@
+extern crate alloc;
+use alloc::string::{String, ToString};
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use uefi::Status;
+
+use super::*;
+
+/// Errors of the BlockToConfig and ConfigToBlock transforms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockConfigError {
+    /// Configuration string does not parse
+    Parse(ConfigStringError),
+    /// Name/value element, only `OFFSET`/`WIDTH` elements address a block
+    NameElement { name: String },
+    /// Element past the end of the block given to BlockToConfig
+    OutOfRange {
+        offset: usize,
+        width: usize,
+        size: usize,
+    },
+    /// Block given to ConfigToBlock is smaller than `required`, the elements that fit are written
+    BufferTooSmall { required: usize },
+}
+
+impl BlockConfigError {
+    /// Status returned by the Config Routing protocol for the error
+    pub fn status(&self) -> Status {
+        match self {
+            BlockConfigError::Parse(_) | BlockConfigError::NameElement { .. } => {
+                Status::INVALID_PARAMETER
+            }
+            BlockConfigError::OutOfRange { .. } => Status::DEVICE_ERROR,
+            BlockConfigError::BufferTooSmall { .. } => Status::BUFFER_TOO_SMALL,
+        }
+    }
+}
+
+impl From<ConfigStringError> for BlockConfigError {
+    fn from(error: ConfigStringError) -> Self {
+        BlockConfigError::Parse(error)
+    }
+}
+
+impl Display for BlockConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockConfigError::Parse(error) => write!(f, "{}", error),
+            BlockConfigError::NameElement { name } => {
+                write!(f, "{} is not a block element", name)
+            }
+            BlockConfigError::OutOfRange {
+                offset,
+                width,
+                size,
+            } => write!(
+                f,
+                "element 0x{:04X}+{} is outside the block of {} bytes",
+                offset, width, size
+            ),
+            BlockConfigError::BufferTooSmall { required } => {
+                write!(f, "block is too small, {} bytes required", required)
+            }
+        }
+    }
+}
+
+impl ConfigRequest {
+    /// BlockToConfig: `<ConfigResp>` with the values of `block` for the elements of the request.
+    ///
+    /// Values keep the width requested, a request without elements gives a response without elements.
+    pub fn block_to_config(&self, block: &[u8]) -> Result<ConfigResp, BlockConfigError> {
+        let mut response = ConfigResp::new(self.header.clone());
+        for element in &self.elements {
+            let (offset, width) = match element {
+                RequestElement::Block { offset, width } => (*offset, *width),
+                RequestElement::Name(name) => {
+                    return Err(BlockConfigError::NameElement { name: name.clone() });
+                }
+            };
+            let value = offset
+                .checked_add(width)
+                .and_then(|end| block.get(offset..end))
+                .ok_or(BlockConfigError::OutOfRange {
+                    offset,
+                    width,
+                    size: block.len(),
+                })?;
+            response.block(offset, value);
+        }
+        Ok(response)
+    }
+}
+
+impl ConfigResp {
+    /// ConfigToBlock: write the values of the response into `block`, returns the
+    /// size of the block the response addresses.
+    ///
+    /// Alternative configurations are ignored. An empty `block` only queries the size.
+    pub fn config_to_block(&self, block: &mut [u8]) -> Result<usize, BlockConfigError> {
+        let mut required = 0;
+        for element in &self.elements {
+            let (offset, value) = match element {
+                ConfigElement::Block { offset, value } => (*offset, value),
+                ConfigElement::NameValue { name, .. } => {
+                    return Err(BlockConfigError::NameElement { name: name.clone() });
+                }
+            };
+            let end = offset.saturating_add(value.len());
+            if let Some(target) = block.get_mut(offset..end) {
+                target.copy_from_slice(value);
+            }
+            required = required.max(end);
+        }
+
+        match required > block.len() {
+            true => Err(BlockConfigError::BufferTooSmall { required }),
+            false => Ok(required),
+        }
+    }
+}
+
+/// BlockToConfig of a `<ConfigRequest>` string, see [`ConfigRequest::block_to_config`]
+pub fn block_to_config(request: &str, block: &[u8]) -> Result<String, BlockConfigError> {
+    let request: ConfigRequest = request.parse()?;
+    Ok(request.block_to_config(block)?.to_string())
+}
+
+/// ConfigToBlock of a `<ConfigResp>` string, see [`ConfigResp::config_to_block`]
+pub fn config_to_block(response: &str, block: &mut [u8]) -> Result<usize, BlockConfigError> {
+    let response: ConfigResp = response.parse()?;
+    response.config_to_block(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=0041&PATH=01";
+
+    #[test]
+    fn block_to_config_reverses_value_bytes() {
+        let request = format!("{}&OFFSET=0001&WIDTH=0002&OFFSET=0004&WIDTH=0001", HEADER);
+        let block = [0x00, 0x34, 0x12, 0x00, 0xab];
+        assert_eq!(
+            block_to_config(&request, &block),
+            Ok(format!(
+                "{}&OFFSET=0001&WIDTH=0002&VALUE=1234&OFFSET=0004&WIDTH=0001&VALUE=ab",
+                HEADER
+            ))
+        );
+    }
+
+    #[test]
+    fn block_to_config_rejects_elements_outside_block() {
+        let request = format!("{}&OFFSET=0003&WIDTH=0002", HEADER);
+        assert_eq!(
+            block_to_config(&request, &[0; 4]),
+            Err(BlockConfigError::OutOfRange {
+                offset: 3,
+                width: 2,
+                size: 4
+            })
+        );
+        let request = format!("{}&Speed", HEADER);
+        assert_eq!(
+            block_to_config(&request, &[0; 4]).map_err(|err| err.status()),
+            Err(Status::INVALID_PARAMETER)
+        );
+    }
+
+    #[test]
+    fn config_to_block_zero_extends_short_values() {
+        let response = format!("{}&OFFSET=0000&WIDTH=0004&VALUE=1234", HEADER);
+        let mut block = [0xff; 4];
+        assert_eq!(config_to_block(&response, &mut block), Ok(4));
+        assert_eq!(block, [0x34, 0x12, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn config_to_block_reports_required_size() {
+        let response = format!(
+            "{}&OFFSET=0000&WIDTH=0001&VALUE=01&OFFSET=0004&WIDTH=0002&VALUE=0302",
+            HEADER
+        );
+        let mut block = [0; 2];
+        let error = config_to_block(&response, &mut block).unwrap_err();
+        assert_eq!(error, BlockConfigError::BufferTooSmall { required: 6 });
+        assert_eq!(error.status(), Status::BUFFER_TOO_SMALL);
+        // Elements that fit are still written
+        assert_eq!(block, [0x01, 0x00]);
+
+        assert_eq!(
+            config_to_block(&response, &mut []),
+            Err(BlockConfigError::BufferTooSmall { required: 6 })
+        );
+        let mut block = [0; 6];
+        assert_eq!(config_to_block(&response, &mut block), Ok(6));
+        assert_eq!(block, [0x01, 0x00, 0x00, 0x00, 0x02, 0x03]);
+    }
+}
@@ -0,0 +1,138 @@
+use uefi::proto::unsafe_protocol;
+use uefi::{CStr16, Char16, Guid, Handle, Result, Status, StatusExt};
+
+extern crate alloc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+
+use core::ptr;
+
+use super::*;
+
+/// EFI_SCREEN_DESCRIPTOR, the part of the screen used by the browser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct EfiScreenDescriptor {
+    pub left_column: usize,
+    pub right_column: usize,
+    pub top_row: usize,
+    pub bottom_row: usize,
+}
+
+/// Form Browser2 Protocol.
+#[derive(Debug)]
+#[repr(C)]
+#[unsafe_protocol("b9d4c360-bcfb-4f9b-9298-53c136982258")]
+pub struct FormBrowser2Protocol {
+    send_form: unsafe extern "efiapi" fn(
+        *const Self,
+        handles: *const Handle,
+        handle_count: usize,
+        form_set_guid: *const Guid,
+        form_id: u16,
+        screen_dimensions: *const EfiScreenDescriptor,
+        action_request: *mut EfiBrowserActionRequest,
+    ) -> Status,
+    browser_callback: unsafe extern "efiapi" fn(
+        *const Self,
+        results_data_size: *mut usize,
+        results_data: *mut Char16,
+        retrieve_data: bool,
+        variable_guid: *const Guid,
+        variable_name: *const Char16,
+    ) -> Status,
+}
+
+impl FormBrowser2Protocol {
+    /// Display the forms of the HII handles and return the action requested
+    /// by the user when the browser exits.
+    ///
+    /// Without `form_set_guid` the first form set of the handles is shown,
+    /// without `form_id` its first form.
+    pub fn send_form(
+        &self,
+        handles: &[Handle],
+        form_set_guid: Option<&Guid>,
+        form_id: Option<u16>,
+        screen: Option<&EfiScreenDescriptor>,
+    ) -> Result<EfiBrowserActionRequest> {
+        let mut action_request = EFI_BROWSER_ACTION_REQUEST_NONE;
+        unsafe {
+            (self.send_form)(
+                self,
+                handles.as_ptr(),
+                handles.len(),
+                form_set_guid.map_or(ptr::null(), |guid| guid as *const Guid),
+                form_id.unwrap_or(0),
+                screen.map_or(ptr::null(), |screen| screen as *const EfiScreenDescriptor),
+                &mut action_request,
+            )
+        }
+        .to_result_with_val(|| action_request)
+    }
+
+    /// Values being edited in the browser (BrowserCallback with RetrieveData),
+    /// a `<ConfigResp>` body without the routing header.
+    ///
+    /// Without `variable_guid` and `variable_name` the values of the storage of
+    /// the question being processed are returned.
+    pub fn get_browser_data(
+        &self,
+        variable_guid: Option<&Guid>,
+        variable_name: Option<&str>,
+    ) -> Result<String> {
+        let name = variable_name.map(to_cstring).transpose()?;
+        let name = name.as_ref().map_or(ptr::null(), |name| name.as_ptr());
+        let guid = variable_guid.map_or(ptr::null(), |guid| guid as *const Guid);
+
+        // Find out the size of the results
+        let mut size = 0;
+        let status =
+            unsafe { (self.browser_callback)(self, &mut size, ptr::null_mut(), true, guid, name) };
+        if status != Status::BUFFER_TOO_SMALL {
+            status.to_result()?;
+            return Ok(String::new());
+        }
+
+        let mut results = vec![0u16; size.div_ceil(2).max(1)];
+        unsafe {
+            (self.browser_callback)(
+                self,
+                &mut size,
+                results.as_mut_ptr() as *mut Char16,
+                true,
+                guid,
+                name,
+            )
+        }
+        .to_result()?;
+
+        let results = CStr16::from_u16_until_nul(&results).map_err(|_| Status::BAD_BUFFER_SIZE)?;
+        Ok(results.to_string())
+    }
+
+    /// Update the values being edited in the browser (BrowserCallback without
+    /// RetrieveData), `results` is a `<ConfigResp>` body without the routing header.
+    pub fn set_browser_data(
+        &self,
+        results: &str,
+        variable_guid: Option<&Guid>,
+        variable_name: Option<&str>,
+    ) -> Result {
+        let name = variable_name.map(to_cstring).transpose()?;
+        let results = to_cstring(results)?;
+        let mut size = results.num_bytes();
+
+        unsafe {
+            (self.browser_callback)(
+                self,
+                &mut size,
+                results.as_ptr() as *mut Char16,
+                false,
+                variable_guid.map_or(ptr::null(), |guid| guid as *const Guid),
+                name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+            )
+        }
+        .to_result()
+    }
+}
@@ -0,0 +1,20 @@
+//! HII configuration: configuration strings, their routing to the drivers owning the
+//! storages, the UEFI variables behind EFI varstores, and the form browser editing them.
+
+mod routing;
+pub use routing::*;
+
+mod request;
+pub use request::*;
+
+mod access;
+pub use access::*;
+
+mod block;
+pub use block::*;
+
+mod browser;
+pub use browser::*;
+
+mod varstore;
+pub use varstore::*;
@@ -0,0 +1,775 @@
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter, Write};
+use core::str::FromStr;
+
+use uefi::Guid;
+
+use crate::ifr::{FormSet, Question, QuestionStorage, VarStore};
+
+/// Errors reported while parsing configuration strings, offsets are in characters
+/// (UTF-16 code units, like the `Progress` pointer of the CHAR16 string).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigStringError {
+    /// Field `field` expected at `offset` is missing
+    Missing { offset: usize, field: &'static str },
+    /// Field value is not hexadecimal
+    InvalidHex { offset: usize },
+    /// Field value has a wrong number of digits, or VALUE is longer than WIDTH
+    InvalidLength { offset: usize },
+    /// Element not allowed at `offset`
+    Unexpected { offset: usize },
+}
+
+impl ConfigStringError {
+    pub fn offset(&self) -> usize {
+        match self {
+            ConfigStringError::Missing { offset, .. }
+            | ConfigStringError::InvalidHex { offset }
+            | ConfigStringError::InvalidLength { offset }
+            | ConfigStringError::Unexpected { offset } => *offset,
+        }
+    }
+}
+
+impl Display for ConfigStringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigStringError::Missing { offset, field } => {
+                write!(f, "{}: {}= expected", offset, field)
+            }
+            ConfigStringError::InvalidHex { offset } => {
+                write!(f, "{}: invalid hexadecimal value", offset)
+            }
+            ConfigStringError::InvalidLength { offset } => {
+                write!(f, "{}: invalid value length", offset)
+            }
+            ConfigStringError::Unexpected { offset } => write!(f, "{}: unexpected element", offset),
+        }
+    }
+}
+
+/// `<ConfigHdr>`: the storage a configuration string applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigHdr {
+    pub guid: Guid,
+    /// Name of the storage, empty for unnamed storages
+    pub name: String,
+    /// Device path of the driver handle owning the storage, as bytes
+    pub path: Vec<u8>,
+}
+
+impl ConfigHdr {
+    pub fn new(guid: Guid, name: &str, path: &[u8]) -> Self {
+        Self {
+            guid,
+            name: String::from(name),
+            path: path.to_vec(),
+        }
+    }
+
+    /// Header of a varstore of a form set, `path` is the device path of its driver handle
+    pub fn for_varstore(varstore: &VarStore, path: &[u8]) -> Self {
+        Self::new(varstore.guid, &varstore.name, path)
+    }
+}
+
+impl Display for ConfigHdr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("GUID=")?;
+        write_hex(f, &self.guid.to_bytes())?;
+        f.write_str("&NAME=")?;
+        for c in self.name.encode_utf16() {
+            write!(f, "{:04x}", c)?;
+        }
+        f.write_str("&PATH=")?;
+        write_hex(f, &self.path)
+    }
+}
+
+impl FromStr for ConfigHdr {
+    type Err = ConfigStringError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(text);
+        let header = parser.header()?;
+        parser.finish()?;
+        Ok(header)
+    }
+}
+
+/// Element of a `<ConfigRequest>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestElement {
+    /// `OFFSET=<Number>&WIDTH=<Number>` of a buffer storage
+    Block { offset: usize, width: usize },
+    /// `<Label>` of a name/value storage
+    Name(String),
+}
+
+/// `<ConfigRequest>`: the elements of a storage whose values are requested.
+///
+/// Without elements the request selects the whole storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigRequest {
+    pub header: ConfigHdr,
+    pub elements: Vec<RequestElement>,
+}
+
+impl ConfigRequest {
+    pub fn new(header: ConfigHdr) -> Self {
+        Self {
+            header,
+            elements: Vec::new(),
+        }
+    }
+
+    /// Request of the storage of `questions`, those stored elsewhere are ignored
+    pub fn for_questions<'a>(
+        header: ConfigHdr,
+        var_store_id: u16,
+        questions: impl IntoIterator<Item = &'a Question>,
+    ) -> Self {
+        let mut request = Self::new(header);
+        for question in questions {
+            if question.varstore != Some(var_store_id) {
+                continue;
+            }
+            match &question.storage {
+                QuestionStorage::Offset(_) | QuestionStorage::Bits { .. } => {
+                    request.block(question.byte_offset().unwrap(), question.width)
+                }
+                QuestionStorage::Name(name) => match &name.text {
+                    Some(name) => request.name(name),
+                    None => continue,
+                },
+                QuestionStorage::None => continue,
+            };
+        }
+        request
+    }
+
+    /// Requests of the storage of every question of a form set, one per
+    /// varstore and storage device. `path` is the device path of the driver
+    /// handle, `device_path` converts the text of an EFI_IFR_VARSTORE_DEVICE.
+    ///
+    /// Name/value varstores are requested by name under an empty NAME, storages
+    /// whose device path does not convert are left out.
+    pub fn for_form_set(
+        form_set: &FormSet,
+        path: &[u8],
+        device_path: impl Fn(&str) -> Option<Vec<u8>>,
+    ) -> Vec<Self> {
+        let mut requests = Vec::new();
+        for varstore in &form_set.varstores {
+            let mut devices: Vec<Option<&str>> = form_set
+                .questions()
+                .filter(|question| question.varstore == Some(varstore.id))
+                .map(Question::storage_device)
+                .collect();
+            devices.sort_unstable();
+            devices.dedup();
+
+            for text in devices {
+                let header = match text {
+                    Some(text) => match device_path(text) {
+                        Some(path) => ConfigHdr::for_varstore(varstore, &path),
+                        None => continue,
+                    },
+                    None => ConfigHdr::for_varstore(varstore, path),
+                };
+                let questions = form_set
+                    .questions()
+                    .filter(|question| question.storage_device() == text);
+                let request = Self::for_questions(header, varstore.id, questions);
+                if !request.elements.is_empty() {
+                    requests.push(request);
+                }
+            }
+        }
+        requests
+    }
+
+    pub fn block(&mut self, offset: usize, width: usize) -> &mut Self {
+        self.elements.push(RequestElement::Block { offset, width });
+        self
+    }
+
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.elements.push(RequestElement::Name(String::from(name)));
+        self
+    }
+
+    /// Requests of a `<MultiConfigRequest>`
+    pub fn parse_multi(text: &str) -> Result<Vec<Self>, ConfigStringError> {
+        let mut parser = Parser::new(text);
+        let mut requests = Vec::new();
+        while !parser.done() {
+            requests.push(parser.request()?);
+        }
+        Ok(requests)
+    }
+}
+
+impl Display for ConfigRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header)?;
+        for element in &self.elements {
+            match element {
+                RequestElement::Block { offset, width } => {
+                    write!(f, "&OFFSET={:04x}&WIDTH={:04x}", offset, width)?
+                }
+                RequestElement::Name(name) => write!(f, "&{}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ConfigRequest {
+    type Err = ConfigStringError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(text);
+        let request = parser.request()?;
+        parser.finish()?;
+        Ok(request)
+    }
+}
+
+/// Element of a `<ConfigResp>`, values are in storage (little endian) byte order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigElement {
+    /// `OFFSET=<Number>&WIDTH=<Number>&VALUE=<Number>`, the width is the length of `value`
+    Block { offset: usize, value: Vec<u8> },
+    /// `<Label>=<Number>` of a name/value storage
+    NameValue { name: String, value: Vec<u8> },
+}
+
+/// `<AltResp>`: values of a storage for a default store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AltConfig {
+    pub header: ConfigHdr,
+    /// DefaultId of the default store
+    pub alt_cfg_id: u16,
+    pub elements: Vec<ConfigElement>,
+}
+
+/// `<ConfigResp>`: values of the elements of a storage, followed by the
+/// alternative configurations returned by ExtractConfig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigResp {
+    pub header: ConfigHdr,
+    pub elements: Vec<ConfigElement>,
+    pub alt_configs: Vec<AltConfig>,
+}
+
+impl ConfigResp {
+    pub fn new(header: ConfigHdr) -> Self {
+        Self {
+            header,
+            elements: Vec::new(),
+            alt_configs: Vec::new(),
+        }
+    }
+
+    pub fn block(&mut self, offset: usize, value: &[u8]) -> &mut Self {
+        self.elements.push(ConfigElement::Block {
+            offset,
+            value: value.to_vec(),
+        });
+        self
+    }
+
+    pub fn name_value(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        self.elements.push(ConfigElement::NameValue {
+            name: String::from(name),
+            value: value.to_vec(),
+        });
+        self
+    }
+
+    /// Alternative configuration of default store `alt_cfg_id`
+    pub fn alt_config(&self, alt_cfg_id: u16) -> Option<&AltConfig> {
+        self.alt_configs
+            .iter()
+            .find(|alt_config| alt_config.alt_cfg_id == alt_cfg_id)
+    }
+
+    /// Responses of a `<MultiConfigResp>`
+    pub fn parse_multi(text: &str) -> Result<Vec<Self>, ConfigStringError> {
+        let mut parser = Parser::new(text);
+        let mut responses = Vec::new();
+        while !parser.done() {
+            responses.push(parser.response()?);
+        }
+        Ok(responses)
+    }
+}
+
+impl Display for ConfigResp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header)?;
+        write_elements(f, &self.elements)?;
+        for alt_config in &self.alt_configs {
+            write!(
+                f,
+                "&{}&ALTCFG={:04x}",
+                alt_config.header, alt_config.alt_cfg_id
+            )?;
+            write_elements(f, &alt_config.elements)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ConfigResp {
+    type Err = ConfigStringError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(text);
+        let response = parser.response()?;
+        parser.finish()?;
+        Ok(response)
+    }
+}
+
+/// `<MultiConfigRequest>` or `<MultiConfigResp>` of `configs`
+pub fn format_multi<T: Display>(configs: &[T]) -> String {
+    let mut text = String::new();
+    for (index, config) in configs.iter().enumerate() {
+        if index > 0 {
+            text.push('&');
+        }
+        let _ = write!(text, "{}", config);
+    }
+    text
+}
+
+fn write_hex(f: &mut Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+}
+
+fn write_elements(f: &mut Formatter<'_>, elements: &[ConfigElement]) -> fmt::Result {
+    for element in elements {
+        let value = match element {
+            ConfigElement::Block { offset, value } => {
+                write!(f, "&OFFSET={:04x}&WIDTH={:04x}&VALUE=", offset, value.len())?;
+                value
+            }
+            ConfigElement::NameValue { name, value } => {
+                write!(f, "&{}=", name)?;
+                value
+            }
+        };
+        // <Number> is the value with the most significant byte first
+        write_hex_reversed(f, value)?;
+    }
+    Ok(())
+}
+
+fn write_hex_reversed(f: &mut Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().rev().try_for_each(|b| write!(f, "{:02x}", b))
+}
+
+/// `&` separated `key=value` pair of a configuration string
+struct Field<'a> {
+    offset: usize,
+    key: &'a str,
+    value: Option<&'a str>,
+}
+
+impl Field<'_> {
+    fn value_offset(&self) -> usize {
+        self.offset + self.key.encode_utf16().count() + 1
+    }
+}
+
+struct Parser<'a> {
+    fields: Vec<Field<'a>>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        if !text.is_empty() {
+            for part in text.split('&') {
+                let (key, value) = match part.split_once('=') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (part, None),
+                };
+                fields.push(Field { offset, key, value });
+                offset += part.encode_utf16().count() + 1;
+            }
+        }
+        Self {
+            fields,
+            index: 0,
+            end: text.encode_utf16().count(),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.index >= self.fields.len()
+    }
+
+    fn finish(&self) -> Result<(), ConfigStringError> {
+        match self.peek() {
+            Some(field) => Err(ConfigStringError::Unexpected {
+                offset: field.offset,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Option<&Field<'a>> {
+        self.fields.get(self.index)
+    }
+
+    fn peek_key(&self, key: &str) -> bool {
+        self.peek().is_some_and(|field| field.key == key)
+    }
+
+    /// Value of the next field, which must be `key`
+    fn expect(&mut self, key: &'static str) -> Result<(usize, &'a str), ConfigStringError> {
+        let missing = ConfigStringError::Missing {
+            offset: self.peek().map_or(self.end, |field| field.offset),
+            field: key,
+        };
+        let field = self
+            .peek()
+            .filter(|field| field.key == key)
+            .ok_or(missing)?;
+        let value = field.value.ok_or(missing)?;
+        let offset = field.value_offset();
+        self.index += 1;
+        Ok((offset, value))
+    }
+
+    fn header(&mut self) -> Result<ConfigHdr, ConfigStringError> {
+        let (offset, guid) = self.expect("GUID")?;
+        let guid: [u8; 16] = hex_bytes(offset, guid)?
+            .try_into()
+            .map_err(|_| ConfigStringError::InvalidLength { offset })?;
+
+        let (offset, name) = self.expect("NAME")?;
+        if !name.len().is_multiple_of(4) {
+            return Err(ConfigStringError::InvalidLength { offset });
+        }
+        // Digits before `i` are ASCII, so `i` is also a character offset
+        let units = (0..name.len())
+            .step_by(4)
+            .map(|i| {
+                name.get(i..i + 4)
+                    .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|digits| u16::from_str_radix(digits, 16).ok())
+                    .ok_or(ConfigStringError::InvalidHex { offset: offset + i })
+            })
+            .collect::<Result<Vec<u16>, _>>()?;
+        let name = char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|_| ConfigStringError::InvalidHex { offset })?;
+
+        let (offset, path) = self.expect("PATH")?;
+        let path = hex_bytes(offset, path)?;
+
+        Ok(ConfigHdr {
+            guid: Guid::from_bytes(guid),
+            name,
+            path,
+        })
+    }
+
+    fn request(&mut self) -> Result<ConfigRequest, ConfigStringError> {
+        let mut request = ConfigRequest::new(self.header()?);
+        while let Some(field) = self.peek() {
+            match (field.key, field.value) {
+                ("GUID", _) => break,
+                ("OFFSET", _) => {
+                    let offset = self.number("OFFSET")?;
+                    let width = self.number("WIDTH")?;
+                    request.block(offset, width);
+                }
+                (name, None) if !name.is_empty() => {
+                    request.name(name);
+                    self.index += 1;
+                }
+                _ => {
+                    return Err(ConfigStringError::Unexpected {
+                        offset: field.offset,
+                    });
+                }
+            }
+        }
+        Ok(request)
+    }
+
+    fn response(&mut self) -> Result<ConfigResp, ConfigStringError> {
+        let mut response = ConfigResp::new(self.header()?);
+        response.elements = self.elements()?;
+
+        // <AltResp> repeats the header followed by ALTCFG, a new header alone starts the next response
+        while self.peek_key("GUID")
+            && self
+                .fields
+                .get(self.index + 3)
+                .is_some_and(|field| field.key == "ALTCFG")
+        {
+            let header = self.header()?;
+            let (offset, id) = self.expect("ALTCFG")?;
+            let alt_cfg_id = u16::from_str_radix(id, 16)
+                .map_err(|_| ConfigStringError::InvalidHex { offset })?;
+            response.alt_configs.push(AltConfig {
+                header,
+                alt_cfg_id,
+                elements: self.elements()?,
+            });
+        }
+        Ok(response)
+    }
+
+    fn elements(&mut self) -> Result<Vec<ConfigElement>, ConfigStringError> {
+        let mut elements = Vec::new();
+        while let Some(field) = self.peek() {
+            match (field.key, field.value) {
+                ("GUID", _) => break,
+                ("OFFSET", _) => {
+                    let offset = self.number("OFFSET")?;
+                    let width = self.number("WIDTH")?;
+                    let (value_offset, value) = self.expect("VALUE")?;
+                    let value = hex_value(value_offset, value, Some(width))?;
+                    elements.push(ConfigElement::Block { offset, value });
+                }
+                (name, Some(value)) if !name.is_empty() => {
+                    let value = hex_value(field.value_offset(), value, None)?;
+                    elements.push(ConfigElement::NameValue {
+                        name: String::from(name),
+                        value,
+                    });
+                    self.index += 1;
+                }
+                _ => {
+                    return Err(ConfigStringError::Unexpected {
+                        offset: field.offset,
+                    });
+                }
+            }
+        }
+        Ok(elements)
+    }
+
+    fn number(&mut self, key: &'static str) -> Result<usize, ConfigStringError> {
+        let (offset, value) = self.expect(key)?;
+        usize::from_str_radix(value, 16).map_err(|_| ConfigStringError::InvalidHex { offset })
+    }
+}
+
+/// Bytes of a hex string in string order (GUID, PATH)
+fn hex_bytes(offset: usize, text: &str) -> Result<Vec<u8>, ConfigStringError> {
+    if !text.len().is_multiple_of(2) {
+        return Err(ConfigStringError::InvalidLength { offset });
+    }
+    // Digits before `i` are ASCII, so `i` is also a character offset
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or(ConfigStringError::InvalidHex { offset: offset + i })
+        })
+        .collect()
+}
+
+/// Storage bytes of a `<Number>`, zero-extended to `width`
+fn hex_value(
+    offset: usize,
+    text: &str,
+    width: Option<usize>,
+) -> Result<Vec<u8>, ConfigStringError> {
+    if !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ConfigStringError::InvalidHex { offset });
+    }
+    // Least significant digits first, an odd count leaves a single digit in the last byte
+    let mut value: Vec<u8> = text
+        .as_bytes()
+        .rchunks(2)
+        .map(|digits| {
+            digits.iter().fold(0, |acc, digit| {
+                acc << 4 | (*digit as char).to_digit(16).unwrap_or(0) as u8
+            })
+        })
+        .collect();
+
+    if let Some(width) = width {
+        if value.len() > width {
+            return Err(ConfigStringError::InvalidLength { offset });
+        }
+        value.resize(width, 0);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uefi::guid;
+
+    const GUID: Guid = guid!("32c66610-94df-4d6d-984f-8cbe44519b87");
+    const HEADER: &str =
+        "GUID=1066c632df946d4d984f8cbe44519b87&NAME=005300650074007500700021&PATH=7f01ff04";
+
+    fn header() -> ConfigHdr {
+        ConfigHdr::new(GUID, "Setup!", &[0x7f, 0x01, 0xff, 0x04])
+    }
+
+    #[test]
+    fn header_round_trip() {
+        assert_eq!(header().to_string(), HEADER);
+        assert_eq!(HEADER.parse::<ConfigHdr>(), Ok(header()));
+
+        let unnamed = ConfigHdr::new(GUID, "", &[]);
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=&PATH=";
+        assert_eq!(unnamed.to_string(), text);
+        assert_eq!(text.parse::<ConfigHdr>(), Ok(unnamed));
+    }
+
+    #[test]
+    fn header_rejects_invalid_name() {
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=000é300&PATH=";
+        assert_eq!(
+            text.parse::<ConfigHdr>(),
+            Err(ConfigStringError::InvalidHex { offset: 43 })
+        );
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=+041&PATH=";
+        assert!(text.parse::<ConfigHdr>().is_err());
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=004&PATH=";
+        assert_eq!(
+            text.parse::<ConfigHdr>(),
+            Err(ConfigStringError::InvalidLength { offset: 43 })
+        );
+    }
+
+    #[test]
+    fn offsets_count_characters() {
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=é000&PATH=";
+        assert_eq!(
+            text.parse::<ConfigHdr>(),
+            Err(ConfigStringError::InvalidLength { offset: 43 })
+        );
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=é00&PATH=";
+        assert_eq!(
+            text.parse::<ConfigHdr>(),
+            Err(ConfigStringError::InvalidHex { offset: 43 })
+        );
+
+        // 'é' is one character, '😀' two UTF-16 code units
+        let prefix = format!("{}&Spé😀d=01&OFFSET=0000&WIDTH=0001&VALUE=", HEADER);
+        let text = format!("{}zz", prefix);
+        assert_eq!(
+            text.parse::<ConfigResp>(),
+            Err(ConfigStringError::InvalidHex {
+                offset: prefix.encode_utf16().count(),
+            })
+        );
+        let text = format!("{}&Spé😀d=01&Spé", HEADER);
+        assert_eq!(
+            text.parse::<ConfigResp>(),
+            Err(ConfigStringError::Unexpected {
+                offset: HEADER.len() + 11,
+            })
+        );
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let mut request = ConfigRequest::new(header());
+        request.block(0x10, 2).name("Speed");
+        let text = format!("{}&OFFSET=0010&WIDTH=0002&Speed", HEADER);
+        assert_eq!(request.to_string(), text);
+        assert_eq!(text.parse::<ConfigRequest>(), Ok(request));
+    }
+
+    #[test]
+    fn block_value_is_most_significant_byte_first() {
+        let mut response = ConfigResp::new(header());
+        response.block(4, &[0x34, 0x12, 0x00]);
+        let text = format!("{}&OFFSET=0004&WIDTH=0003&VALUE=001234", HEADER);
+        assert_eq!(response.to_string(), text);
+        assert_eq!(text.parse::<ConfigResp>(), Ok(response));
+    }
+
+    #[test]
+    fn short_block_value_is_zero_extended() {
+        let text = format!("{}&OFFSET=0000&WIDTH=0004&VALUE=234", HEADER);
+        let response = text.parse::<ConfigResp>().unwrap();
+        assert_eq!(
+            response.elements,
+            [ConfigElement::Block {
+                offset: 0,
+                value: alloc::vec![0x34, 0x02, 0x00, 0x00],
+            }]
+        );
+
+        let text = format!("{}&OFFSET=0000&WIDTH=0001&VALUE=1234", HEADER);
+        assert!(matches!(
+            text.parse::<ConfigResp>(),
+            Err(ConfigStringError::InvalidLength { .. })
+        ));
+    }
+
+    #[test]
+    fn name_value_round_trip() {
+        let mut response = ConfigResp::new(ConfigHdr::new(GUID, "", &[]));
+        response
+            .name_value("Speed", &[0x2c, 0x01])
+            .name_value("Enable", &[1]);
+        let text = "GUID=1066c632df946d4d984f8cbe44519b87&NAME=&PATH=&Speed=012c&Enable=01";
+        assert_eq!(response.to_string(), text);
+        assert_eq!(text.parse::<ConfigResp>(), Ok(response));
+    }
+
+    #[test]
+    fn alt_config_round_trip() {
+        let mut response = ConfigResp::new(header());
+        response.block(0, &[1]);
+        response.alt_configs.push(AltConfig {
+            header: header(),
+            alt_cfg_id: 1,
+            elements: alloc::vec![ConfigElement::Block {
+                offset: 0,
+                value: alloc::vec![2],
+            }],
+        });
+        let text = format!(
+            "{h}&OFFSET=0000&WIDTH=0001&VALUE=01&{h}&ALTCFG=0001&OFFSET=0000&WIDTH=0001&VALUE=02",
+            h = HEADER
+        );
+        assert_eq!(response.to_string(), text);
+        let parsed = text.parse::<ConfigResp>().unwrap();
+        assert_eq!(
+            parsed.alt_config(1).unwrap().elements[0],
+            response.alt_configs[0].elements[0]
+        );
+        assert_eq!(parsed, response);
+    }
+
+    #[test]
+    fn multi_round_trip() {
+        let mut first = ConfigResp::new(header());
+        first.block(0, &[1]);
+        let mut second = ConfigResp::new(ConfigHdr::new(GUID, "", &[]));
+        second.name_value("Speed", &[0x10]);
+        let text = format_multi(&[first.clone(), second.clone()]);
+        assert_eq!(
+            ConfigResp::parse_multi(&text),
+            Ok(alloc::vec![first, second])
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use uefi::proto::device_path::{DevicePath, FfiDevicePath};
+use uefi::proto::unsafe_protocol;
+use uefi::{CStr16, CString16, Char16, Error, Guid, Result, Status, StatusExt, boot};
+
+extern crate alloc;
+use alloc::string::{String, ToString};
+
+use core::ptr::{self, NonNull};
+
+/// HII Configuration Routing Protocol.
+///
+/// Strings are `<ConfigRequest>`, `<ConfigResp>` and their multi forms. The
+/// error data of the request functions is the offset, in characters, of the
+/// part of the input the failure was detected at.
+#[derive(Debug)]
+#[repr(C)]
+#[unsafe_protocol("587e72d7-cc50-4f79-8209-ca291fc1a10f")]
+pub struct HiiConfigRoutingProtocol {
+    extract_config: unsafe extern "efiapi" fn(
+        *const Self,
+        request: *const Char16,
+        progress: *mut *const Char16,
+        results: *mut *mut Char16,
+    ) -> Status,
+    export_config: unsafe extern "efiapi" fn(*const Self, results: *mut *mut Char16) -> Status,
+    route_config: unsafe extern "efiapi" fn(
+        *const Self,
+        configuration: *const Char16,
+        progress: *mut *const Char16,
+    ) -> Status,
+    block_to_config: unsafe extern "efiapi" fn(
+        *const Self,
+        config_request: *const Char16,
+        block: *const u8,
+        block_size: usize,
+        config: *mut *mut Char16,
+        progress: *mut *const Char16,
+    ) -> Status,
+    config_to_block: unsafe extern "efiapi" fn(
+        *const Self,
+        config_resp: *const Char16,
+        block: *mut u8,
+        block_size: *mut usize,
+        progress: *mut *const Char16,
+    ) -> Status,
+    get_alt_config: unsafe extern "efiapi" fn(
+        *const Self,
+        config_resp: *const Char16,
+        guid: *const Guid,
+        name: *const Char16,
+        device_path: *const FfiDevicePath,
+        alt_cfg_id: *const u16,
+        alt_cfg_resp: *mut *mut Char16,
+    ) -> Status,
+}
+
+impl HiiConfigRoutingProtocol {
+    /// Current configuration of the storages selected by a `<MultiConfigRequest>`
+    pub fn extract_config(&self, request: &str) -> Result<String, usize> {
+        let request = to_cstring(request).map_err(|err| Error::new(err.status(), 0))?;
+        let mut progress = ptr::null();
+        let mut results = ptr::null_mut();
+
+        let status =
+            unsafe { (self.extract_config)(self, request.as_ptr(), &mut progress, &mut results) };
+        let results = unsafe { take_string(results) };
+        match status.is_success() {
+            true => results.ok_or(Error::new(Status::OUT_OF_RESOURCES, 0)),
+            false => Err(Error::new(status, progress_offset(&request, progress))),
+        }
+    }
+
+    /// Current configuration of every storage of every form set, a `<MultiConfigResp>`
+    pub fn export_config(&self) -> Result<String> {
+        let mut results = ptr::null_mut();
+
+        let status = unsafe { (self.export_config)(self, &mut results) };
+        let results = unsafe { take_string(results) };
+        status.to_result()?;
+        results.ok_or(Status::OUT_OF_RESOURCES.into())
+    }
+
+    /// Apply a `<MultiConfigResp>` to the storages it names
+    pub fn route_config(&self, configuration: &str) -> Result<(), usize> {
+        let configuration = to_cstring(configuration).map_err(|err| Error::new(err.status(), 0))?;
+        let mut progress = ptr::null();
+
+        let status = unsafe { (self.route_config)(self, configuration.as_ptr(), &mut progress) };
+        match status.is_success() {
+            true => Ok(()),
+            false => Err(Error::new(
+                status,
+                progress_offset(&configuration, progress),
+            )),
+        }
+    }
+
+    /// `<ConfigResp>` with the values of `block` for the elements of a `<ConfigRequest>`
+    pub fn block_to_config(&self, request: &str, block: &[u8]) -> Result<String, usize> {
+        let request = to_cstring(request).map_err(|err| Error::new(err.status(), 0))?;
+        let mut config = ptr::null_mut();
+        let mut progress = ptr::null();
+
+        let status = unsafe {
+            (self.block_to_config)(
+                self,
+                request.as_ptr(),
+                block.as_ptr(),
+                block.len(),
+                &mut config,
+                &mut progress,
+            )
+        };
+        let config = unsafe { take_string(config) };
+        match status.is_success() {
+            true => config.ok_or(Error::new(Status::OUT_OF_RESOURCES, 0)),
+            false => Err(Error::new(status, progress_offset(&request, progress))),
+        }
+    }
+
+    /// Write the values of a `<ConfigResp>` into `block`.
+    ///
+    /// On BUFFER_TOO_SMALL the error data is the size `block` needs.
+    pub fn config_to_block(&self, response: &str, block: &mut [u8]) -> Result<(), usize> {
+        let response = to_cstring(response).map_err(|err| Error::new(err.status(), 0))?;
+        let mut block_size = block.len();
+        let mut progress = ptr::null();
+
+        let status = unsafe {
+            (self.config_to_block)(
+                self,
+                response.as_ptr(),
+                block.as_mut_ptr(),
+                &mut block_size,
+                &mut progress,
+            )
+        };
+        match status {
+            Status::SUCCESS => Ok(()),
+            Status::BUFFER_TOO_SMALL => Err(Error::new(status, block_size)),
+            _ => Err(Error::new(status, progress_offset(&response, progress))),
+        }
+    }
+
+    /// `<ConfigResp>` of the ALTCFG section `alt_cfg_id` (a DefaultId) of a `<MultiConfigResp>`,
+    /// the section is selected by the storage GUID, name and device path.
+    pub fn get_alt_config(
+        &self,
+        response: &str,
+        guid: Option<&Guid>,
+        name: Option<&str>,
+        device_path: Option<&DevicePath>,
+        alt_cfg_id: Option<u16>,
+    ) -> Result<String> {
+        let response = to_cstring(response)?;
+        let name = name.map(to_cstring).transpose()?;
+        let mut alt_cfg_resp = ptr::null_mut();
+
+        let status = unsafe {
+            (self.get_alt_config)(
+                self,
+                response.as_ptr(),
+                guid.map_or(ptr::null(), |guid| guid as *const Guid),
+                name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+                device_path.map_or(ptr::null(), |device_path| device_path.as_ffi_ptr()),
+                alt_cfg_id
+                    .as_ref()
+                    .map_or(ptr::null(), |alt_cfg_id| alt_cfg_id as *const u16),
+                &mut alt_cfg_resp,
+            )
+        };
+        let alt_cfg_resp = unsafe { take_string(alt_cfg_resp) };
+        status.to_result()?;
+        alt_cfg_resp.ok_or(Status::NOT_FOUND.into())
+    }
+}
+
+pub(super) fn to_cstring(text: &str) -> Result<CString16> {
+    CString16::try_from(text).map_err(|_| Status::INVALID_PARAMETER.into())
+}
+
+/// Copy a string allocated by the protocol and free it
+unsafe fn take_string(ptr: *mut Char16) -> Option<String> {
+    let ptr = NonNull::new(ptr)?;
+    let text = unsafe { CStr16::from_ptr(ptr.as_ptr()) }.to_string();
+    let _ = unsafe { boot::free_pool(ptr.cast()) };
+    Some(text)
+}
+
+/// Offset in characters of `progress` inside `text`
+fn progress_offset(text: &CStr16, progress: *const Char16) -> usize {
+    let start = text.as_ptr() as usize;
+    let offset = (progress as usize).wrapping_sub(start) / 2;
+    match offset <= text.num_chars() {
+        true => offset,
+        false => 0,
+    }
+}
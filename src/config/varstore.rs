@@ -0,0 +1,174 @@
+use uefi::boot;
+use uefi::proto::device_path::text::DevicePathFromText;
+use uefi::runtime::{self, VariableAttributes, VariableVendor};
+use uefi::{Error, Result, Status};
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::*;
+use crate::ifr::{
+    FormSet, IfrTypeValue, Question, ValueError, VarStore, VarStoreChange, VarStoreContents,
+    VarStoreKind,
+};
+
+/// Contents of the UEFI variable backing an EFI varstore (EFI_IFR_VARSTORE_EFI).
+///
+/// Other kinds of varstores fail with UNSUPPORTED.
+pub fn read_efi_varstore(varstore: &VarStore) -> Result<Vec<u8>> {
+    let VarStoreKind::Efi { .. } = varstore.kind else {
+        return Err(Status::UNSUPPORTED.into());
+    };
+    let name = to_cstring(&varstore.name)?;
+    let (data, _) = runtime::get_variable_boxed(&name, &VariableVendor(varstore.guid))?;
+    Ok(data.into_vec())
+}
+
+/// Contents of the EFI varstores of a formset keyed by VarStoreId, see
+/// [`FormSet::question_values`].
+///
+/// Varstores whose variable cannot be read are left out.
+pub fn read_efi_varstores(form_set: &FormSet) -> BTreeMap<u16, VarStoreContents> {
+    form_set
+        .varstores
+        .iter()
+        .filter_map(|varstore| {
+            let contents = read_efi_varstore(varstore).ok()?;
+            Some((varstore.id, VarStoreContents::Buffer(contents)))
+        })
+        .collect()
+}
+
+/// Write the UEFI variable backing an EFI varstore with the attributes it declares
+pub fn write_efi_varstore(varstore: &VarStore, data: &[u8]) -> Result {
+    let VarStoreKind::Efi { attributes } = varstore.kind else {
+        return Err(Status::UNSUPPORTED.into());
+    };
+    let name = to_cstring(&varstore.name)?;
+    runtime::set_variable(
+        &name,
+        &VariableVendor(varstore.guid),
+        VariableAttributes::from_bits_retain(attributes),
+        data,
+    )
+}
+
+/// Set the value of a question and return the change of its varstore, nothing
+/// is written with `dry_run`.
+///
+/// EFI varstores are written to their UEFI variable, other buffer and
+/// name/value varstores are routed through `routing`, on the device of the
+/// question for questions in an EFI_IFR_VARSTORE_DEVICE scope. Values rejected
+/// by the question fail with INVALID_PARAMETER and the [`ValueError`] as error
+/// data.
+pub fn set_question_value(
+    form_set: &FormSet,
+    question: &Question,
+    value: &IfrTypeValue,
+    routing: Option<&HiiConfigRoutingProtocol>,
+    dry_run: bool,
+) -> Result<VarStoreChange, Option<ValueError>> {
+    let Some(varstore) = question.varstore.and_then(|id| form_set.varstore(id)) else {
+        let no_storage = ValueError::NoStorage {
+            question_id: question.id,
+        };
+        return Err(Error::new(Status::INVALID_PARAMETER, Some(no_storage)));
+    };
+    let rejected = |error| Error::new(Status::INVALID_PARAMETER, Some(error));
+    let failed = |err: Error| Error::new(err.status(), None);
+
+    if let VarStoreKind::Efi { .. } = varstore.kind {
+        let contents = VarStoreContents::Buffer(read_efi_varstore(varstore).map_err(failed)?);
+        let change = question.change_value(&contents, value).map_err(rejected)?;
+        if let (false, VarStoreContents::Buffer(new)) = (dry_run, &change.new) {
+            write_efi_varstore(varstore, new).map_err(failed)?;
+        }
+        return Ok(change);
+    }
+
+    let Some(routing) = routing else {
+        return Err(Error::new(Status::UNSUPPORTED, None));
+    };
+    let device = match question
+        .device
+        .as_ref()
+        .and_then(|device| device.text.as_deref())
+    {
+        Some(text) => Some(device_path_bytes(text).map_err(failed)?),
+        None => None,
+    };
+    let (header, contents) =
+        routed_contents(routing, varstore, device.as_deref()).map_err(failed)?;
+    let change = question.change_value(&contents, value).map_err(rejected)?;
+    if !dry_run {
+        let mut response = ConfigResp::new(header);
+        match &change.new {
+            VarStoreContents::Buffer(new) => {
+                let start = question.byte_offset().unwrap_or_default();
+                response.block(start, &new[start..start + question.width])
+            }
+            VarStoreContents::NameValue(new) => {
+                let name = question.storage_name().unwrap_or_default();
+                response.name_value(name, &new[name])
+            }
+        };
+        routing
+            .route_config(&response.to_string())
+            .map_err(|err| Error::new(err.status(), None))?;
+    }
+    Ok(change)
+}
+
+/// Routing header and current contents of a buffer or name/value varstore,
+/// found in the configuration exported by Config Routing.
+///
+/// With `device` only the storage of that device path matches.
+fn routed_contents(
+    routing: &HiiConfigRoutingProtocol,
+    varstore: &VarStore,
+    device: Option<&[u8]>,
+) -> Result<(ConfigHdr, VarStoreContents)> {
+    let export = routing.export_config()?;
+    let responses =
+        ConfigResp::parse_multi(&export).map_err(|_| Error::from(Status::INVALID_PARAMETER))?;
+    let response = responses
+        .into_iter()
+        .find(|response| {
+            response.header.guid == varstore.guid
+                && response.header.name == varstore.name
+                && device.is_none_or(|path| response.header.path == path)
+        })
+        .ok_or(Error::from(Status::NOT_FOUND))?;
+
+    if varstore.kind == VarStoreKind::NameValue {
+        let values = response
+            .elements
+            .into_iter()
+            .filter_map(|element| match element {
+                ConfigElement::NameValue { name, value } => Some((name, value)),
+                ConfigElement::Block { .. } => None,
+            })
+            .collect();
+        return Ok((response.header, VarStoreContents::NameValue(values)));
+    }
+
+    let mut contents = vec![0u8; varstore.size as usize];
+    // Elements past the declared size are not addressed by any question
+    match response.config_to_block(&mut contents) {
+        Ok(_) | Err(BlockConfigError::BufferTooSmall { .. }) => {
+            Ok((response.header, VarStoreContents::Buffer(contents)))
+        }
+        Err(error) => Err(error.status().into()),
+    }
+}
+
+/// Device path bytes of the text of an EFI_IFR_VARSTORE_DEVICE
+fn device_path_bytes(text: &str) -> Result<Vec<u8>> {
+    let handle = boot::get_handle_for_protocol::<DevicePathFromText>()?;
+    let from_text = boot::open_protocol_exclusive::<DevicePathFromText>(handle)?;
+    let path = from_text.convert_text_to_device_path(&to_cstring(text)?)?;
+    Ok(path.as_bytes().to_vec())
+}
@@ -0,0 +1,509 @@
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use uefi::{Guid, guid};
+
+use super::*;
+use crate::package_header::{HiiFormPackageHdr, HiiPackage};
+
+/// EFI_HII_PLATFORM_SETUP_FORMSET_GUID, form sets shown by the setup browser
+pub const EFI_HII_PLATFORM_SETUP_FORMSET_GUID: Guid = guid!("93039971-8545-4b04-b45e-32eb8326040e");
+
+/// Builder of a form set, emitting the IFR opcodes of a form package.
+///
+/// String arguments are string ids of the string package shipped next to the
+/// form package; questions, forms and varstores are referenced by their ids.
+///
+/// # Example usage:
+/// ```
+/// use hii_database::ifr::*;
+/// use uefi::guid;
+///
+/// let form_set = FormSetBuilder::new(guid!("32c66610-94df-4d6d-984f-8cbe44519b87"), 0x02, 0x03)
+///     .varstore_efi(0x0B, guid!("3a3aab90-7886-4f2e-88f8-597a951b78bc"), "SystemAccess", 0x07, 3)
+///     .form(0x01, 0x04, |f| {
+///         f.one_of(
+///             IfrQuestionHeader::new(0x01, 0x05, 0x06).with_storage(0x0B, 0),
+///             EFI_IFR_NUMERIC_SIZE_1,
+///             |f| f.option(0x08, IfrTypeValue::U8(0), 0).option(0x07, IfrTypeValue::U8(1), EFI_IFR_OPTION_DEFAULT),
+///         )
+///         .suppress_if(q(0x01).eq(0), |f| f.subtitle(0x04))
+///     })
+///     .build_package()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FormSetBuilder {
+    form_set: IfrFormSet,
+    body: FormBuilder,
+}
+
+impl FormSetBuilder {
+    pub fn new(guid: Guid, title: u16, help: u16) -> Self {
+        Self {
+            form_set: IfrFormSet {
+                guid,
+                form_set_title: title,
+                help,
+                flags: 0,
+                class_guid: Vec::new(),
+            },
+            body: FormBuilder::new(),
+        }
+    }
+
+    /// Add a class GUID, at most three are encoded. Without one the form set is
+    /// built with [`EFI_HII_PLATFORM_SETUP_FORMSET_GUID`]
+    pub fn class_guid(mut self, guid: Guid) -> Self {
+        self.form_set.class_guid.push(guid);
+        self
+    }
+
+    pub fn default_store(mut self, default_id: u16, name: u16) -> Self {
+        self.body.op(IfrOp::DefaultStore {
+            default_name: name,
+            default_id,
+        });
+        self
+    }
+
+    /// Buffer varstore (EFI_IFR_VARSTORE) of `size` bytes
+    pub fn varstore(mut self, var_store_id: u16, guid: Guid, name: &str, size: u16) -> Self {
+        self.body.op(IfrOp::VarStore(IfrVarStore {
+            guid,
+            var_store_id,
+            size,
+            name: String::from(name),
+        }));
+        self
+    }
+
+    /// UEFI variable varstore (EFI_IFR_VARSTORE_EFI) of `size` bytes
+    pub fn varstore_efi(
+        mut self,
+        var_store_id: u16,
+        guid: Guid,
+        name: &str,
+        attributes: u32,
+        size: u16,
+    ) -> Self {
+        self.body.op(IfrOp::VarStoreEfi(IfrVarStoreEfi {
+            var_store_id,
+            guid,
+            attributes,
+            size,
+            name: String::from(name),
+        }));
+        self
+    }
+
+    pub fn varstore_name_value(mut self, var_store_id: u16, guid: Guid) -> Self {
+        self.body.op(IfrOp::VarStoreNameValue(IfrVarStoreNameValue {
+            var_store_id,
+            guid,
+        }));
+        self
+    }
+
+    pub fn form<F>(mut self, form_id: u16, title: u16, body: F) -> Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.body.scope(
+            IfrOp::Form(IfrForm {
+                form_id,
+                form_title: title,
+            }),
+            body,
+        );
+        self
+    }
+
+    /// Opcodes of the form set, the data of a form package
+    pub fn build(mut self) -> Result<Vec<u8>, IfrError> {
+        if self.form_set.class_guid.is_empty() {
+            self.form_set
+                .class_guid
+                .push(EFI_HII_PLATFORM_SETUP_FORMSET_GUID);
+        }
+        let mut ops = Vec::with_capacity(self.body.ops.len() + 2);
+        ops.push((IfrOp::FormSet(self.form_set), true));
+        ops.extend(self.body.ops);
+        ops.push((IfrOp::End, false));
+        encode(&ops)
+    }
+
+    /// Form package ready to be added to a package list
+    pub fn build_package(self) -> Result<HiiPackage, IfrError> {
+        Ok(HiiFormPackageHdr::create(&self.build()?))
+    }
+}
+
+/// Opcodes of a scope: a form, a question or a condition.
+#[derive(Debug, Clone, Default)]
+pub struct FormBuilder {
+    ops: Vec<(IfrOp, bool)>,
+}
+
+impl FormBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an opcode without scope
+    pub fn op(&mut self, op: IfrOp) -> &mut Self {
+        self.ops.push((op, false));
+        self
+    }
+
+    /// Append an opcode opening a scope, `body` fills the scope before EFI_IFR_END
+    pub fn scope<F>(&mut self, op: IfrOp, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.ops.push((op, true));
+        body(self);
+        self.ops.push((IfrOp::End, false));
+        self
+    }
+
+    /// Append a postfix expression, scoped opcodes must be closed by [`IfrOp::End`]
+    pub fn expression(&mut self, expression: impl AsRef<[IfrOp]>) -> &mut Self {
+        for op in expression.as_ref() {
+            self.ops.push((op.clone(), matches!(op, IfrOp::Map)));
+        }
+        self
+    }
+
+    /// Opcodes added so far, e.g. for [`update_form`]
+    pub fn build(&self) -> Result<Vec<u8>, IfrError> {
+        encode(&self.ops)
+    }
+
+    pub fn subtitle(&mut self, text: u16) -> &mut Self {
+        self.op(IfrOp::Subtitle {
+            statement: IfrStatementHeader {
+                prompt: text,
+                help: 0,
+            },
+            flags: 0,
+        })
+    }
+
+    pub fn text(&mut self, prompt: u16, help: u16, text_two: u16) -> &mut Self {
+        self.op(IfrOp::Text {
+            statement: IfrStatementHeader { prompt, help },
+            text_two,
+        })
+    }
+
+    /// Question opcode with `body` nested in its scope (options, defaults, conditions)
+    pub fn question<F>(&mut self, op: IfrOp, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.scope(op, body)
+    }
+
+    /// Append an opcode, its scope is only opened if `body` adds opcodes
+    fn optional_scope<F>(&mut self, op: IfrOp, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        let start = self.ops.len();
+        self.ops.push((op, false));
+        body(self);
+        if self.ops.len() > start + 1 {
+            self.ops[start].1 = true;
+            self.ops.push((IfrOp::End, false));
+        }
+        self
+    }
+
+    /// Checkbox, `flags` are EFI_IFR_CHECKBOX_DEFAULT(_MFG), `body` adds defaults
+    /// and conditions
+    pub fn checkbox<F>(&mut self, question: IfrQuestionHeader, flags: u8, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.optional_scope(
+            IfrOp::Checkbox(IfrFlaggedQuestion { question, flags }),
+            body,
+        )
+    }
+
+    /// Numeric, `flags` select the size and display format, `body` adds defaults
+    /// and conditions
+    pub fn numeric<F>(
+        &mut self,
+        question: IfrQuestionHeader,
+        flags: u8,
+        min: u64,
+        max: u64,
+        step: u64,
+        body: F,
+    ) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        let op = IfrOp::Numeric(IfrNumeric {
+            question,
+            flags,
+            data: IfrMinMaxStep {
+                min_value: min,
+                max_value: max,
+                step,
+            },
+            data_width: None,
+        });
+        self.optional_scope(op, body)
+    }
+
+    /// One-of, `options` adds the options with [`FormBuilder::option`];
+    /// MINMAXSTEP spans the numeric option values
+    pub fn one_of<F>(&mut self, question: IfrQuestionHeader, flags: u8, options: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        let start = self.ops.len();
+        let op = IfrOp::OneOf(IfrNumeric {
+            question,
+            flags,
+            data: IfrMinMaxStep::default(),
+            data_width: None,
+        });
+        self.scope(op, options);
+
+        // Only the options of this one-of, not those of nested scopes
+        let mut depth = 0;
+        let values: Vec<u64> = self.ops[start + 1..]
+            .iter()
+            .filter_map(|(op, scope)| {
+                let value = match op {
+                    IfrOp::OneOfOption(option) if depth == 0 => option.value.as_u64(),
+                    IfrOp::End => {
+                        depth -= 1;
+                        None
+                    }
+                    _ => None,
+                };
+                if *scope {
+                    depth += 1;
+                }
+                value
+            })
+            .collect();
+        if let (Some(min), Some(max)) = (values.iter().min(), values.iter().max())
+            && let IfrOp::OneOf(numeric) = &mut self.ops[start].0
+        {
+            numeric.data.min_value = *min;
+            numeric.data.max_value = *max;
+        }
+        self
+    }
+
+    pub fn option(&mut self, text: u16, value: IfrTypeValue, flags: u8) -> &mut Self {
+        self.op(IfrOp::OneOfOption(IfrOneOfOption {
+            option: text,
+            flags,
+            value,
+        }))
+    }
+
+    pub fn string(&mut self, question: IfrQuestionHeader, min_size: u8, max_size: u8) -> &mut Self {
+        self.op(IfrOp::String(IfrString {
+            question,
+            min_size,
+            max_size,
+            flags: 0,
+        }))
+    }
+
+    pub fn password(
+        &mut self,
+        question: IfrQuestionHeader,
+        min_size: u16,
+        max_size: u16,
+    ) -> &mut Self {
+        self.op(IfrOp::Password(IfrPassword {
+            question,
+            min_size,
+            max_size,
+        }))
+    }
+
+    pub fn action(&mut self, question: IfrQuestionHeader, config: Option<u16>) -> &mut Self {
+        self.op(IfrOp::Action(IfrAction {
+            question,
+            question_config: config,
+        }))
+    }
+
+    /// Reference (EFI_IFR_REF) to another form of the form set
+    pub fn goto(&mut self, question: IfrQuestionHeader, form_id: u16) -> &mut Self {
+        self.op(IfrOp::Ref(IfrRef {
+            question,
+            form_id: Some(form_id),
+            question_id: None,
+            form_set_id: None,
+            device_path: None,
+        }))
+    }
+
+    /// Default value of the enclosing question for `default_id`
+    pub fn default_value(&mut self, default_id: u16, value: IfrTypeValue) -> &mut Self {
+        self.op(IfrOp::Default(IfrDefault { default_id, value }))
+    }
+
+    /// EDK2 label (EFI_IFR_GUID_LABEL) marking an insertion point for dynamic opcodes
+    pub fn label(&mut self, number: u16) -> &mut Self {
+        // Built-in extensions always encode
+        let op = IfrGuidExtension::Label { number }.to_op().unwrap();
+        self.op(op)
+    }
+
+    pub fn locked(&mut self) -> &mut Self {
+        self.op(IfrOp::Locked)
+    }
+
+    pub fn suppress_if<F>(&mut self, expression: impl AsRef<[IfrOp]>, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.condition(IfrOp::SuppressIf, expression, body)
+    }
+
+    pub fn gray_out_if<F>(&mut self, expression: impl AsRef<[IfrOp]>, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.condition(IfrOp::GrayOutIf, expression, body)
+    }
+
+    pub fn disable_if<F>(&mut self, expression: impl AsRef<[IfrOp]>, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.condition(IfrOp::DisableIf, expression, body)
+    }
+
+    /// Error `error` is shown while `expression` holds, inside a question scope
+    pub fn inconsistent_if(&mut self, error: u16, expression: impl AsRef<[IfrOp]>) -> &mut Self {
+        self.condition(IfrOp::InconsistentIf { error }, expression, |f| f)
+    }
+
+    /// Submitting is refused with `error` while `expression` holds, inside a question scope
+    pub fn no_submit_if(&mut self, error: u16, expression: impl AsRef<[IfrOp]>) -> &mut Self {
+        self.condition(IfrOp::NoSubmitIf { error }, expression, |f| f)
+    }
+
+    /// Rule referenced from expressions by [`IfrOp::RuleRef`]
+    pub fn rule(&mut self, rule_id: u8, expression: impl AsRef<[IfrOp]>) -> &mut Self {
+        self.condition(IfrOp::Rule { rule_id }, expression, |f| f)
+    }
+
+    fn condition<F>(&mut self, op: IfrOp, expression: impl AsRef<[IfrOp]>, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        self.scope(op, |f| body(f.expression(expression)))
+    }
+}
+
+fn encode(ops: &[(IfrOp, bool)]) -> Result<Vec<u8>, IfrError> {
+    let mut data = Vec::new();
+    for (op, scope) in ops {
+        let bytes = op
+            .encode(*scope)
+            .map_err(|err| err.with_offset(data.len()))?;
+        data.extend_from_slice(&bytes);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_header::ifr_parse::EfiIfrOpCode;
+
+    fn ops(data: &[u8]) -> Vec<(IfrOp, bool)> {
+        IfrOpIter::new(data)
+            .map(|entry| entry.map(|entry| (entry.op, entry.scope)).unwrap())
+            .collect()
+    }
+
+    fn question(question_id: u16) -> IfrQuestionHeader {
+        IfrQuestionHeader::new(question_id, 0x10, 0x11).with_storage(1, question_id)
+    }
+
+    #[test]
+    fn question_scope_only_with_children() {
+        let data = FormBuilder::new()
+            .checkbox(question(1), 0, |f| f)
+            .checkbox(question(2), 0, |f| {
+                f.default_value(0, IfrTypeValue::Boolean(true))
+            })
+            .numeric(question(3), EFI_IFR_NUMERIC_SIZE_1, 0, 10, 1, |f| f)
+            .build()
+            .unwrap();
+        let scopes: Vec<_> = ops(&data)
+            .into_iter()
+            .map(|(op, scope)| (op.op_code(), scope))
+            .collect();
+        assert_eq!(
+            scopes,
+            [
+                (EfiIfrOpCode::CheckboxOp, false),
+                (EfiIfrOpCode::CheckboxOp, true),
+                (EfiIfrOpCode::DefaultOp, false),
+                (EfiIfrOpCode::EndOp, false),
+                (EfiIfrOpCode::NumericOp, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_of_spans_option_values() {
+        let data = FormBuilder::new()
+            .one_of(question(1), EFI_IFR_NUMERIC_SIZE_2, |f| {
+                f.option(0x20, IfrTypeValue::U16(5), 0)
+                    .option(0x21, IfrTypeValue::U16(2), 0)
+                    .suppress_if([IfrOp::True], |f| f.option(0x22, IfrTypeValue::U16(9), 0))
+            })
+            .build()
+            .unwrap();
+        let Some((IfrOp::OneOf(one_of), true)) = ops(&data).into_iter().next() else {
+            panic!("not a one of");
+        };
+        // Options in nested conditions do not widen the range
+        assert_eq!(
+            one_of.data,
+            IfrMinMaxStep {
+                min_value: 2,
+                max_value: 5,
+                step: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn platform_setup_class_by_default() {
+        let guid = EFI_HII_PLATFORM_SETUP_FORMSET_GUID;
+        let data = FormSetBuilder::new(Guid::ZERO, 0x02, 0x03).build().unwrap();
+        let Some((IfrOp::FormSet(form_set), true)) = ops(&data).into_iter().next() else {
+            panic!("not a form set");
+        };
+        assert_eq!(form_set.class_guid, [guid]);
+
+        let other = guid!("ce4f5b0c-dc00-4a32-97ed-2966981c7725");
+        let data = FormSetBuilder::new(Guid::ZERO, 0x02, 0x03)
+            .class_guid(other)
+            .build()
+            .unwrap();
+        let Some((IfrOp::FormSet(form_set), true)) = ops(&data).into_iter().next() else {
+            panic!("not a form set");
+        };
+        assert_eq!(form_set.class_guid, [other]);
+    }
+}
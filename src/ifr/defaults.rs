@@ -0,0 +1,431 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::*;
+
+/// EFI_HII_DEFAULT_CLASS_x, DefaultId of the standard default stores
+pub const EFI_HII_DEFAULT_CLASS_STANDARD: u16 = 0x0000;
+pub const EFI_HII_DEFAULT_CLASS_MANUFACTURING: u16 = 0x0001;
+pub const EFI_HII_DEFAULT_CLASS_SAFE: u16 = 0x0002;
+
+/// Where the default of a question comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultSource {
+    /// EFI_IFR_DEFAULT with a constant value
+    Value,
+    /// EFI_IFR_DEFAULT with a DEFAULT expression
+    Expression,
+    /// EFI_IFR_OPTION_DEFAULT or EFI_IFR_OPTION_DEFAULT_MFG option flag
+    OptionFlag,
+    /// EFI_IFR_CHECKBOX_DEFAULT or EFI_IFR_CHECKBOX_DEFAULT_MFG flag
+    CheckboxFlag,
+    /// Default of another default store, `default_id` has none
+    DefaultStore(u16),
+    /// Value the form browser falls back to: FALSE, the minimum, the first
+    /// option or the option sequence
+    Fallback,
+}
+
+impl Display for DefaultSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultSource::Value => write!(f, "default"),
+            DefaultSource::Expression => write!(f, "default expression"),
+            DefaultSource::OptionFlag => write!(f, "option flag"),
+            DefaultSource::CheckboxFlag => write!(f, "checkbox flag"),
+            DefaultSource::DefaultStore(id) => write!(f, "default store 0x{:04X}", id),
+            DefaultSource::Fallback => write!(f, "fallback"),
+        }
+    }
+}
+
+/// Default value of a question for a default store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedDefault<'a> {
+    pub question: &'a Question,
+    pub value: IfrTypeValue,
+    pub source: DefaultSource,
+}
+
+impl ComputedDefault<'_> {
+    /// Whether the varstore `contents` hold a value other than the default
+    pub fn differs_from(&self, contents: &VarStoreContents) -> bool {
+        let mut image = contents.clone();
+        match self.question.encode_value(&mut image, &self.value) {
+            Ok(()) => image != *contents,
+            Err(_) => true,
+        }
+    }
+}
+
+impl Display for ComputedDefault<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.question.format_value(&self.value),
+            self.source
+        )
+    }
+}
+
+impl FormSet {
+    /// Default of a question for `default_id` computed like the form browser:
+    /// EFI_IFR_DEFAULT, option flags, checkbox flags, the defaults of the other
+    /// default stores in ascending order, then the fallback value.
+    ///
+    /// DEFAULT expressions are evaluated with `values`.
+    pub fn question_default(
+        &self,
+        question: &Question,
+        default_id: u16,
+        values: &QuestionValues,
+    ) -> Option<(IfrTypeValue, DefaultSource)> {
+        if let Some(default) = self.own_default(question, default_id, values) {
+            return Some(default);
+        }
+
+        let mut default_ids: Vec<u16> = self.default_stores.iter().map(|store| store.id).collect();
+        default_ids.sort_unstable();
+        for id in default_ids.into_iter().filter(|id| *id != default_id) {
+            if let Some((value, _)) = self.own_default(question, id, values) {
+                return Some((value, DefaultSource::DefaultStore(id)));
+            }
+        }
+
+        self.fallback_default(question, values)
+            .map(|value| (value, DefaultSource::Fallback))
+    }
+
+    /// Defaults of every question with storage for `default_id`
+    pub fn defaults(&self, default_id: u16, values: &QuestionValues) -> Vec<ComputedDefault<'_>> {
+        self.questions()
+            .filter(|question| question.varstore.is_some())
+            .filter_map(|question| {
+                let (value, source) = self.question_default(question, default_id, values)?;
+                Some(ComputedDefault {
+                    question,
+                    value,
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Contents of a varstore holding the defaults of `default_id`, bytes no
+    /// question stores a default in are zero and names without a default are
+    /// left out
+    pub fn default_image(
+        &self,
+        var_store_id: u16,
+        default_id: u16,
+        values: &QuestionValues,
+    ) -> Option<VarStoreContents> {
+        let varstore = self.varstore(var_store_id)?;
+        let mut contents = match varstore.kind {
+            VarStoreKind::NameValue => VarStoreContents::NameValue(BTreeMap::new()),
+            _ => VarStoreContents::Buffer(vec![0u8; varstore.size as usize]),
+        };
+        for default in self.defaults(default_id, values) {
+            if default.question.varstore == Some(var_store_id) {
+                // Defaults are stored as declared, even outside the range of the question
+                let _ = default.question.encode_value(&mut contents, &default.value);
+            }
+        }
+        Some(contents)
+    }
+
+    /// Default given by the question itself for exactly `default_id`
+    fn own_default(
+        &self,
+        question: &Question,
+        default_id: u16,
+        values: &QuestionValues,
+    ) -> Option<(IfrTypeValue, DefaultSource)> {
+        for default in &question.defaults {
+            if default.default_id != default_id {
+                continue;
+            }
+            match &default.value {
+                DefaultValue::Value(value) => return Some((value.clone(), DefaultSource::Value)),
+                DefaultValue::Expression(expression) => {
+                    let rules = self.form_rules(question);
+                    let value = Evaluator::new(values)
+                        .with_rules(rules)
+                        .with_this(question.id)
+                        .eval(expression)
+                        .ok()?;
+                    return Some((question.type_value(&value)?, DefaultSource::Expression));
+                }
+            }
+        }
+
+        // Options and checkboxes only give standard and manufacturing defaults
+        let flag = match default_id {
+            EFI_HII_DEFAULT_CLASS_STANDARD => (EFI_IFR_OPTION_DEFAULT, EFI_IFR_CHECKBOX_DEFAULT),
+            EFI_HII_DEFAULT_CLASS_MANUFACTURING => {
+                (EFI_IFR_OPTION_DEFAULT_MFG, EFI_IFR_CHECKBOX_DEFAULT_MFG)
+            }
+            _ => return None,
+        };
+        match question.kind {
+            QuestionKind::OneOf => question
+                .options
+                .iter()
+                .find(|option| option.flags & flag.0 != 0)
+                .map(|option| (option.value.clone(), DefaultSource::OptionFlag)),
+            // Without the flag the other default stores or the fallback FALSE apply
+            QuestionKind::Checkbox if question.op_flags & flag.1 != 0 => {
+                Some((IfrTypeValue::Boolean(true), DefaultSource::CheckboxFlag))
+            }
+            _ => None,
+        }
+    }
+
+    /// Value used by the form browser when no default store gives a default
+    fn fallback_default(
+        &self,
+        question: &Question,
+        values: &QuestionValues,
+    ) -> Option<IfrTypeValue> {
+        let shown = |option: &&QuestionOption| {
+            !option.conditions.iter().any(|condition| {
+                condition.kind == ConditionKind::SuppressIf
+                    && Evaluator::new(values)
+                        .eval_bool(&condition.expression)
+                        .unwrap_or(false)
+            })
+        };
+
+        match question.kind {
+            QuestionKind::Checkbox => Some(IfrTypeValue::Boolean(false)),
+            // Zero if it is in range, the minimum otherwise
+            QuestionKind::Numeric => {
+                let zero = question.number_value(0)?;
+                match question.validate_value(&zero) {
+                    Ok(()) => Some(zero),
+                    Err(_) => question.number_value(question.min),
+                }
+            }
+            QuestionKind::OneOf => question
+                .options
+                .iter()
+                .find(shown)
+                .map(|option| option.value.clone()),
+            // Options in the order of the form
+            QuestionKind::OrderedList => {
+                let mut buffer: Vec<u8> = question
+                    .options
+                    .iter()
+                    .filter(shown)
+                    .take(question.max as usize)
+                    .flat_map(|option| option.value.to_bytes())
+                    .collect();
+                buffer.resize(question.width, 0);
+                Some(IfrTypeValue::Buffer(buffer))
+            }
+            _ => None,
+        }
+    }
+
+    /// Rules of the form holding `question`
+    fn form_rules(&self, question: &Question) -> &[Rule] {
+        self.forms
+            .iter()
+            .find(|form| form.questions().any(|other| core::ptr::eq(other, question)))
+            .map_or(&[], |form| &form.rules)
+    }
+}
+
+impl Question {
+    /// Value of an expression result in the type of the question
+    fn type_value(&self, value: &ExprValue) -> Option<IfrTypeValue> {
+        match (&self.kind, value) {
+            (QuestionKind::Checkbox, value) => Some(IfrTypeValue::Boolean(value.as_uint()? != 0)),
+            (QuestionKind::OneOf | QuestionKind::Numeric, value) => {
+                self.number_value(value.as_uint()?)
+            }
+            (_, ExprValue::Date { year, month, day }) => Some(IfrTypeValue::Date {
+                year: *year,
+                month: *month,
+                day: *day,
+            }),
+            (
+                _,
+                ExprValue::Time {
+                    hour,
+                    minute,
+                    second,
+                },
+            ) => Some(IfrTypeValue::Time {
+                hour: *hour,
+                minute: *minute,
+                second: *second,
+            }),
+            (QuestionKind::String | QuestionKind::Password, ExprValue::String(text)) => Some(
+                IfrTypeValue::Buffer(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            ),
+            (_, ExprValue::Buffer(buffer)) => Some(IfrTypeValue::Buffer(buffer.clone())),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_header::StringTable;
+    use uefi::{Guid, guid};
+
+    const FORM_SET: Guid = guid!("32c66610-94df-4d6d-984f-8cbe44519b87");
+    const VARSTORE: Guid = guid!("3a3aab90-7886-4f2e-88f8-597a951b78bc");
+    const SETUP: u16 = 1;
+    const PLATFORM: u16 = 0x5;
+
+    fn header(question_id: u16, offset: u16) -> IfrQuestionHeader {
+        IfrQuestionHeader::new(question_id, 0x10, 0x11).with_storage(SETUP, offset)
+    }
+
+    /// Default stores declared out of their DefaultId order
+    fn form_set() -> FormSet {
+        let data = FormSetBuilder::new(FORM_SET, 0x02, 0x03)
+            .default_store(PLATFORM, 0x05)
+            .default_store(EFI_HII_DEFAULT_CLASS_SAFE, 0x06)
+            .default_store(EFI_HII_DEFAULT_CLASS_STANDARD, 0x07)
+            .default_store(EFI_HII_DEFAULT_CLASS_MANUFACTURING, 0x08)
+            .varstore(SETUP, VARSTORE, "Setup", 8)
+            .form(1, 0x04, |f| {
+                let flags = EFI_IFR_NUMERIC_SIZE_1 | EFI_IFR_DISPLAY_UINT_DEC;
+                f.numeric(header(1, 0), flags, 0, 20, 1, |f| {
+                    f.default_value(PLATFORM, IfrTypeValue::U8(9))
+                        .default_value(EFI_HII_DEFAULT_CLASS_SAFE, IfrTypeValue::U8(8))
+                });
+                let default = IfrOp::Default(IfrDefault {
+                    default_id: EFI_HII_DEFAULT_CLASS_STANDARD,
+                    value: IfrTypeValue::Other,
+                });
+                f.numeric(header(2, 1), flags, 0, 0xFF, 1, |f| {
+                    f.scope(default, |f| {
+                        f.scope(IfrOp::Value, |f| f.expression(q(1) + 1))
+                    })
+                });
+                f.one_of(header(3, 2), EFI_IFR_NUMERIC_SIZE_1, |f| {
+                    f.option(0x30, IfrTypeValue::U8(1), 0)
+                        .option(0x31, IfrTypeValue::U8(2), EFI_IFR_OPTION_DEFAULT_MFG)
+                        .option(0x32, IfrTypeValue::U8(3), EFI_IFR_OPTION_DEFAULT)
+                });
+                f.checkbox(header(4, 3), EFI_IFR_CHECKBOX_DEFAULT_MFG, |f| f);
+                f.checkbox(header(5, 4), 0, |f| f);
+                f.numeric(header(6, 5), flags, 5, 20, 1, |f| f)
+            })
+            .build()
+            .unwrap();
+        let strings = StringTable::new(String::from("en-US"));
+        FormSet::from_tree(&IfrTree::parse(&data).unwrap(), &strings).remove(0)
+    }
+
+    fn default(
+        form_set: &FormSet,
+        question_id: u16,
+        default_id: u16,
+    ) -> Option<(IfrTypeValue, DefaultSource)> {
+        let question = form_set
+            .questions()
+            .find(|question| question.id == question_id)
+            .unwrap();
+        let values = QuestionValues::from([(1, ExprValue::Uint(10))]);
+        form_set.question_default(question, default_id, &values)
+    }
+
+    #[test]
+    fn default_value() {
+        let form_set = form_set();
+        assert_eq!(
+            default(&form_set, 1, PLATFORM),
+            Some((IfrTypeValue::U8(9), DefaultSource::Value))
+        );
+        assert_eq!(
+            default(&form_set, 2, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((IfrTypeValue::U8(11), DefaultSource::Expression))
+        );
+    }
+
+    #[test]
+    fn default_flags() {
+        let form_set = form_set();
+        assert_eq!(
+            default(&form_set, 3, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((IfrTypeValue::U8(3), DefaultSource::OptionFlag))
+        );
+        assert_eq!(
+            default(&form_set, 3, EFI_HII_DEFAULT_CLASS_MANUFACTURING),
+            Some((IfrTypeValue::U8(2), DefaultSource::OptionFlag))
+        );
+        assert_eq!(
+            default(&form_set, 4, EFI_HII_DEFAULT_CLASS_MANUFACTURING),
+            Some((IfrTypeValue::Boolean(true), DefaultSource::CheckboxFlag))
+        );
+    }
+
+    #[test]
+    fn other_default_stores_in_ascending_order() {
+        let form_set = form_set();
+        // SAFE (2) comes before PLATFORM (5) although declared after it
+        assert_eq!(
+            default(&form_set, 1, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((
+                IfrTypeValue::U8(8),
+                DefaultSource::DefaultStore(EFI_HII_DEFAULT_CLASS_SAFE)
+            ))
+        );
+        // STANDARD (0) comes before MANUFACTURING (1)
+        assert_eq!(
+            default(&form_set, 3, EFI_HII_DEFAULT_CLASS_SAFE),
+            Some((
+                IfrTypeValue::U8(3),
+                DefaultSource::DefaultStore(EFI_HII_DEFAULT_CLASS_STANDARD)
+            ))
+        );
+        // A checkbox without the standard flag takes the manufacturing one
+        assert_eq!(
+            default(&form_set, 4, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((
+                IfrTypeValue::Boolean(true),
+                DefaultSource::DefaultStore(EFI_HII_DEFAULT_CLASS_MANUFACTURING)
+            ))
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        let form_set = form_set();
+        assert_eq!(
+            default(&form_set, 5, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((IfrTypeValue::Boolean(false), DefaultSource::Fallback))
+        );
+        // Zero is below the minimum
+        assert_eq!(
+            default(&form_set, 6, EFI_HII_DEFAULT_CLASS_STANDARD),
+            Some((IfrTypeValue::U8(5), DefaultSource::Fallback))
+        );
+    }
+
+    #[test]
+    fn default_image() {
+        let form_set = form_set();
+        let values = QuestionValues::from([(1, ExprValue::Uint(10))]);
+        assert_eq!(
+            form_set.default_image(SETUP, EFI_HII_DEFAULT_CLASS_STANDARD, &values),
+            Some(VarStoreContents::Buffer(vec![8, 11, 3, 1, 0, 5, 0, 0]))
+        );
+        assert_eq!(
+            form_set.default_image(2, EFI_HII_DEFAULT_CLASS_STANDARD, &values),
+            None
+        );
+    }
+}
@@ -0,0 +1,427 @@
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::ops;
+
+use super::*;
+
+/// IFR expression built in Rust, kept as opcodes in postfix order.
+///
+/// Operators combine the opcodes of their operands, so the result can be passed
+/// to the condition methods of [`FormBuilder`] or evaluated by [`Evaluator`].
+/// Comparing a question with a constant or with another question produces the
+/// compact EFI_IFR_EQ_ID_VAL and EFI_IFR_EQ_ID_ID forms.
+///
+/// `!` is logical NOT, `&`, `|`, `+`, `-`, `*`, `/`, `%`, `<<` and `>>` are the
+/// bitwise and arithmetic opcodes.
+///
+/// # Example usage:
+/// ```
+/// use hii_database::ifr::*;
+///
+/// let hidden = q(0x1001).eq(1).and(not(q(0x1002).eq(0)));
+/// assert_eq!(
+///     hidden.ops(),
+///     &[
+///         IfrOp::EqIdVal { question_id: 0x1001, value: 1 },
+///         IfrOp::EqIdVal { question_id: 0x1002, value: 0 },
+///         IfrOp::Not,
+///         IfrOp::And,
+///     ]
+/// );
+/// assert_eq!(Expr::decode(&hidden.encode().unwrap()), Ok(hidden));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    ops: Vec<IfrOp>,
+}
+
+/// Value of question `question_id` (EFI_IFR_QUESTION_REF1)
+pub fn q(question_id: u16) -> Expr {
+    Expr::op(IfrOp::QuestionRef1 { question_id })
+}
+
+/// Logical NOT of `expr`
+pub fn not(expr: Expr) -> Expr {
+    expr.unary(IfrOp::Not)
+}
+
+/// Value of the question enclosing the expression (EFI_IFR_THIS)
+pub fn this() -> Expr {
+    Expr::op(IfrOp::This)
+}
+
+/// Result of rule `rule_id` (EFI_IFR_RULE_REF)
+pub fn rule(rule_id: u8) -> Expr {
+    Expr::op(IfrOp::RuleRef { rule_id })
+}
+
+/// String `string_id` of the form set (EFI_IFR_STRING_REF1)
+pub fn string(string_id: u16) -> Expr {
+    Expr::op(IfrOp::StringRef1 { string_id })
+}
+
+/// `then` if `condition` is TRUE, otherwise `otherwise` (EFI_IFR_CONDITIONAL)
+pub fn cond(condition: impl Into<Expr>, then: impl Into<Expr>, otherwise: impl Into<Expr>) -> Expr {
+    condition
+        .into()
+        .ternary(then, otherwise, IfrOp::Conditional)
+}
+
+impl Expr {
+    /// Expression made of a single opcode
+    pub fn op(op: IfrOp) -> Self {
+        Self { ops: vec![op] }
+    }
+
+    /// Wrap opcodes already in postfix order
+    pub fn from_ops(ops: Vec<IfrOp>) -> Self {
+        Self { ops }
+    }
+
+    pub fn ops(&self) -> &[IfrOp] {
+        &self.ops
+    }
+
+    pub fn into_ops(self) -> Vec<IfrOp> {
+        self.ops
+    }
+
+    /// Opcode bytes of the expression, EFI_IFR_MAP opens a scope closed by its EFI_IFR_END
+    pub fn encode(&self) -> Result<Vec<u8>, IfrError> {
+        let mut data = Vec::new();
+        for op in &self.ops {
+            let bytes = op
+                .encode(matches!(op, IfrOp::Map))
+                .map_err(|err| err.with_offset(data.len()))?;
+            data.extend_from_slice(&bytes);
+        }
+        Ok(data)
+    }
+
+    /// Decode opcode bytes produced by [`Expr::encode`] or found in a condition scope
+    pub fn decode(data: &[u8]) -> Result<Self, IfrError> {
+        let mut ops = Vec::new();
+        let mut scopes = Vec::new();
+
+        for entry in IfrOpIter::new(data) {
+            let entry = entry?;
+            match entry.op {
+                IfrOp::End if scopes.pop().is_none() => {
+                    return Err(IfrError::UnbalancedEnd {
+                        offset: entry.offset,
+                    });
+                }
+                _ if entry.scope => scopes.push(entry.offset),
+                _ => {}
+            }
+            ops.push(entry.op);
+        }
+
+        match scopes.pop() {
+            Some(offset) => Err(IfrError::UnclosedScope { offset }),
+            None => Ok(Self { ops }),
+        }
+    }
+
+    fn unary(mut self, op: IfrOp) -> Self {
+        self.ops.push(op);
+        self
+    }
+
+    fn binary(mut self, other: impl Into<Expr>, op: IfrOp) -> Self {
+        self.ops.extend(other.into().ops);
+        self.ops.push(op);
+        self
+    }
+
+    fn ternary(mut self, second: impl Into<Expr>, third: impl Into<Expr>, op: IfrOp) -> Self {
+        self.ops.extend(second.into().ops);
+        self.ops.extend(third.into().ops);
+        self.ops.push(op);
+        self
+    }
+
+    /// Question id if the expression is a plain question reference
+    fn question_id(&self) -> Option<u16> {
+        match self.ops[..] {
+            [IfrOp::QuestionRef1 { question_id }] => Some(question_id),
+            _ => None,
+        }
+    }
+
+    /// Constant if the expression is a plain integer that fits EFI_IFR_EQ_ID_VAL
+    fn constant(&self) -> Option<u16> {
+        match self.ops[..] {
+            [IfrOp::Uint8(value)] => Some(value as u16),
+            [IfrOp::Uint16(value)] => Some(value),
+            [IfrOp::Zero] => Some(0),
+            [IfrOp::One] => Some(1),
+            _ => None,
+        }
+    }
+
+    pub fn eq(self, other: impl Into<Expr>) -> Self {
+        let other = other.into();
+        match (self.question_id(), other.question_id(), other.constant()) {
+            (Some(question_id), _, Some(value)) => Expr::op(IfrOp::EqIdVal { question_id, value }),
+            (Some(question_id_1), Some(question_id_2), _) => Expr::op(IfrOp::EqIdId {
+                question_id_1,
+                question_id_2,
+            }),
+            _ => self.binary(other, IfrOp::Equal),
+        }
+    }
+
+    pub fn ne(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::NotEqual)
+    }
+
+    pub fn gt(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::GreaterThan)
+    }
+
+    pub fn ge(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::GreaterEqual)
+    }
+
+    pub fn lt(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::LessThan)
+    }
+
+    pub fn le(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::LessEqual)
+    }
+
+    pub fn and(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::And)
+    }
+
+    pub fn or(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::Or)
+    }
+
+    /// TRUE if the value is one of `values`; EFI_IFR_EQ_ID_VAL_LIST for questions
+    pub fn is_in(self, values: &[u16]) -> Self {
+        if let Some(question_id) = self.question_id() {
+            return Expr::op(IfrOp::EqIdValList {
+                question_id,
+                value_list: values.to_vec(),
+            });
+        }
+        values
+            .iter()
+            .map(|value| self.clone().binary(*value, IfrOp::Equal))
+            .reduce(Expr::or)
+            .unwrap_or_else(|| Expr::from(false))
+    }
+
+    pub fn bit_not(self) -> Self {
+        self.unary(IfrOp::BitwiseNot)
+    }
+
+    pub fn to_boolean(self) -> Self {
+        self.unary(IfrOp::ToBoolean)
+    }
+
+    pub fn to_uint(self) -> Self {
+        self.unary(IfrOp::ToUint)
+    }
+
+    /// Convert to a string, `format` is EFI_IFR_STRING_x
+    pub fn to_string(self, format: u8) -> Self {
+        self.unary(IfrOp::ToString { format })
+    }
+
+    pub fn to_lower(self) -> Self {
+        self.unary(IfrOp::ToLower)
+    }
+
+    pub fn to_upper(self) -> Self {
+        self.unary(IfrOp::ToUpper)
+    }
+
+    pub fn length(self) -> Self {
+        self.unary(IfrOp::Length)
+    }
+
+    pub fn catenate(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, IfrOp::Catenate)
+    }
+
+    /// Substring of `length` characters at `position`
+    pub fn mid(self, position: impl Into<Expr>, length: impl Into<Expr>) -> Self {
+        self.ternary(position, length, IfrOp::Mid)
+    }
+
+    /// Index of `needle` from `start`, `format` is EFI_IFR_FF_x
+    pub fn find(self, needle: impl Into<Expr>, start: impl Into<Expr>, format: u8) -> Self {
+        self.ternary(needle, start, IfrOp::Find { format })
+    }
+
+    /// TRUE if the string matches the glob `pattern` (EFI_IFR_MATCH)
+    pub fn matches(self, pattern: impl Into<Expr>) -> Self {
+        pattern.into().binary(self, IfrOp::Match)
+    }
+
+    /// Value paired with the expression in `pairs`, UNDEFINED without a match (EFI_IFR_MAP)
+    pub fn map(mut self, pairs: &[(Expr, Expr)]) -> Self {
+        self.ops.push(IfrOp::Map);
+        for (key, value) in pairs {
+            self.ops.extend_from_slice(&key.ops);
+            self.ops.extend_from_slice(&value.ops);
+        }
+        self.ops.push(IfrOp::End);
+        self
+    }
+}
+
+impl AsRef<[IfrOp]> for Expr {
+    fn as_ref(&self) -> &[IfrOp] {
+        &self.ops
+    }
+}
+
+impl From<Expr> for Vec<IfrOp> {
+    fn from(expr: Expr) -> Self {
+        expr.ops
+    }
+}
+
+impl From<bool> for Expr {
+    fn from(value: bool) -> Self {
+        Expr::op(if value { IfrOp::True } else { IfrOp::False })
+    }
+}
+
+impl From<u8> for Expr {
+    fn from(value: u8) -> Self {
+        Expr::op(IfrOp::Uint8(value))
+    }
+}
+
+impl From<u16> for Expr {
+    fn from(value: u16) -> Self {
+        Expr::op(IfrOp::Uint16(value))
+    }
+}
+
+impl From<u32> for Expr {
+    fn from(value: u32) -> Self {
+        Expr::op(IfrOp::Uint32(value))
+    }
+}
+
+/// Smallest EFI_IFR_UINTx holding the value
+impl From<u64> for Expr {
+    fn from(value: u64) -> Self {
+        match value {
+            0..=0xFF => Expr::from(value as u8),
+            0x100..=0xFFFF => Expr::from(value as u16),
+            0x1_0000..=0xFFFF_FFFF => Expr::from(value as u32),
+            _ => Expr::op(IfrOp::Uint64(value)),
+        }
+    }
+}
+
+/// Integer literals, negative values are sign-extended to a two's complement UINT64
+impl From<i32> for Expr {
+    fn from(value: i32) -> Self {
+        Expr::from(value as i64 as u64)
+    }
+}
+
+impl ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        self.unary(IfrOp::Not)
+    }
+}
+
+macro_rules! binary_operator {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl<T: Into<Expr>> ops::$trait<T> for Expr {
+            type Output = Expr;
+
+            fn $method(self, other: T) -> Expr {
+                self.binary(other, $op)
+            }
+        }
+    };
+}
+
+binary_operator!(BitAnd, bitand, IfrOp::BitwiseAnd);
+binary_operator!(BitOr, bitor, IfrOp::BitwiseOr);
+binary_operator!(Shl, shl, IfrOp::ShiftLeft);
+binary_operator!(Shr, shr, IfrOp::ShiftRight);
+binary_operator!(Add, add, IfrOp::Add);
+binary_operator!(Sub, sub, IfrOp::Subtract);
+binary_operator!(Mul, mul, IfrOp::Multiply);
+binary_operator!(Div, div, IfrOp::Divide);
+binary_operator!(Rem, rem, IfrOp::Modulo);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_literals_sign_extend() {
+        assert_eq!(Expr::from(-1).ops(), [IfrOp::Uint64(u64::MAX)]);
+        assert_eq!(Expr::from(-2).ops(), [IfrOp::Uint64(u64::MAX - 1)]);
+        assert_eq!(Expr::from(0x1234).ops(), [IfrOp::Uint16(0x1234)]);
+
+        let values = QuestionValues::new();
+        let difference = Expr::from(3) - 4;
+        assert_eq!(
+            Evaluator::new(&values).eval(difference.ops()),
+            Evaluator::new(&values).eval(Expr::from(-1).ops())
+        );
+    }
+
+    #[test]
+    fn serialized_expression_decodes() {
+        let hidden = q(0x1001).eq(1).and(not(q(0x1002).eq(0x200)));
+        let data = hidden.encode().unwrap();
+
+        let ops: Vec<IfrOp> = IfrOpIter::new(&data)
+            .map(|entry| entry.unwrap().op)
+            .collect();
+        assert_eq!(
+            ops,
+            [
+                IfrOp::EqIdVal {
+                    question_id: 0x1001,
+                    value: 1,
+                },
+                IfrOp::EqIdVal {
+                    question_id: 0x1002,
+                    value: 0x200,
+                },
+                IfrOp::Not,
+                IfrOp::And,
+            ]
+        );
+
+        // Without scopes every opcode is a root of the tree
+        let tree = IfrTree::parse(&data).unwrap();
+        let roots: Vec<&IfrOp> = tree.roots.iter().map(IfrNode::op).collect();
+        assert_eq!(roots, hidden.ops().iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn map_scope_decodes() {
+        let mapped = q(1).map(&[
+            (Expr::from(0), Expr::from(10)),
+            (Expr::from(1), Expr::from(20)),
+        ]);
+        let data = mapped.encode().unwrap();
+        assert_eq!(Expr::decode(&data), Ok(mapped));
+
+        let tree = IfrTree::parse(&data).unwrap();
+        assert_eq!(tree.roots.len(), 2);
+        assert_eq!(tree.roots[1].op(), &IfrOp::Map);
+        assert_eq!(tree.roots[1].children.len(), 4);
+    }
+}
@@ -0,0 +1,1092 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::{IfrOp, IfrTypeValue, Rule};
+use crate::package_header::StringTable;
+use crate::package_header::ifr_parse::EfiIfrOpCode;
+
+/// EFI_IFR_STRING_UNSIGNED_DEC .. EFI_IFR_STRING_ASCII formats of TO_STRING
+pub const EFI_IFR_STRING_UNSIGNED_DEC: u8 = 0;
+pub const EFI_IFR_STRING_SIGNED_DEC: u8 = 1;
+pub const EFI_IFR_STRING_LOWERCASE_HEX: u8 = 2;
+pub const EFI_IFR_STRING_UPPERCASE_HEX: u8 = 3;
+pub const EFI_IFR_STRING_ASCII: u8 = 0;
+pub const EFI_IFR_STRING_UNICODE: u8 = 0x08;
+
+/// EFI_IFR_FF_CASE_SENSITIVE / EFI_IFR_FF_CASE_INSENSITIVE formats of FIND
+pub const EFI_IFR_FF_CASE_SENSITIVE: u8 = 0;
+pub const EFI_IFR_FF_CASE_INSENSITIVE: u8 = 1;
+
+/// EFI_IFR_FLAGS_FIRST_NON_MATCHING flag of SPAN
+pub const EFI_IFR_FLAGS_FIRST_NON_MATCHING: u8 = 0x01;
+
+/// Nesting limit of EFI_IFR_RULE_REF evaluation
+const MAX_RULE_DEPTH: usize = 32;
+
+/// Value on the expression stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprValue {
+    Undefined,
+    Boolean(bool),
+    Uint(u64),
+    String(String),
+    Buffer(Vec<u8>),
+    Date { year: u16, month: u8, day: u8 },
+    Time { hour: u8, minute: u8, second: u8 },
+}
+
+impl ExprValue {
+    /// Numeric view used by comparisons and arithmetic; booleans, dates and
+    /// times compare by their EFI_IFR_TYPE_VALUE bytes like EDK2 does
+    pub fn as_uint(&self) -> Option<u64> {
+        match self {
+            ExprValue::Boolean(v) => Some(*v as u64),
+            ExprValue::Uint(v) => Some(*v),
+            ExprValue::Date { year, month, day } => {
+                Some(*year as u64 | (*month as u64) << 16 | (*day as u64) << 24)
+            }
+            ExprValue::Time {
+                hour,
+                minute,
+                second,
+            } => Some(*hour as u64 | (*minute as u64) << 8 | (*second as u64) << 16),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ExprValue::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        *self == ExprValue::Undefined
+    }
+
+    /// Convert an option or default value, STRING_ID values are resolved through `strings`
+    pub fn from_type_value(value: &IfrTypeValue, strings: Option<&StringTable>) -> Self {
+        match value {
+            IfrTypeValue::U8(v) => ExprValue::Uint(*v as u64),
+            IfrTypeValue::U16(v) => ExprValue::Uint(*v as u64),
+            IfrTypeValue::U32(v) => ExprValue::Uint(*v as u64),
+            IfrTypeValue::U64(v) => ExprValue::Uint(*v),
+            IfrTypeValue::Boolean(v) => ExprValue::Boolean(*v),
+            IfrTypeValue::Time {
+                hour,
+                minute,
+                second,
+            } => ExprValue::Time {
+                hour: *hour,
+                minute: *minute,
+                second: *second,
+            },
+            IfrTypeValue::Date { year, month, day } => ExprValue::Date {
+                year: *year,
+                month: *month,
+                day: *day,
+            },
+            IfrTypeValue::String(id) => strings
+                .and_then(|table| table.get(*id))
+                .map_or(ExprValue::Undefined, |s| ExprValue::String(s.to_string())),
+            IfrTypeValue::Buffer(buffer) => ExprValue::Buffer(buffer.clone()),
+            _ => ExprValue::Undefined,
+        }
+    }
+}
+
+impl Display for ExprValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprValue::Undefined => write!(f, "UNDEFINED"),
+            ExprValue::Boolean(v) => write!(f, "{}", if *v { "TRUE" } else { "FALSE" }),
+            ExprValue::Uint(v) => write!(f, "{}", v),
+            ExprValue::String(s) => write!(f, "{:?}", s),
+            ExprValue::Buffer(buffer) => {
+                for (i, byte) in buffer.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+            ExprValue::Date { year, month, day } => {
+                write!(f, "{:04}/{:02}/{:02}", year, month, day)
+            }
+            ExprValue::Time {
+                hour,
+                minute,
+                second,
+            } => write!(f, "{:02}:{:02}:{:02}", hour, minute, second),
+        }
+    }
+}
+
+/// Current values of questions keyed by QuestionId.
+pub type QuestionValues = BTreeMap<u16, ExprValue>;
+
+/// Storage access for EFI_IFR_GET and EFI_IFR_SET.
+pub trait VariableResolver {
+    /// Read `var_store_info` (offset or name) of the varstore as a value of EFI_IFR_TYPE_x `var_store_type`
+    fn get(&mut self, var_store_id: u16, var_store_info: u16, var_store_type: u8) -> Option<ExprValue>;
+
+    /// Write the value, returns false if the storage could not be updated
+    fn set(
+        &mut self,
+        var_store_id: u16,
+        var_store_info: u16,
+        var_store_type: u8,
+        value: &ExprValue,
+    ) -> bool;
+}
+
+/// Errors of malformed expressions; type mismatches evaluate to [`ExprValue::Undefined`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprError {
+    /// Opcode pops more values than the stack holds
+    StackUnderflow(EfiIfrOpCode),
+    /// Opcode is not an expression opcode
+    UnexpectedOp(EfiIfrOpCode),
+    /// Scope of an expression opcode is not closed
+    UnclosedScope(EfiIfrOpCode),
+    /// EFI_IFR_RULE_REF to a rule that is not defined
+    RuleNotFound(u8),
+    /// EFI_IFR_RULE_REF nesting is too deep (recursive rules)
+    TooDeep,
+    /// Expression leaves no value on the stack
+    Empty,
+}
+
+impl Display for ExprError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::StackUnderflow(op) => write!(f, "{:?} pops from an empty stack", op),
+            ExprError::UnexpectedOp(op) => write!(f, "{:?} is not an expression opcode", op),
+            ExprError::UnclosedScope(op) => write!(f, "scope of {:?} is not closed", op),
+            ExprError::RuleNotFound(id) => write!(f, "rule {} is not defined", id),
+            ExprError::TooDeep => write!(f, "rule references are nested too deep"),
+            ExprError::Empty => write!(f, "expression has no value"),
+        }
+    }
+}
+
+/// Stack-based evaluator of IFR expressions over a question-value environment.
+///
+/// # Example usage:
+/// ```
+/// use hii_database::ifr::{Evaluator, ExprValue, IfrOp, QuestionValues};
+///
+/// let mut values = QuestionValues::new();
+/// values.insert(0x1001, ExprValue::Uint(1));
+///
+/// // suppressif ideqval 0x1001 == 1 AND NOT TRUE
+/// let expression = [
+///     IfrOp::EqIdVal { question_id: 0x1001, value: 1 },
+///     IfrOp::True,
+///     IfrOp::Not,
+///     IfrOp::And,
+/// ];
+/// let result = Evaluator::new(&values).eval(&expression);
+/// assert_eq!(result, Ok(ExprValue::Boolean(false)));
+/// ```
+pub struct Evaluator<'a> {
+    values: &'a QuestionValues,
+    strings: Option<&'a StringTable>,
+    rules: &'a [Rule],
+    resolver: Option<&'a mut dyn VariableResolver>,
+    this: Option<u16>,
+    version: u64,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(values: &'a QuestionValues) -> Self {
+        Self {
+            values,
+            strings: None,
+            rules: &[],
+            resolver: None,
+            this: None,
+            // UEFI 2.70, value of EFI_IFR_VERSION
+            version: 2 << 16 | 70,
+        }
+    }
+
+    /// Strings for EFI_IFR_STRING_REF1/2 and string values
+    pub fn with_strings(mut self, strings: &'a StringTable) -> Self {
+        self.strings = Some(strings);
+        self
+    }
+
+    /// Rules of the form for EFI_IFR_RULE_REF
+    pub fn with_rules(mut self, rules: &'a [Rule]) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Storage for EFI_IFR_GET and EFI_IFR_SET
+    pub fn with_resolver(mut self, resolver: &'a mut dyn VariableResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Question referred to by EFI_IFR_THIS
+    pub fn with_this(mut self, question_id: u16) -> Self {
+        self.this = Some(question_id);
+        self
+    }
+
+    /// Value pushed by EFI_IFR_VERSION
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Evaluate a postfix expression and return the value left on top of the stack
+    pub fn eval(&mut self, expression: &[IfrOp]) -> Result<ExprValue, ExprError> {
+        self.eval_depth(expression, 0)
+    }
+
+    /// Evaluate a condition; non-boolean results count as FALSE like in the form browser
+    pub fn eval_bool(&mut self, expression: &[IfrOp]) -> Result<bool, ExprError> {
+        Ok(self.eval(expression)?.as_bool().unwrap_or(false))
+    }
+
+    fn eval_depth(&mut self, expression: &[IfrOp], depth: usize) -> Result<ExprValue, ExprError> {
+        let mut stack = self.run(expression, depth)?;
+        stack.pop().ok_or(ExprError::Empty)
+    }
+
+    fn question(&self, question_id: u16) -> ExprValue {
+        self.values
+            .get(&question_id)
+            .cloned()
+            .unwrap_or(ExprValue::Undefined)
+    }
+
+    fn string(&self, string_id: u64) -> ExprValue {
+        u16::try_from(string_id)
+            .ok()
+            .and_then(|id| self.strings?.get(id))
+            .map_or(ExprValue::Undefined, |s| ExprValue::String(s.to_string()))
+    }
+
+    /// Run the opcodes and return the whole stack
+    fn run(&mut self, expression: &[IfrOp], depth: usize) -> Result<Vec<ExprValue>, ExprError> {
+        let mut stack: Vec<ExprValue> = Vec::new();
+        let mut pc = 0;
+
+        while pc < expression.len() {
+            let op = &expression[pc];
+            pc += 1;
+
+            macro_rules! pop {
+                () => {
+                    stack.pop().ok_or(ExprError::StackUnderflow(op.op_code()))?
+                };
+            }
+
+            let value = match op {
+                IfrOp::End | IfrOp::Value => continue,
+                IfrOp::EqIdVal { question_id, value } => {
+                    match self.question(*question_id).as_uint() {
+                        Some(current) => ExprValue::Boolean(current == *value as u64),
+                        None => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::EqIdId {
+                    question_id_1,
+                    question_id_2,
+                } => compare(&self.question(*question_id_1), &self.question(*question_id_2))
+                    .map_or(ExprValue::Undefined, |ord| ExprValue::Boolean(ord.is_eq())),
+                IfrOp::EqIdValList {
+                    question_id,
+                    value_list,
+                } => match self.question(*question_id).as_uint() {
+                    Some(current) => {
+                        ExprValue::Boolean(value_list.iter().any(|v| *v as u64 == current))
+                    }
+                    None => ExprValue::Undefined,
+                },
+                IfrOp::And | IfrOp::Or => {
+                    let right = pop!();
+                    let left = pop!();
+                    match (left.as_bool(), right.as_bool()) {
+                        (Some(l), Some(r)) if *op == IfrOp::And => ExprValue::Boolean(l && r),
+                        (Some(l), Some(r)) => ExprValue::Boolean(l || r),
+                        _ => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::Not => match pop!().as_bool() {
+                    Some(v) => ExprValue::Boolean(!v),
+                    None => ExprValue::Undefined,
+                },
+                IfrOp::Equal
+                | IfrOp::NotEqual
+                | IfrOp::GreaterThan
+                | IfrOp::GreaterEqual
+                | IfrOp::LessThan
+                | IfrOp::LessEqual => {
+                    let right = pop!();
+                    let left = pop!();
+                    match compare(&left, &right) {
+                        Some(ord) => ExprValue::Boolean(match op {
+                            IfrOp::Equal => ord.is_eq(),
+                            IfrOp::NotEqual => ord.is_ne(),
+                            IfrOp::GreaterThan => ord.is_gt(),
+                            IfrOp::GreaterEqual => ord.is_ge(),
+                            IfrOp::LessThan => ord.is_lt(),
+                            _ => ord.is_le(),
+                        }),
+                        None => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::BitwiseAnd
+                | IfrOp::BitwiseOr
+                | IfrOp::ShiftLeft
+                | IfrOp::ShiftRight
+                | IfrOp::Add
+                | IfrOp::Subtract
+                | IfrOp::Multiply
+                | IfrOp::Divide
+                | IfrOp::Modulo => {
+                    let right = pop!();
+                    let left = pop!();
+                    match (uint_operand(&left), uint_operand(&right)) {
+                        (Some(l), Some(r)) => arithmetic(op, l, r),
+                        _ => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::BitwiseNot => match uint_operand(&pop!()) {
+                    Some(v) => ExprValue::Uint(!v),
+                    None => ExprValue::Undefined,
+                },
+                IfrOp::Uint8(v) => ExprValue::Uint(*v as u64),
+                IfrOp::Uint16(v) => ExprValue::Uint(*v as u64),
+                IfrOp::Uint32(v) => ExprValue::Uint(*v as u64),
+                IfrOp::Uint64(v) => ExprValue::Uint(*v),
+                IfrOp::True => ExprValue::Boolean(true),
+                IfrOp::False => ExprValue::Boolean(false),
+                IfrOp::Zero => ExprValue::Uint(0),
+                IfrOp::One => ExprValue::Uint(1),
+                IfrOp::Ones => ExprValue::Uint(u64::MAX),
+                IfrOp::Undefined => ExprValue::Undefined,
+                IfrOp::Version => ExprValue::Uint(self.version),
+                IfrOp::Security { .. } => ExprValue::Boolean(true),
+                IfrOp::This | IfrOp::Read => match self.this {
+                    Some(id) => self.question(id),
+                    None => ExprValue::Undefined,
+                },
+                IfrOp::Write => {
+                    pop!();
+                    ExprValue::Boolean(true)
+                }
+                IfrOp::Dup => {
+                    let top = pop!();
+                    stack.push(top.clone());
+                    top
+                }
+                IfrOp::QuestionRef1 { question_id } => self.question(*question_id),
+                IfrOp::QuestionRef2 => match pop!() {
+                    ExprValue::Uint(id) => u16::try_from(id)
+                        .map_or(ExprValue::Undefined, |id| self.question(id)),
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::QuestionRef3 { device_path, guid } => match pop!() {
+                    // Questions of other formsets are not known offline
+                    ExprValue::Uint(id) if device_path.unwrap_or(0) == 0 && guid.is_none() => {
+                        u16::try_from(id).map_or(ExprValue::Undefined, |id| self.question(id))
+                    }
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::RuleRef { rule_id } => {
+                    if depth >= MAX_RULE_DEPTH {
+                        return Err(ExprError::TooDeep);
+                    }
+                    let rules = self.rules;
+                    let rule = rules
+                        .iter()
+                        .find(|rule| rule.id == *rule_id)
+                        .ok_or(ExprError::RuleNotFound(*rule_id))?;
+                    self.eval_depth(&rule.expression, depth + 1)?
+                }
+                IfrOp::StringRef1 { string_id } => self.string(*string_id as u64),
+                IfrOp::StringRef2 => match pop!() {
+                    ExprValue::Uint(id) => self.string(id),
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::Get {
+                    var_store_id,
+                    var_store_info,
+                    var_store_type,
+                } => self
+                    .resolver
+                    .as_mut()
+                    .and_then(|resolver| resolver.get(*var_store_id, *var_store_info, *var_store_type))
+                    .unwrap_or(ExprValue::Undefined),
+                IfrOp::Set {
+                    var_store_id,
+                    var_store_info,
+                    var_store_type,
+                } => {
+                    let value = pop!();
+                    let written = self.resolver.as_mut().is_some_and(|resolver| {
+                        resolver.set(*var_store_id, *var_store_info, *var_store_type, &value)
+                    });
+                    ExprValue::Boolean(written)
+                }
+                IfrOp::ToBoolean => match pop!() {
+                    ExprValue::Boolean(v) => ExprValue::Boolean(v),
+                    ExprValue::Uint(v) => ExprValue::Boolean(v != 0),
+                    ExprValue::String(s) if s.eq_ignore_ascii_case("true") => {
+                        ExprValue::Boolean(true)
+                    }
+                    ExprValue::String(s) if s.eq_ignore_ascii_case("false") => {
+                        ExprValue::Boolean(false)
+                    }
+                    ExprValue::Buffer(buffer) => ExprValue::Boolean(buffer.iter().any(|b| *b != 0)),
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::ToUint => match pop!() {
+                    ExprValue::String(s) => parse_uint(&s).map_or(ExprValue::Undefined, ExprValue::Uint),
+                    ExprValue::Buffer(buffer) if buffer.len() <= 8 => {
+                        let mut bytes = [0u8; 8];
+                        bytes[..buffer.len()].copy_from_slice(&buffer);
+                        ExprValue::Uint(u64::from_le_bytes(bytes))
+                    }
+                    value => value.as_uint().map_or(ExprValue::Undefined, ExprValue::Uint),
+                },
+                IfrOp::ToString { format } => to_string(pop!(), *format),
+                IfrOp::ToLower | IfrOp::ToUpper => match pop!() {
+                    ExprValue::String(s) if *op == IfrOp::ToLower => ExprValue::String(s.to_lowercase()),
+                    ExprValue::String(s) => ExprValue::String(s.to_uppercase()),
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::Length => match pop!() {
+                    ExprValue::String(s) => ExprValue::Uint(s.chars().count() as u64),
+                    ExprValue::Buffer(buffer) => ExprValue::Uint(buffer.len() as u64),
+                    _ => ExprValue::Undefined,
+                },
+                IfrOp::Catenate => {
+                    let right = pop!();
+                    let left = pop!();
+                    match (left, right) {
+                        (ExprValue::String(l), ExprValue::String(r)) => ExprValue::String(l + &r),
+                        (ExprValue::Buffer(mut l), ExprValue::Buffer(r)) => {
+                            l.extend_from_slice(&r);
+                            ExprValue::Buffer(l)
+                        }
+                        _ => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::Mid => {
+                    let length = pop!();
+                    let index = pop!();
+                    let source = pop!();
+                    mid(source, &index, &length)
+                }
+                IfrOp::Find { format } => {
+                    let index = pop!();
+                    let needle = pop!();
+                    let haystack = pop!();
+                    find(&haystack, &needle, &index, *format)
+                }
+                IfrOp::Token => {
+                    let index = pop!();
+                    let delimiters = pop!();
+                    let source = pop!();
+                    token(&source, &delimiters, &index)
+                }
+                IfrOp::Span { flags } => {
+                    let index = pop!();
+                    let charset = pop!();
+                    let source = pop!();
+                    span(&source, &charset, &index, *flags)
+                }
+                IfrOp::Match => {
+                    let string = pop!();
+                    let pattern = pop!();
+                    match (&string, &pattern) {
+                        (ExprValue::String(s), ExprValue::String(p)) => {
+                            ExprValue::Boolean(meta_match(s, p))
+                        }
+                        _ => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::Match2 { .. } => {
+                    let pattern = pop!();
+                    let string = pop!();
+                    match (&string, &pattern) {
+                        (ExprValue::String(s), ExprValue::String(p)) => {
+                            regex_search(s, p).map_or(ExprValue::Undefined, ExprValue::Boolean)
+                        }
+                        _ => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::Conditional => {
+                    let if_false = pop!();
+                    let if_true = pop!();
+                    match pop!().as_bool() {
+                        Some(true) => if_true,
+                        Some(false) => if_false,
+                        None => ExprValue::Undefined,
+                    }
+                }
+                IfrOp::Map => {
+                    let end = scope_end(expression, pc).ok_or(ExprError::UnclosedScope(op.op_code()))?;
+                    let pairs = self.run(&expression[pc..end], depth)?;
+                    pc = end + 1;
+
+                    let key = pop!();
+                    pairs
+                        .chunks_exact(2)
+                        .find(|pair| compare(&pair[0], &key).is_some_and(|ord| ord.is_eq()))
+                        .map_or(ExprValue::Undefined, |pair| pair[1].clone())
+                }
+                op => return Err(ExprError::UnexpectedOp(op.op_code())),
+            };
+            stack.push(value);
+        }
+        Ok(stack)
+    }
+}
+
+/// Index of the END closing the scope that starts at `start`
+fn scope_end(expression: &[IfrOp], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, op) in expression.iter().enumerate().skip(start) {
+        match op {
+            IfrOp::End if depth == 0 => return Some(index),
+            IfrOp::End => depth -= 1,
+            IfrOp::Map => depth += 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn compare(left: &ExprValue, right: &ExprValue) -> Option<core::cmp::Ordering> {
+    match (left, right) {
+        (ExprValue::String(l), ExprValue::String(r)) => Some(l.cmp(r)),
+        (ExprValue::Buffer(l), ExprValue::Buffer(r)) => Some(l.cmp(r)),
+        _ => Some(left.as_uint()?.cmp(&right.as_uint()?)),
+    }
+}
+
+fn uint_operand(value: &ExprValue) -> Option<u64> {
+    match value {
+        ExprValue::Uint(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn arithmetic(op: &IfrOp, left: u64, right: u64) -> ExprValue {
+    let result = match op {
+        IfrOp::BitwiseAnd => Some(left & right),
+        IfrOp::BitwiseOr => Some(left | right),
+        IfrOp::ShiftLeft => Some(left.checked_shl(right as u32).unwrap_or(0)),
+        IfrOp::ShiftRight => Some(left.checked_shr(right as u32).unwrap_or(0)),
+        IfrOp::Add => Some(left.wrapping_add(right)),
+        IfrOp::Subtract => Some(left.wrapping_sub(right)),
+        IfrOp::Multiply => Some(left.wrapping_mul(right)),
+        IfrOp::Divide => left.checked_div(right),
+        IfrOp::Modulo => left.checked_rem(right),
+        _ => None,
+    };
+    result.map_or(ExprValue::Undefined, ExprValue::Uint)
+}
+
+fn parse_uint(s: &str) -> Option<u64> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn to_string(value: ExprValue, format: u8) -> ExprValue {
+    match value {
+        ExprValue::String(s) => ExprValue::String(s),
+        ExprValue::Boolean(v) => ExprValue::String(String::from(if v { "True" } else { "False" })),
+        ExprValue::Uint(v) => ExprValue::String(match format {
+            EFI_IFR_STRING_SIGNED_DEC => format!("{}", v as i64),
+            EFI_IFR_STRING_LOWERCASE_HEX => format!("{:x}", v),
+            EFI_IFR_STRING_UPPERCASE_HEX => format!("{:X}", v),
+            _ => format!("{}", v),
+        }),
+        ExprValue::Buffer(buffer) if format & EFI_IFR_STRING_UNICODE != 0 => ExprValue::String(
+            char::decode_utf16(
+                buffer
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .take_while(|c| *c != 0),
+            )
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect(),
+        ),
+        ExprValue::Buffer(buffer) => ExprValue::String(
+            buffer
+                .iter()
+                .take_while(|b| **b != 0)
+                .map(|b| *b as char)
+                .collect(),
+        ),
+        _ => ExprValue::Undefined,
+    }
+}
+
+fn mid(source: ExprValue, index: &ExprValue, length: &ExprValue) -> ExprValue {
+    let (Some(index), Some(length)) = (uint_operand(index), uint_operand(length)) else {
+        return ExprValue::Undefined;
+    };
+    let (index, length) = (index as usize, length as usize);
+    match source {
+        ExprValue::String(s) => ExprValue::String(s.chars().skip(index).take(length).collect()),
+        ExprValue::Buffer(buffer) => {
+            ExprValue::Buffer(buffer.into_iter().skip(index).take(length).collect())
+        }
+        _ => ExprValue::Undefined,
+    }
+}
+
+fn find(haystack: &ExprValue, needle: &ExprValue, index: &ExprValue, format: u8) -> ExprValue {
+    let (ExprValue::String(haystack), ExprValue::String(needle), Some(index)) =
+        (haystack, needle, uint_operand(index))
+    else {
+        return ExprValue::Undefined;
+    };
+    let (haystack, needle): (Vec<char>, Vec<char>) = match format {
+        EFI_IFR_FF_CASE_INSENSITIVE => (
+            haystack.to_uppercase().chars().collect(),
+            needle.to_uppercase().chars().collect(),
+        ),
+        EFI_IFR_FF_CASE_SENSITIVE => (haystack.chars().collect(), needle.chars().collect()),
+        _ => return ExprValue::Undefined,
+    };
+
+    let found = (index as usize..=haystack.len().saturating_sub(needle.len()))
+        .find(|start| haystack[*start..].starts_with(&needle));
+    ExprValue::Uint(found.map_or(u64::MAX, |start| start as u64))
+}
+
+fn token(source: &ExprValue, delimiters: &ExprValue, index: &ExprValue) -> ExprValue {
+    let (ExprValue::String(source), ExprValue::String(delimiters), Some(index)) =
+        (source, delimiters, uint_operand(index))
+    else {
+        return ExprValue::Undefined;
+    };
+    let token = source
+        .split(|c| delimiters.contains(c))
+        .nth(index as usize)
+        .unwrap_or("");
+    ExprValue::String(String::from(token))
+}
+
+fn span(source: &ExprValue, charset: &ExprValue, index: &ExprValue, flags: u8) -> ExprValue {
+    let (ExprValue::String(source), ExprValue::String(charset), Some(index)) =
+        (source, charset, uint_operand(index))
+    else {
+        return ExprValue::Undefined;
+    };
+    // Charset is a list of character ranges given as pairs of characters
+    let ranges: Vec<char> = charset.chars().collect();
+    let in_charset = |c: char| ranges.chunks(2).any(|r| c >= r[0] && c <= *r.last().unwrap());
+    let non_matching = flags & EFI_IFR_FLAGS_FIRST_NON_MATCHING != 0;
+
+    let found = source
+        .chars()
+        .enumerate()
+        .skip(index as usize)
+        .find(|(_, c)| in_charset(*c) != non_matching);
+    ExprValue::Uint(found.map_or(u64::MAX, |(position, _)| position as u64))
+}
+
+/// Case-insensitive pattern match of EFI_UNICODE_COLLATION_PROTOCOL.MetaiMatch
+/// (`*`, `?` and `[...]` character sets)
+fn meta_match(string: &str, pattern: &str) -> bool {
+    let string: Vec<char> = string.to_uppercase().chars().collect();
+    let pattern: Vec<char> = pattern.to_uppercase().chars().collect();
+    meta_match_at(&string, &pattern)
+}
+
+fn meta_match_at(string: &[char], pattern: &[char]) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return string.is_empty();
+    };
+    match first {
+        '*' => (0..=string.len()).any(|skip| meta_match_at(&string[skip..], rest)),
+        '?' => !string.is_empty() && meta_match_at(&string[1..], rest),
+        '[' => {
+            let Some(close) = rest.iter().position(|c| *c == ']') else {
+                return false;
+            };
+            let set = &rest[..close];
+            let Some(c) = string.first() else {
+                return false;
+            };
+            let matched = set.iter().enumerate().any(|(i, s)| {
+                if set.get(i + 1) == Some(&'-') && i + 2 < set.len() {
+                    *c >= *s && *c <= set[i + 2]
+                } else {
+                    c == s
+                }
+            });
+            matched && meta_match_at(&string[1..], &rest[close + 1..])
+        }
+        c => string.first() == Some(c) && meta_match_at(&string[1..], rest),
+    }
+}
+
+/// Regular expression search for EFI_IFR_MATCH2, supports literals, `.`,
+/// character classes, `^`, `$`, `\` escapes and the `*`, `+`, `?` quantifiers.
+/// Returns `None` for patterns using unsupported syntax.
+fn regex_search(string: &str, pattern: &str) -> Option<bool> {
+    let atoms = regex_compile(pattern)?;
+    let string: Vec<char> = string.chars().collect();
+
+    if let Some(RegexAtom::Start) = atoms.first() {
+        return Some(regex_match_at(&string, 0, &atoms[1..]));
+    }
+    Some((0..=string.len()).any(|start| regex_match_at(&string, start, &atoms)))
+}
+
+#[derive(Clone)]
+enum RegexClass {
+    Any,
+    Char(char),
+    Set { negated: bool, items: Vec<(char, char)> },
+}
+
+impl RegexClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            RegexClass::Any => true,
+            RegexClass::Char(expected) => c == *expected,
+            RegexClass::Set { negated, items } => {
+                items.iter().any(|(low, high)| c >= *low && c <= *high) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+enum RegexAtom {
+    Start,
+    End,
+    /// Class with its minimum and maximum (`None` = unbounded) repetition
+    Repeat(RegexClass, usize, Option<usize>),
+}
+
+fn regex_compile(pattern: &str) -> Option<Vec<RegexAtom>> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut atoms = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let class = match chars[i] {
+            '^' if i == 0 => {
+                atoms.push(RegexAtom::Start);
+                i += 1;
+                continue;
+            }
+            '$' if i == chars.len() - 1 => {
+                atoms.push(RegexAtom::End);
+                i += 1;
+                continue;
+            }
+            '.' => RegexClass::Any,
+            '\\' => {
+                i += 1;
+                RegexClass::Char(*chars.get(i)?)
+            }
+            '[' => {
+                let mut items = Vec::new();
+                i += 1;
+                let negated = chars.get(i) == Some(&'^');
+                if negated {
+                    i += 1;
+                }
+                while *chars.get(i)? != ']' {
+                    let low = chars[i];
+                    if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+                        items.push((low, chars[i + 2]));
+                        i += 3;
+                    } else {
+                        items.push((low, low));
+                        i += 1;
+                    }
+                }
+                RegexClass::Set { negated, items }
+            }
+            '(' | ')' | '|' | '{' | '}' | '*' | '+' | '?' => return None,
+            c => RegexClass::Char(c),
+        };
+        i += 1;
+
+        let (min, max) = match chars.get(i) {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            _ => (1, Some(1)),
+        };
+        if min != 1 || max != Some(1) {
+            i += 1;
+        }
+        atoms.push(RegexAtom::Repeat(class, min, max));
+    }
+    Some(atoms)
+}
+
+fn regex_match_at(string: &[char], position: usize, atoms: &[RegexAtom]) -> bool {
+    let Some((atom, rest)) = atoms.split_first() else {
+        return true;
+    };
+    match atom {
+        RegexAtom::Start => position == 0 && regex_match_at(string, position, rest),
+        RegexAtom::End => position == string.len() && regex_match_at(string, position, rest),
+        RegexAtom::Repeat(class, min, max) => {
+            let available = string[position..]
+                .iter()
+                .take_while(|c| class.matches(**c))
+                .count();
+            let most = max.map_or(available, |max| max.min(available));
+            if most < *min {
+                return false;
+            }
+            // Greedy with backtracking
+            (*min..=most)
+                .rev()
+                .any(|count| regex_match_at(string, position + count, rest))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uefi::Guid;
+
+    const STRING: u16 = 1;
+    const OTHER: u16 = 2;
+
+    fn values(string: &str, other: &str) -> QuestionValues {
+        let mut values = QuestionValues::new();
+        values.insert(STRING, ExprValue::String(String::from(string)));
+        values.insert(OTHER, ExprValue::String(String::from(other)));
+        values
+    }
+
+    fn eval(values: &QuestionValues, expression: &[IfrOp]) -> ExprValue {
+        Evaluator::new(values).eval(expression).unwrap()
+    }
+
+    fn uint(values: &QuestionValues, left: u64, right: u64, op: IfrOp) -> ExprValue {
+        eval(values, &[IfrOp::Uint64(left), IfrOp::Uint64(right), op])
+    }
+
+    fn string(value: &str) -> ExprValue {
+        ExprValue::String(String::from(value))
+    }
+
+    #[test]
+    fn binary_operand_order() {
+        let values = QuestionValues::new();
+        assert_eq!(uint(&values, 10, 3, IfrOp::Subtract), ExprValue::Uint(7));
+        assert_eq!(uint(&values, 10, 3, IfrOp::Divide), ExprValue::Uint(3));
+        assert_eq!(uint(&values, 10, 3, IfrOp::Modulo), ExprValue::Uint(1));
+        assert_eq!(uint(&values, 1, 4, IfrOp::ShiftLeft), ExprValue::Uint(16));
+        assert_eq!(uint(&values, 16, 4, IfrOp::ShiftRight), ExprValue::Uint(1));
+        assert_eq!(
+            uint(&values, 3, 10, IfrOp::LessThan),
+            ExprValue::Boolean(true)
+        );
+        assert_eq!(uint(&values, 10, 0, IfrOp::Divide), ExprValue::Undefined);
+
+        let values = self::values("ab", "cd");
+        let catenate = [
+            IfrOp::QuestionRef1 {
+                question_id: STRING,
+            },
+            IfrOp::QuestionRef1 { question_id: OTHER },
+            IfrOp::Catenate,
+        ];
+        assert_eq!(eval(&values, &catenate), string("abcd"));
+    }
+
+    #[test]
+    fn string_operand_order() {
+        let values = values("one,two;three", ",;");
+        let source = IfrOp::QuestionRef1 {
+            question_id: STRING,
+        };
+
+        // MID string index length
+        let mid = [source.clone(), IfrOp::Uint8(4), IfrOp::Uint8(3), IfrOp::Mid];
+        assert_eq!(eval(&values, &mid), string("two"));
+
+        // TOKEN string delimiters index
+        let token = [
+            source.clone(),
+            IfrOp::QuestionRef1 { question_id: OTHER },
+            IfrOp::Uint8(2),
+            IfrOp::Token,
+        ];
+        assert_eq!(eval(&values, &token), string("three"));
+
+        // SPAN string charset index
+        let values = self::values("ab12cd", "09");
+        let span = |flags| {
+            eval(
+                &values,
+                &[
+                    IfrOp::QuestionRef1 {
+                        question_id: STRING,
+                    },
+                    IfrOp::QuestionRef1 { question_id: OTHER },
+                    IfrOp::Uint8(1),
+                    IfrOp::Span { flags },
+                ],
+            )
+        };
+        assert_eq!(span(0), ExprValue::Uint(2));
+        assert_eq!(span(EFI_IFR_FLAGS_FIRST_NON_MATCHING), ExprValue::Uint(1));
+    }
+
+    #[test]
+    fn find_from_index() {
+        let values = values("abcabc", "BC");
+        let find = |index, format| {
+            eval(
+                &values,
+                &[
+                    IfrOp::QuestionRef1 {
+                        question_id: STRING,
+                    },
+                    IfrOp::QuestionRef1 { question_id: OTHER },
+                    IfrOp::Uint8(index),
+                    IfrOp::Find { format },
+                ],
+            )
+        };
+        assert_eq!(find(0, EFI_IFR_FF_CASE_INSENSITIVE), ExprValue::Uint(1));
+        assert_eq!(find(2, EFI_IFR_FF_CASE_INSENSITIVE), ExprValue::Uint(4));
+        assert_eq!(
+            find(0, EFI_IFR_FF_CASE_SENSITIVE),
+            ExprValue::Uint(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn conditional_operand_order() {
+        let values = QuestionValues::new();
+        let conditional = |condition| {
+            eval(
+                &values,
+                &[
+                    condition,
+                    IfrOp::Uint8(1),
+                    IfrOp::Uint8(2),
+                    IfrOp::Conditional,
+                ],
+            )
+        };
+        assert_eq!(conditional(IfrOp::True), ExprValue::Uint(1));
+        assert_eq!(conditional(IfrOp::False), ExprValue::Uint(2));
+        assert_eq!(conditional(IfrOp::Undefined), ExprValue::Undefined);
+    }
+
+    #[test]
+    fn match_pattern() {
+        // MATCH pops the string first, the pattern is pushed first
+        let matches = |pattern: &str, string: &str| {
+            let values = values(pattern, string);
+            eval(
+                &values,
+                &[
+                    IfrOp::QuestionRef1 {
+                        question_id: STRING,
+                    },
+                    IfrOp::QuestionRef1 { question_id: OTHER },
+                    IfrOp::Match,
+                ],
+            )
+        };
+        assert_eq!(matches("B*t?", "boots"), ExprValue::Boolean(true));
+        assert_eq!(matches("B*t?", "boot"), ExprValue::Boolean(false));
+        assert_eq!(matches("[a-c]x", "Bx"), ExprValue::Boolean(true));
+        assert_eq!(matches("[a-c]x", "dx"), ExprValue::Boolean(false));
+    }
+
+    #[test]
+    fn match2_regex() {
+        // MATCH2 pops the pattern first, the string is pushed first
+        let matches = |string: &str, pattern: &str| {
+            let values = values(string, pattern);
+            eval(
+                &values,
+                &[
+                    IfrOp::QuestionRef1 {
+                        question_id: STRING,
+                    },
+                    IfrOp::QuestionRef1 { question_id: OTHER },
+                    IfrOp::Match2 {
+                        syntax_type: Guid::ZERO,
+                    },
+                ],
+            )
+        };
+        assert_eq!(
+            matches("ver 2.70", "[0-9]+\\.[0-9]+$"),
+            ExprValue::Boolean(true)
+        );
+        assert_eq!(
+            matches("ver 2.", "[0-9]+\\.[0-9]+$"),
+            ExprValue::Boolean(false)
+        );
+        assert_eq!(matches("ver 2", "^[0-9]"), ExprValue::Boolean(false));
+        assert_eq!(matches("colour", "^colou?r$"), ExprValue::Boolean(true));
+        assert_eq!(matches("abc", "[^a-c]"), ExprValue::Boolean(false));
+        assert_eq!(matches("a|b", "a|b"), ExprValue::Undefined);
+    }
+
+    #[test]
+    fn undefined_propagates() {
+        let values = QuestionValues::new();
+        let missing = IfrOp::QuestionRef1 { question_id: 0x99 };
+
+        for op in [IfrOp::Add, IfrOp::Equal, IfrOp::And, IfrOp::Catenate] {
+            let expression = [missing.clone(), IfrOp::Uint8(1), op];
+            assert_eq!(eval(&values, &expression), ExprValue::Undefined);
+        }
+        for op in [
+            IfrOp::Not,
+            IfrOp::BitwiseNot,
+            IfrOp::Length,
+            IfrOp::ToBoolean,
+        ] {
+            assert_eq!(eval(&values, &[missing.clone(), op]), ExprValue::Undefined);
+        }
+        let ideqval = [IfrOp::EqIdVal {
+            question_id: 0x99,
+            value: 0,
+        }];
+        assert_eq!(eval(&values, &ideqval), ExprValue::Undefined);
+        assert!(!Evaluator::new(&values).eval_bool(&ideqval).unwrap());
+    }
+
+    #[test]
+    fn stack_underflow() {
+        let values = QuestionValues::new();
+        assert_eq!(
+            Evaluator::new(&values).eval(&[IfrOp::Uint8(1), IfrOp::Subtract]),
+            Err(ExprError::StackUnderflow(EfiIfrOpCode::SubtractOp))
+        );
+    }
+}
@@ -0,0 +1,322 @@
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use core::any::Any;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use uefi::{Guid, guid};
+
+use super::*;
+
+/// EFI_IFR_TIANO_GUID, EDK2 extensions of EFI_IFR_GUID
+pub const EFI_IFR_TIANO_GUID: Guid = guid!("0f0b1735-87a0-4193-b266-538c38af48ce");
+/// EFI_IFR_FRAMEWORK_GUID, Framework HII compatibility extensions
+pub const EFI_IFR_FRAMEWORK_GUID: Guid = guid!("31ca5d1a-d511-4931-b782-ae6b2b178cd7");
+/// EDKII_IFR_BIT_VARSTORE_GUID, questions in its scope store bit fields
+pub const EDKII_IFR_BIT_VARSTORE_GUID: Guid = guid!("82ddd68b-9163-4187-9b27-20a8fd60a71d");
+
+/// ExtendOpCode values of EFI_IFR_TIANO_GUID
+pub const EFI_IFR_EXTEND_OP_LABEL: u8 = 0x00;
+pub const EFI_IFR_EXTEND_OP_BANNER: u8 = 0x01;
+pub const EFI_IFR_EXTEND_OP_TIMEOUT: u8 = 0x02;
+pub const EFI_IFR_EXTEND_OP_CLASS: u8 = 0x03;
+pub const EFI_IFR_EXTEND_OP_SUBCLASS: u8 = 0x04;
+
+/// ExtendOpCode values of EFI_IFR_FRAMEWORK_GUID
+pub const EFI_IFR_EXTEND_OP_OPTIONKEY: u8 = 0x00;
+pub const EFI_IFR_EXTEND_OP_VAREQNAME: u8 = 0x01;
+
+/// Alignment of EFI_IFR_GUID_BANNER
+pub const EFI_IFR_BANNER_ALIGN_LEFT: u8 = 0x00;
+pub const EFI_IFR_BANNER_ALIGN_CENTER: u8 = 0x01;
+pub const EFI_IFR_BANNER_ALIGN_RIGHT: u8 = 0x02;
+
+/// Values of EFI_IFR_GUID_CLASS
+pub const EFI_NON_DEVICE_CLASS: u16 = 0x00;
+pub const EFI_DISK_DEVICE_CLASS: u16 = 0x01;
+pub const EFI_VIDEO_DEVICE_CLASS: u16 = 0x02;
+pub const EFI_NETWORK_DEVICE_CLASS: u16 = 0x04;
+pub const EFI_INPUT_DEVICE_CLASS: u16 = 0x08;
+pub const EFI_ON_BOARD_DEVICE_CLASS: u16 = 0x10;
+pub const EFI_OTHER_DEVICE_CLASS: u16 = 0x20;
+
+/// Values of EFI_IFR_GUID_SUBCLASS
+pub const EFI_SETUP_APPLICATION_SUBCLASS: u16 = 0x00;
+pub const EFI_GENERAL_APPLICATION_SUBCLASS: u16 = 0x01;
+pub const EFI_FRONT_PAGE_SUBCLASS: u16 = 0x02;
+pub const EFI_SINGLE_USE_SUBCLASS: u16 = 0x03;
+
+/// Vendor-specific typed view of an EFI_IFR_GUID opcode.
+pub trait IfrGuidOpView: Debug + Display {
+    /// GUID of the extension the view was decoded from
+    fn guid(&self) -> Guid;
+
+    /// Allows downcasting to the concrete view type
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Decoded data of an EFI_IFR_GUID opcode.
+#[derive(Debug)]
+pub enum IfrGuidExtension {
+    /// EFI_IFR_GUID_LABEL, marks an insertion point for dynamic opcodes
+    Label { number: u16 },
+    /// EFI_IFR_GUID_BANNER
+    Banner {
+        title: u16,
+        line_number: u16,
+        alignment: u8,
+    },
+    /// EFI_IFR_GUID_TIMEOUT, seconds
+    Timeout { timeout: u16 },
+    /// EFI_IFR_GUID_CLASS
+    Class { class: u16 },
+    /// EFI_IFR_GUID_SUBCLASS
+    SubClass { sub_class: u16 },
+    /// EFI_IFR_GUID_OPTIONKEY, `option_value` is the raw EFI_IFR_TYPE_VALUE
+    OptionKey {
+        question_id: u16,
+        option_value: Vec<u8>,
+        key_value: u16,
+    },
+    /// EFI_IFR_GUID_VAREQNAME
+    VarEqName { question_id: u16, name_id: u16 },
+    /// Decoded by a registered decoder
+    Vendor(Box<dyn IfrGuidOpView>),
+}
+
+impl IfrGuidExtension {
+    /// Decode the data of the EDK2 and Framework extensions; `data` follows the GUID
+    pub fn decode(guid: Guid, data: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(data);
+        let extend_op_code = r.u8()?;
+
+        Some(match (guid, extend_op_code) {
+            (EFI_IFR_TIANO_GUID, EFI_IFR_EXTEND_OP_LABEL) => {
+                IfrGuidExtension::Label { number: r.u16()? }
+            }
+            (EFI_IFR_TIANO_GUID, EFI_IFR_EXTEND_OP_BANNER) => IfrGuidExtension::Banner {
+                title: r.u16()?,
+                line_number: r.u16()?,
+                alignment: r.u8()?,
+            },
+            (EFI_IFR_TIANO_GUID, EFI_IFR_EXTEND_OP_TIMEOUT) => {
+                IfrGuidExtension::Timeout { timeout: r.u16()? }
+            }
+            (EFI_IFR_TIANO_GUID, EFI_IFR_EXTEND_OP_CLASS) => {
+                IfrGuidExtension::Class { class: r.u16()? }
+            }
+            (EFI_IFR_TIANO_GUID, EFI_IFR_EXTEND_OP_SUBCLASS) => IfrGuidExtension::SubClass {
+                sub_class: r.u16()?,
+            },
+            (EFI_IFR_FRAMEWORK_GUID, EFI_IFR_EXTEND_OP_OPTIONKEY) => {
+                let question_id = r.u16()?;
+                let rest = r.rest();
+                let (option_value, key_value) =
+                    rest.split_at_checked(rest.len().checked_sub(2)?)?;
+                IfrGuidExtension::OptionKey {
+                    question_id,
+                    option_value: option_value.to_vec(),
+                    key_value: u16::from_le_bytes([key_value[0], key_value[1]]),
+                }
+            }
+            (EFI_IFR_FRAMEWORK_GUID, EFI_IFR_EXTEND_OP_VAREQNAME) => IfrGuidExtension::VarEqName {
+                question_id: r.u16()?,
+                name_id: r.u16()?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// EFI_IFR_GUID opcode of an EDK2 or Framework extension, `None` for vendor views
+    pub fn to_op(&self) -> Option<IfrOp> {
+        let mut w = Writer::new();
+        let guid = match self {
+            IfrGuidExtension::Label { number } => {
+                w.u8(EFI_IFR_EXTEND_OP_LABEL);
+                w.u16(*number);
+                EFI_IFR_TIANO_GUID
+            }
+            IfrGuidExtension::Banner {
+                title,
+                line_number,
+                alignment,
+            } => {
+                w.u8(EFI_IFR_EXTEND_OP_BANNER);
+                w.u16(*title);
+                w.u16(*line_number);
+                w.u8(*alignment);
+                EFI_IFR_TIANO_GUID
+            }
+            IfrGuidExtension::Timeout { timeout } => {
+                w.u8(EFI_IFR_EXTEND_OP_TIMEOUT);
+                w.u16(*timeout);
+                EFI_IFR_TIANO_GUID
+            }
+            IfrGuidExtension::Class { class } => {
+                w.u8(EFI_IFR_EXTEND_OP_CLASS);
+                w.u16(*class);
+                EFI_IFR_TIANO_GUID
+            }
+            IfrGuidExtension::SubClass { sub_class } => {
+                w.u8(EFI_IFR_EXTEND_OP_SUBCLASS);
+                w.u16(*sub_class);
+                EFI_IFR_TIANO_GUID
+            }
+            IfrGuidExtension::OptionKey {
+                question_id,
+                option_value,
+                key_value,
+            } => {
+                w.u8(EFI_IFR_EXTEND_OP_OPTIONKEY);
+                w.u16(*question_id);
+                w.bytes(option_value);
+                w.u16(*key_value);
+                EFI_IFR_FRAMEWORK_GUID
+            }
+            IfrGuidExtension::VarEqName {
+                question_id,
+                name_id,
+            } => {
+                w.u8(EFI_IFR_EXTEND_OP_VAREQNAME);
+                w.u16(*question_id);
+                w.u16(*name_id);
+                EFI_IFR_FRAMEWORK_GUID
+            }
+            IfrGuidExtension::Vendor(_) => return None,
+        };
+        Some(IfrOp::Guid {
+            guid,
+            data: w.into_bytes(),
+        })
+    }
+}
+
+impl Display for IfrGuidExtension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IfrGuidExtension::Label { number } => write!(f, "label 0x{:04X}", number),
+            IfrGuidExtension::Banner {
+                title,
+                line_number,
+                alignment,
+            } => {
+                let align = match *alignment {
+                    EFI_IFR_BANNER_ALIGN_LEFT => "left",
+                    EFI_IFR_BANNER_ALIGN_CENTER => "center",
+                    EFI_IFR_BANNER_ALIGN_RIGHT => "right",
+                    _ => "unknown",
+                };
+                write!(
+                    f,
+                    "banner title=0x{:04X} line={} align={}",
+                    title, line_number, align
+                )
+            }
+            IfrGuidExtension::Timeout { timeout } => write!(f, "timeout {}", timeout),
+            IfrGuidExtension::Class { class } => write!(f, "class 0x{:04X}", class),
+            IfrGuidExtension::SubClass { sub_class } => write!(f, "subclass 0x{:04X}", sub_class),
+            IfrGuidExtension::OptionKey {
+                question_id,
+                key_value,
+                ..
+            } => write!(
+                f,
+                "optionkey question=0x{:04X} key=0x{:04X}",
+                question_id, key_value
+            ),
+            IfrGuidExtension::VarEqName {
+                question_id,
+                name_id,
+            } => {
+                write!(
+                    f,
+                    "vareqname question=0x{:04X} name=0x{:04X}",
+                    question_id, name_id
+                )
+            }
+            IfrGuidExtension::Vendor(view) => write!(f, "{}", view),
+        }
+    }
+}
+
+/// Decoder for the data of an EFI_IFR_GUID opcode, `None` if the data is malformed.
+pub type IfrGuidOpDecoder = fn(data: &[u8]) -> Option<Box<dyn IfrGuidOpView>>;
+
+/// Set of EFI_IFR_GUID decoders keyed by GUID, on top of the EDK2 and Framework extensions.
+#[derive(Default)]
+pub struct IfrGuidOpRegistry {
+    decoders: Vec<(Guid, IfrGuidOpDecoder)>,
+}
+
+impl IfrGuidOpRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a decoder, replacing any decoder already registered for `guid`
+    pub fn register(&mut self, guid: Guid, decoder: IfrGuidOpDecoder) -> &mut Self {
+        match self.decoders.iter_mut().find(|(g, _)| *g == guid) {
+            Some(entry) => entry.1 = decoder,
+            None => self.decoders.push((guid, decoder)),
+        }
+        self
+    }
+
+    pub fn decoder(&self, guid: Guid) -> Option<IfrGuidOpDecoder> {
+        self.decoders
+            .iter()
+            .find(|(g, _)| *g == guid)
+            .map(|(_, decoder)| *decoder)
+    }
+
+    /// Decode with the decoder registered for `guid`, falling back to the built-in extensions
+    pub fn decode(&self, guid: Guid, data: &[u8]) -> Option<IfrGuidExtension> {
+        match self.decoder(guid) {
+            Some(decoder) => decoder(data).map(IfrGuidExtension::Vendor),
+            None => IfrGuidExtension::decode(guid, data),
+        }
+    }
+}
+
+impl IfrOp {
+    /// Built-in extension of an EFI_IFR_GUID opcode
+    pub fn guid_extension(&self) -> Option<IfrGuidExtension> {
+        match self {
+            IfrOp::Guid { guid, data } => IfrGuidExtension::decode(*guid, data),
+            _ => None,
+        }
+    }
+
+    /// Extension of an EFI_IFR_GUID opcode decoded with the decoders of `registry`
+    pub fn guid_extension_with(&self, registry: &IfrGuidOpRegistry) -> Option<IfrGuidExtension> {
+        match self {
+            IfrOp::Guid { guid, data } => registry.decode(*guid, data),
+            _ => None,
+        }
+    }
+
+    /// Label number of an EFI_IFR_GUID_LABEL opcode
+    pub fn label(&self) -> Option<u16> {
+        match self.guid_extension()? {
+            IfrGuidExtension::Label { number } => Some(number),
+            _ => None,
+        }
+    }
+}
+
+impl IfrTree {
+    /// Labels of the tree with the offsets of their opcodes
+    pub fn labels(&self) -> Vec<(u16, usize)> {
+        self.walk()
+            .filter_map(|(node, _)| node.op().label().map(|number| (number, node.offset())))
+            .collect()
+    }
+
+    /// First EFI_IFR_GUID_LABEL with the given number
+    pub fn find_label(&self, number: u16) -> Option<&IfrNode> {
+        self.walk()
+            .map(|(node, _)| node)
+            .find(|node| node.op().label() == Some(number))
+    }
+}
@@ -0,0 +1,133 @@
+//! Internal Forms Representation (IFR) of form packages.
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use crate::package_header::ifr_parse::EfiIfrOpCode;
+
+mod op;
+pub use op::*;
+
+mod tree;
+pub use tree::*;
+
+mod model;
+pub use model::*;
+
+mod expression;
+pub use expression::*;
+
+mod visibility;
+pub use visibility::*;
+
+mod vfr;
+pub use vfr::*;
+
+mod builder;
+pub use builder::*;
+
+mod expr_builder;
+pub use expr_builder::*;
+
+mod stats;
+pub use stats::*;
+
+mod validate;
+pub use validate::*;
+
+mod guid_op;
+pub use guid_op::*;
+
+mod update;
+pub use update::*;
+
+mod value;
+pub use value::*;
+
+mod defaults;
+pub use defaults::*;
+
+/// Errors reported while decoding IFR opcodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfrError {
+    /// Opcode value is not defined by the specification
+    UnknownOpCode { offset: usize, op_code: u8 },
+    /// Opcode is shorter than its structure requires
+    ShortOpCode {
+        offset: usize,
+        op_code: EfiIfrOpCode,
+        length: usize,
+    },
+    /// Opcode length is zero or runs past the end of the form package
+    Truncated { offset: usize },
+    /// EFI_IFR_END without an open scope
+    UnbalancedEnd { offset: usize },
+    /// Scope opened at `offset` is never closed by EFI_IFR_END
+    UnclosedScope { offset: usize },
+    /// Encoded opcode does not fit in the 7-bit length of EFI_IFR_OP_HEADER
+    Oversized {
+        offset: usize,
+        op_code: EfiIfrOpCode,
+        length: usize,
+    },
+}
+
+impl IfrError {
+    /// Offset of the opcode in the form package data
+    pub fn offset(&self) -> usize {
+        match self {
+            IfrError::UnknownOpCode { offset, .. }
+            | IfrError::ShortOpCode { offset, .. }
+            | IfrError::Truncated { offset }
+            | IfrError::UnbalancedEnd { offset }
+            | IfrError::UnclosedScope { offset }
+            | IfrError::Oversized { offset, .. } => *offset,
+        }
+    }
+
+    pub(crate) fn with_offset(self, at: usize) -> Self {
+        match self {
+            IfrError::UnknownOpCode { op_code, .. } => IfrError::UnknownOpCode { offset: at, op_code },
+            IfrError::ShortOpCode { op_code, length, .. } => IfrError::ShortOpCode {
+                offset: at,
+                op_code,
+                length,
+            },
+            IfrError::Truncated { .. } => IfrError::Truncated { offset: at },
+            IfrError::UnbalancedEnd { .. } => IfrError::UnbalancedEnd { offset: at },
+            IfrError::UnclosedScope { .. } => IfrError::UnclosedScope { offset: at },
+            IfrError::Oversized { op_code, length, .. } => IfrError::Oversized {
+                offset: at,
+                op_code,
+                length,
+            },
+        }
+    }
+}
+
+impl Display for IfrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IfrError::UnknownOpCode { offset, op_code } => {
+                write!(f, "0x{:04X}: unknown opcode 0x{:02X}", offset, op_code)
+            }
+            IfrError::ShortOpCode {
+                offset,
+                op_code,
+                length,
+            } => write!(f, "0x{:04X}: {:?} is too short ({} bytes)", offset, op_code, length),
+            IfrError::Truncated { offset } => write!(f, "0x{:04X}: truncated opcode", offset),
+            IfrError::UnbalancedEnd { offset } => {
+                write!(f, "0x{:04X}: END without an open scope", offset)
+            }
+            IfrError::UnclosedScope { offset } => {
+                write!(f, "0x{:04X}: scope is not closed", offset)
+            }
+            IfrError::Oversized {
+                offset,
+                op_code,
+                length,
+            } => write!(f, "0x{:04X}: {:?} is too long ({} bytes)", offset, op_code, length),
+        }
+    }
+}
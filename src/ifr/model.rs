@@ -0,0 +1,756 @@
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use uefi::Guid;
+
+use super::*;
+use crate::package_header::StringTable;
+use crate::package_header::ifr_parse::is_expression_op_code;
+
+/// STRING_ID together with its text from the string package.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HiiString {
+    pub id: u16,
+    pub text: Option<String>,
+}
+
+impl HiiString {
+    pub fn resolve(id: u16, strings: &StringTable) -> Self {
+        Self {
+            id,
+            text: strings.get(id).map(String::from),
+        }
+    }
+}
+
+impl Display for HiiString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.text {
+            Some(text) => write!(f, "{}", text),
+            None => write!(f, "STRING_TOKEN(0x{:04X})", self.id),
+        }
+    }
+}
+
+/// Kind of storage declared by a varstore opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarStoreKind {
+    /// EFI_IFR_VARSTORE, buffer storage
+    Buffer,
+    /// EFI_IFR_VARSTORE_EFI, buffer stored in a UEFI variable
+    Efi { attributes: u32 },
+    /// EFI_IFR_VARSTORE_NAME_VALUE, one name per question
+    NameValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarStore {
+    pub id: u16,
+    pub guid: Guid,
+    pub name: String,
+    pub size: u16,
+    pub kind: VarStoreKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultStore {
+    pub id: u16,
+    pub name: HiiString,
+}
+
+/// Opcode kind of a condition scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionKind {
+    SuppressIf,
+    GrayOutIf,
+    DisableIf,
+}
+
+/// Condition scope enclosing a statement, option or form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub kind: ConditionKind,
+    /// Offset of the condition opcode
+    pub offset: usize,
+    /// Expression opcodes in postfix order, nested scopes end with [`IfrOp::End`]
+    pub expression: Vec<IfrOp>,
+}
+
+/// Where a question keeps its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuestionStorage {
+    /// Question is not bound to a varstore
+    None,
+    /// Byte offset in a buffer varstore
+    Offset(u16),
+    /// Bit field of a buffer varstore (EDK2 bit varstore), offset and width in bits
+    Bits { offset: u16, width: u8 },
+    /// Name in a name/value varstore
+    Name(HiiString),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuestionKind {
+    OneOf,
+    Checkbox,
+    Numeric,
+    Password,
+    OrderedList,
+    Date,
+    Time,
+    String,
+    Ref {
+        form_id: Option<u16>,
+        question_id: Option<u16>,
+        form_set_id: Option<Guid>,
+    },
+    Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionOption {
+    pub text: HiiString,
+    pub flags: u8,
+    pub value: IfrTypeValue,
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefaultValue {
+    Value(IfrTypeValue),
+    /// Value computed by an expression (EFI_IFR_DEFAULT_2)
+    Expression(Vec<IfrOp>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionDefault {
+    pub default_id: u16,
+    pub value: DefaultValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    /// Offset of the question opcode
+    pub offset: usize,
+    pub id: u16,
+    pub kind: QuestionKind,
+    pub prompt: HiiString,
+    pub help: HiiString,
+    /// VarStoreId, `None` if the question has no storage
+    pub varstore: Option<u16>,
+    pub storage: QuestionStorage,
+    /// Device path text of the enclosing EFI_IFR_VARSTORE_DEVICE, `None` for
+    /// the storage of the HII handle
+    pub device: Option<HiiString>,
+    /// Storage width in bytes, bytes spanned by the field for bit storage
+    pub width: usize,
+    /// Flags of EFI_IFR_QUESTION_HEADER
+    pub flags: u8,
+    /// Flags specific to the question opcode
+    pub op_flags: u8,
+    pub options: Vec<QuestionOption>,
+    /// Minimum value (minimum size for strings and passwords)
+    pub min: u64,
+    /// Maximum value (maximum size for strings and passwords, containers for ordered lists)
+    pub max: u64,
+    pub step: u64,
+    pub defaults: Vec<QuestionDefault>,
+    /// EFI_IFR_VALUE expression
+    pub value: Option<Vec<IfrOp>>,
+    pub locked: bool,
+    /// Enclosing condition scopes, outermost first
+    pub conditions: Vec<Condition>,
+}
+
+impl Question {
+    /// Name of the storage of a question of a name/value varstore
+    pub fn storage_name(&self) -> Option<&str> {
+        match &self.storage {
+            QuestionStorage::Name(name) => name.text.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Device path text of the storage of a question in an EFI_IFR_VARSTORE_DEVICE scope
+    pub fn storage_device(&self) -> Option<&str> {
+        self.device
+            .as_ref()
+            .and_then(|device| device.text.as_deref())
+    }
+
+    /// First byte of the storage of a question of a buffer varstore
+    pub fn byte_offset(&self) -> Option<usize> {
+        match self.storage {
+            QuestionStorage::Offset(offset) => Some(offset as usize),
+            QuestionStorage::Bits { offset, .. } => Some(offset as usize / 8),
+            QuestionStorage::Name(_) | QuestionStorage::None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Subtitle {
+        offset: usize,
+        text: HiiString,
+        flags: u8,
+        conditions: Vec<Condition>,
+    },
+    Text {
+        offset: usize,
+        text: HiiString,
+        help: HiiString,
+        text_two: HiiString,
+        conditions: Vec<Condition>,
+    },
+    ResetButton {
+        offset: usize,
+        text: HiiString,
+        help: HiiString,
+        default_id: u16,
+        conditions: Vec<Condition>,
+    },
+    Question(Question),
+}
+
+impl Statement {
+    pub fn offset(&self) -> usize {
+        match self {
+            Statement::Subtitle { offset, .. }
+            | Statement::Text { offset, .. }
+            | Statement::ResetButton { offset, .. } => *offset,
+            Statement::Question(question) => question.offset,
+        }
+    }
+
+    pub fn prompt(&self) -> &HiiString {
+        match self {
+            Statement::Subtitle { text, .. }
+            | Statement::Text { text, .. }
+            | Statement::ResetButton { text, .. } => text,
+            Statement::Question(question) => &question.prompt,
+        }
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        match self {
+            Statement::Subtitle { conditions, .. }
+            | Statement::Text { conditions, .. }
+            | Statement::ResetButton { conditions, .. } => conditions,
+            Statement::Question(question) => &question.conditions,
+        }
+    }
+
+    pub fn question(&self) -> Option<&Question> {
+        match self {
+            Statement::Question(question) => Some(question),
+            _ => None,
+        }
+    }
+}
+
+/// EFI_IFR_RULE, an expression referenced by EFI_IFR_RULE_REF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub id: u8,
+    pub expression: Vec<IfrOp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form {
+    pub id: u16,
+    pub title: HiiString,
+    pub statements: Vec<Statement>,
+    /// Condition scopes enclosing the whole form
+    pub conditions: Vec<Condition>,
+    pub rules: Vec<Rule>,
+}
+
+impl Form {
+    pub fn questions(&self) -> impl Iterator<Item = &Question> {
+        self.statements.iter().filter_map(Statement::question)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSet {
+    pub guid: Guid,
+    pub title: HiiString,
+    pub help: HiiString,
+    pub class_guids: Vec<Guid>,
+    pub varstores: Vec<VarStore>,
+    pub default_stores: Vec<DefaultStore>,
+    pub forms: Vec<Form>,
+}
+
+impl FormSet {
+    /// Build the model of every formset of a tree
+    pub fn from_tree(tree: &IfrTree, strings: &StringTable) -> Vec<FormSet> {
+        tree.form_sets()
+            .filter_map(|node| Self::from_node(node, strings))
+            .collect()
+    }
+
+    /// Build the model of a formset from its EFI_IFR_FORM_SET node
+    pub fn from_node(node: &IfrNode, strings: &StringTable) -> Option<FormSet> {
+        let IfrOp::FormSet(op) = node.op() else {
+            return None;
+        };
+
+        let mut form_set = FormSet {
+            guid: op.guid,
+            title: HiiString::resolve(op.form_set_title, strings),
+            help: HiiString::resolve(op.help, strings),
+            class_guids: op.class_guid.clone(),
+            varstores: Vec::new(),
+            default_stores: Vec::new(),
+            forms: Vec::new(),
+        };
+        let mut parser = ModelParser {
+            strings,
+            conditions: Vec::new(),
+            rules: Vec::new(),
+            bit_storage: false,
+            device: None,
+        };
+        parser.form_set_children(&node.children, &mut form_set);
+        Some(form_set)
+    }
+
+    pub fn varstore(&self, id: u16) -> Option<&VarStore> {
+        self.varstores.iter().find(|varstore| varstore.id == id)
+    }
+
+    pub fn form(&self, id: u16) -> Option<&Form> {
+        self.forms.iter().find(|form| form.id == id)
+    }
+
+    /// Every question of every form
+    pub fn questions(&self) -> impl Iterator<Item = &Question> {
+        self.forms.iter().flat_map(Form::questions)
+    }
+
+    pub fn question(&self, id: u16) -> Option<&Question> {
+        self.questions().find(|question| question.id == id)
+    }
+
+    /// Find a question by its prompt text
+    pub fn question_by_prompt(&self, prompt: &str) -> Option<&Question> {
+        self.questions()
+            .find(|question| question.prompt.text.as_deref() == Some(prompt))
+    }
+
+    /// Find a question by the UEFI keyword of its prompt, `keywords` holds the
+    /// strings of the "x-UEFI-ns" language
+    pub fn question_by_keyword(&self, keywords: &StringTable, keyword: &str) -> Option<&Question> {
+        let id = keywords.find(keyword)?;
+        self.questions().find(|question| question.prompt.id == id)
+    }
+}
+
+/// Flatten expression nodes into postfix opcodes, closing nested scopes with END.
+pub(crate) fn flatten_expression(nodes: &[IfrNode], out: &mut Vec<IfrOp>) {
+    for node in nodes {
+        out.push(node.op().clone());
+        flatten_expression(&node.children, out);
+        if node.entry.scope {
+            out.push(IfrOp::End);
+        }
+    }
+}
+
+/// Split the children of a condition scope into its expression and its body.
+pub(crate) fn split_condition(children: &[IfrNode]) -> (Vec<IfrOp>, &[IfrNode]) {
+    let count = children
+        .iter()
+        .take_while(|node| is_expression_op_code(node.op().op_code() as u8))
+        .count();
+    let mut expression = Vec::new();
+    flatten_expression(&children[..count], &mut expression);
+    (expression, &children[count..])
+}
+
+fn condition_kind(op: &IfrOp) -> Option<ConditionKind> {
+    match op {
+        IfrOp::SuppressIf => Some(ConditionKind::SuppressIf),
+        IfrOp::GrayOutIf => Some(ConditionKind::GrayOutIf),
+        IfrOp::DisableIf => Some(ConditionKind::DisableIf),
+        _ => None,
+    }
+}
+
+struct ModelParser<'a> {
+    strings: &'a StringTable,
+    conditions: Vec<Condition>,
+    /// Rules of the form being parsed
+    rules: Vec<Rule>,
+    /// Inside the scope of an EDKII_IFR_BIT_VARSTORE_GUID opcode
+    bit_storage: bool,
+    /// Device path of the enclosing EFI_IFR_VARSTORE_DEVICE scope
+    device: Option<HiiString>,
+}
+
+impl ModelParser<'_> {
+    fn text(&self, id: u16) -> HiiString {
+        HiiString::resolve(id, self.strings)
+    }
+
+    /// Run `body` with the condition of `node` pushed, returns false if `node` is not a condition
+    fn with_condition<F>(&mut self, node: &IfrNode, body: F) -> bool
+    where
+        F: FnOnce(&mut Self, &[IfrNode]),
+    {
+        let Some(kind) = condition_kind(node.op()) else {
+            return false;
+        };
+        let (expression, children) = split_condition(&node.children);
+        self.conditions.push(Condition {
+            kind,
+            offset: node.offset(),
+            expression,
+        });
+        body(self, children);
+        self.conditions.pop();
+        true
+    }
+
+    fn form_set_children(&mut self, children: &[IfrNode], form_set: &mut FormSet) {
+        for node in children {
+            if self.with_condition(node, |parser, body| {
+                parser.form_set_children(body, form_set)
+            }) {
+                continue;
+            }
+
+            match node.op() {
+                IfrOp::VarStore(op) => form_set.varstores.push(VarStore {
+                    id: op.var_store_id,
+                    guid: op.guid,
+                    name: op.name.clone(),
+                    size: op.size,
+                    kind: VarStoreKind::Buffer,
+                }),
+                IfrOp::VarStoreEfi(op) => form_set.varstores.push(VarStore {
+                    id: op.var_store_id,
+                    guid: op.guid,
+                    name: op.name.clone(),
+                    size: op.size,
+                    kind: VarStoreKind::Efi {
+                        attributes: op.attributes,
+                    },
+                }),
+                IfrOp::VarStoreNameValue(op) => form_set.varstores.push(VarStore {
+                    id: op.var_store_id,
+                    guid: op.guid,
+                    name: String::new(),
+                    size: 0,
+                    kind: VarStoreKind::NameValue,
+                }),
+                IfrOp::DefaultStore {
+                    default_name,
+                    default_id,
+                } => form_set.default_stores.push(DefaultStore {
+                    id: *default_id,
+                    name: self.text(*default_name),
+                }),
+                IfrOp::Form(op) => {
+                    let mut form = Form {
+                        id: op.form_id,
+                        title: self.text(op.form_title),
+                        statements: Vec::new(),
+                        conditions: self.conditions.clone(),
+                        rules: Vec::new(),
+                    };
+                    let outer = core::mem::take(&mut self.conditions);
+                    self.statements(&node.children, form_set, &mut form.statements);
+                    self.conditions = outer;
+                    form.rules = core::mem::take(&mut self.rules);
+                    form_set.forms.push(form);
+                }
+                IfrOp::FormMap { form_id, methods } => {
+                    let mut form = Form {
+                        id: *form_id,
+                        title: methods
+                            .first()
+                            .map(|method| self.text(method.method_title))
+                            .unwrap_or_default(),
+                        statements: Vec::new(),
+                        conditions: self.conditions.clone(),
+                        rules: Vec::new(),
+                    };
+                    let outer = core::mem::take(&mut self.conditions);
+                    self.statements(&node.children, form_set, &mut form.statements);
+                    self.conditions = outer;
+                    form.rules = core::mem::take(&mut self.rules);
+                    form_set.forms.push(form);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn statements(&mut self, children: &[IfrNode], form_set: &FormSet, out: &mut Vec<Statement>) {
+        for node in children {
+            if self.with_condition(node, |parser, body| parser.statements(body, form_set, out)) {
+                continue;
+            }
+
+            match node.op() {
+                IfrOp::Subtitle { statement, flags } => out.push(Statement::Subtitle {
+                    offset: node.offset(),
+                    text: self.text(statement.prompt),
+                    flags: *flags,
+                    conditions: self.conditions.clone(),
+                }),
+                IfrOp::Text {
+                    statement,
+                    text_two,
+                } => out.push(Statement::Text {
+                    offset: node.offset(),
+                    text: self.text(statement.prompt),
+                    help: self.text(statement.help),
+                    text_two: self.text(*text_two),
+                    conditions: self.conditions.clone(),
+                }),
+                IfrOp::ResetButton {
+                    statement,
+                    default_id,
+                } => out.push(Statement::ResetButton {
+                    offset: node.offset(),
+                    text: self.text(statement.prompt),
+                    help: self.text(statement.help),
+                    default_id: *default_id,
+                    conditions: self.conditions.clone(),
+                }),
+                IfrOp::Rule { rule_id } => {
+                    let mut expression = Vec::new();
+                    flatten_expression(&node.children, &mut expression);
+                    self.rules.push(Rule {
+                        id: *rule_id,
+                        expression,
+                    });
+                    continue;
+                }
+                op if op.question().is_some() => {
+                    let question = self.question(node, form_set);
+                    out.push(Statement::Question(question));
+                    continue;
+                }
+                IfrOp::Guid {
+                    guid: EDKII_IFR_BIT_VARSTORE_GUID,
+                    ..
+                } => {
+                    let outer = core::mem::replace(&mut self.bit_storage, true);
+                    self.statements(&node.children, form_set, out);
+                    self.bit_storage = outer;
+                    continue;
+                }
+                IfrOp::VarStoreDevice { device_path } => {
+                    let device = Some(self.text(*device_path));
+                    let outer = core::mem::replace(&mut self.device, device);
+                    self.statements(&node.children, form_set, out);
+                    self.device = outer;
+                    continue;
+                }
+                _ => {}
+            }
+
+            // Statements may be grouped inside the scope of another statement
+            self.statements(&node.children, form_set, out);
+        }
+    }
+
+    fn question(&mut self, node: &IfrNode, form_set: &FormSet) -> Question {
+        let op = node.op();
+        let header = *op.question().unwrap();
+
+        let varstore = (header.var_store_id != 0).then_some(header.var_store_id);
+        let storage = match varstore.and_then(|id| form_set.varstore(id)) {
+            Some(VarStore {
+                kind: VarStoreKind::NameValue,
+                ..
+            }) => QuestionStorage::Name(self.text(header.var_store_info)),
+            Some(_) => QuestionStorage::Offset(header.var_store_info),
+            None => QuestionStorage::None,
+        };
+
+        let mut question = Question {
+            offset: node.offset(),
+            id: header.question_id,
+            kind: QuestionKind::Action,
+            prompt: self.text(header.header.prompt),
+            help: self.text(header.header.help),
+            varstore,
+            storage,
+            device: self.device.clone(),
+            width: 0,
+            flags: header.flags,
+            op_flags: 0,
+            options: Vec::new(),
+            min: 0,
+            max: 0,
+            step: 0,
+            defaults: Vec::new(),
+            value: None,
+            locked: false,
+            conditions: self.conditions.clone(),
+        };
+
+        match op {
+            IfrOp::OneOf(numeric) | IfrOp::Numeric(numeric) => {
+                question.kind = match op {
+                    IfrOp::OneOf(_) => QuestionKind::OneOf,
+                    _ => QuestionKind::Numeric,
+                };
+                question.width = numeric.width();
+                question.op_flags = numeric.flags;
+                question.min = numeric.data.min_value;
+                question.max = numeric.data.max_value;
+                question.step = numeric.data.step;
+            }
+            IfrOp::Checkbox(checkbox) => {
+                question.kind = QuestionKind::Checkbox;
+                question.width = 1;
+                question.op_flags = checkbox.flags;
+                question.max = 1;
+            }
+            IfrOp::Date(date) => {
+                question.kind = QuestionKind::Date;
+                // EFI_HII_DATE
+                question.width = 4;
+                question.op_flags = date.flags;
+            }
+            IfrOp::Time(time) => {
+                question.kind = QuestionKind::Time;
+                // EFI_HII_TIME
+                question.width = 3;
+                question.op_flags = time.flags;
+            }
+            IfrOp::String(string) => {
+                question.kind = QuestionKind::String;
+                question.width = string.max_size as usize * 2;
+                question.op_flags = string.flags;
+                question.min = string.min_size as u64;
+                question.max = string.max_size as u64;
+            }
+            IfrOp::Password(password) => {
+                question.kind = QuestionKind::Password;
+                question.width = password.max_size as usize * 2;
+                question.min = password.min_size as u64;
+                question.max = password.max_size as u64;
+            }
+            IfrOp::OrderedList(list) => {
+                question.kind = QuestionKind::OrderedList;
+                question.op_flags = list.flags;
+                question.max = list.max_containers as u64;
+            }
+            IfrOp::Ref(reference) => {
+                question.kind = QuestionKind::Ref {
+                    form_id: reference.form_id,
+                    question_id: reference.question_id,
+                    form_set_id: reference.form_set_id,
+                };
+                // EFI_HII_REF
+                question.width = 22;
+            }
+            _ => {}
+        }
+
+        // VarOffset of a bit varstore question is in bits, the flags of
+        // numerics and one-ofs give the width in bits
+        if let (true, QuestionStorage::Offset(offset)) = (self.bit_storage, &question.storage) {
+            let width = match op {
+                IfrOp::OneOf(numeric) | IfrOp::Numeric(numeric) => {
+                    numeric.flags & EDKII_IFR_NUMERIC_SIZE_BIT
+                }
+                _ => 1,
+            };
+            question.width = (*offset as usize % 8 + width as usize).div_ceil(8);
+            question.storage = QuestionStorage::Bits {
+                offset: *offset,
+                width,
+            };
+        }
+
+        let outer = core::mem::take(&mut self.conditions);
+        self.question_children(&node.children, &mut question);
+        self.conditions = outer;
+
+        if question.kind == QuestionKind::OrderedList {
+            // Every container holds one option value
+            let item_width = question
+                .options
+                .first()
+                .map_or(1, |option| value_width(&option.value));
+            question.width = question.max as usize * item_width;
+        }
+        question
+    }
+
+    fn question_children(&mut self, children: &[IfrNode], question: &mut Question) {
+        for node in children {
+            if self.with_condition(node, |parser, body| {
+                parser.question_children(body, question)
+            }) {
+                continue;
+            }
+
+            match node.op() {
+                IfrOp::OneOfOption(option) => question.options.push(QuestionOption {
+                    text: self.text(option.option),
+                    flags: option.flags,
+                    value: option.value.clone(),
+                    conditions: self.conditions.clone(),
+                }),
+                IfrOp::Default(default) => {
+                    let value = match default.value {
+                        IfrTypeValue::Other => {
+                            let mut expression = Vec::new();
+                            flatten_expression(&node.children, &mut expression);
+                            // EFI_IFR_VALUE only wraps the expression
+                            if expression.first() == Some(&IfrOp::Value) {
+                                expression.remove(0);
+                                expression.pop();
+                            }
+                            DefaultValue::Expression(expression)
+                        }
+                        ref value => DefaultValue::Value(value.clone()),
+                    };
+                    question.defaults.push(QuestionDefault {
+                        default_id: default.default_id,
+                        value,
+                    });
+                }
+                IfrOp::Value => {
+                    let mut expression = Vec::new();
+                    flatten_expression(&node.children, &mut expression);
+                    question.value = Some(expression);
+                }
+                IfrOp::Locked => question.locked = true,
+                // Device of the storage of this question only
+                IfrOp::VarStoreDevice { device_path } => {
+                    question.device = Some(self.text(*device_path))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Storage width of a typed value
+pub fn value_width(value: &IfrTypeValue) -> usize {
+    match value {
+        IfrTypeValue::U8(_) | IfrTypeValue::Boolean(_) => 1,
+        IfrTypeValue::U16(_) | IfrTypeValue::String(_) | IfrTypeValue::Action(_) => 2,
+        IfrTypeValue::U32(_) => 4,
+        IfrTypeValue::U64(_) => 8,
+        IfrTypeValue::Time { .. } => 3,
+        IfrTypeValue::Date { .. } => 4,
+        IfrTypeValue::Buffer(buffer) => buffer.len(),
+        IfrTypeValue::Ref(_) => 22,
+        IfrTypeValue::Other | IfrTypeValue::Undefined => 0,
+    }
+}
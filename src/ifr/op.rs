@@ -0,0 +1,1580 @@
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use uefi::Guid;
+
+use super::IfrError;
+use crate::package_header::ifr_parse::EfiIfrOpCode;
+
+/// EFI_IFR_FLAG_CALLBACK
+pub const EFI_IFR_FLAG_CALLBACK: u8 = 0x04;
+/// EFI_IFR_FLAG_RESET_REQUIRED
+pub const EFI_IFR_FLAG_RESET_REQUIRED: u8 = 0x10;
+/// EFI_IFR_FLAG_READ_ONLY
+pub const EFI_IFR_FLAG_READ_ONLY: u8 = 0x01;
+
+/// EFI_IFR_NUMERIC_SIZE mask of ONE_OF and NUMERIC flags
+pub const EFI_IFR_NUMERIC_SIZE: u8 = 0x03;
+/// EFI_IFR_DISPLAY mask of ONE_OF and NUMERIC flags
+pub const EFI_IFR_DISPLAY: u8 = 0x30;
+
+/// EFI_IFR_NUMERIC_SIZE_x values of ONE_OF and NUMERIC flags
+pub const EFI_IFR_NUMERIC_SIZE_1: u8 = 0x00;
+pub const EFI_IFR_NUMERIC_SIZE_2: u8 = 0x01;
+pub const EFI_IFR_NUMERIC_SIZE_4: u8 = 0x02;
+pub const EFI_IFR_NUMERIC_SIZE_8: u8 = 0x03;
+
+/// EFI_IFR_DISPLAY_x values of ONE_OF and NUMERIC flags
+pub const EFI_IFR_DISPLAY_INT_DEC: u8 = 0x00;
+pub const EFI_IFR_DISPLAY_UINT_DEC: u8 = 0x10;
+pub const EFI_IFR_DISPLAY_UINT_HEX: u8 = 0x20;
+
+/// EDKII_IFR_NUMERIC_SIZE_BIT mask, width in bits of ONE_OF and NUMERIC questions of a bit varstore
+pub const EDKII_IFR_NUMERIC_SIZE_BIT: u8 = 0x3F;
+/// EDKII_IFR_DISPLAY_BIT mask of ONE_OF and NUMERIC flags in a bit varstore
+pub const EDKII_IFR_DISPLAY_BIT: u8 = 0xC0;
+
+/// EDKII_IFR_DISPLAY_x_BIT values of ONE_OF and NUMERIC flags in a bit varstore
+pub const EDKII_IFR_DISPLAY_INT_DEC_BIT: u8 = 0x00;
+pub const EDKII_IFR_DISPLAY_UINT_DEC_BIT: u8 = 0x40;
+pub const EDKII_IFR_DISPLAY_UINT_HEX_BIT: u8 = 0x80;
+
+/// EFI_IFR_CHECKBOX_DEFAULT
+pub const EFI_IFR_CHECKBOX_DEFAULT: u8 = 0x01;
+/// EFI_IFR_CHECKBOX_DEFAULT_MFG
+pub const EFI_IFR_CHECKBOX_DEFAULT_MFG: u8 = 0x02;
+
+/// EFI_IFR_OPTION_DEFAULT
+pub const EFI_IFR_OPTION_DEFAULT: u8 = 0x10;
+/// EFI_IFR_OPTION_DEFAULT_MFG
+pub const EFI_IFR_OPTION_DEFAULT_MFG: u8 = 0x20;
+
+/// EFI_IFR_TYPE_x values of ONE_OF_OPTION and DEFAULT
+pub const EFI_IFR_TYPE_NUM_SIZE_8: u8 = 0x00;
+pub const EFI_IFR_TYPE_NUM_SIZE_16: u8 = 0x01;
+pub const EFI_IFR_TYPE_NUM_SIZE_32: u8 = 0x02;
+pub const EFI_IFR_TYPE_NUM_SIZE_64: u8 = 0x03;
+pub const EFI_IFR_TYPE_BOOLEAN: u8 = 0x04;
+pub const EFI_IFR_TYPE_TIME: u8 = 0x05;
+pub const EFI_IFR_TYPE_DATE: u8 = 0x06;
+pub const EFI_IFR_TYPE_STRING: u8 = 0x07;
+pub const EFI_IFR_TYPE_OTHER: u8 = 0x08;
+pub const EFI_IFR_TYPE_UNDEFINED: u8 = 0x09;
+pub const EFI_IFR_TYPE_ACTION: u8 = 0x0A;
+pub const EFI_IFR_TYPE_BUFFER: u8 = 0x0B;
+pub const EFI_IFR_TYPE_REF: u8 = 0x0C;
+
+/// Size of the opcode header (OpCode + Length/Scope)
+pub const EFI_IFR_OP_HEADER_SIZE: usize = 2;
+
+/// EFI_IFR_STATEMENT_HEADER.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IfrStatementHeader {
+    pub prompt: u16,
+    pub help: u16,
+}
+
+/// EFI_IFR_QUESTION_HEADER.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IfrQuestionHeader {
+    pub header: IfrStatementHeader,
+    pub question_id: u16,
+    pub var_store_id: u16,
+    /// VarStoreInfo: VarOffset for buffer varstores, VarName (string id) for name/value varstores
+    pub var_store_info: u16,
+    pub flags: u8,
+}
+
+/// MINMAXSTEP union of ONE_OF and NUMERIC, width given by [`IfrNumeric::data_width`]
+/// (always UINT32 for questions of a bit varstore).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IfrMinMaxStep {
+    pub min_value: u64,
+    pub max_value: u64,
+    pub step: u64,
+}
+
+/// EFI_HII_REF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrHiiRef {
+    pub question_id: u16,
+    pub form_id: u16,
+    pub form_set_guid: Guid,
+    pub device_path: u16,
+}
+
+/// EFI_IFR_TYPE_VALUE tagged with its EFI_IFR_TYPE_x.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfrTypeValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    Boolean(bool),
+    Time { hour: u8, minute: u8, second: u8 },
+    Date { year: u16, month: u8, day: u8 },
+    String(u16),
+    /// Value is given by the nested expression
+    Other,
+    Undefined,
+    Action(u16),
+    Buffer(Vec<u8>),
+    Ref(IfrHiiRef),
+}
+
+impl IfrTypeValue {
+    /// EFI_IFR_TYPE_x of the value
+    pub fn type_code(&self) -> u8 {
+        match self {
+            IfrTypeValue::U8(_) => EFI_IFR_TYPE_NUM_SIZE_8,
+            IfrTypeValue::U16(_) => EFI_IFR_TYPE_NUM_SIZE_16,
+            IfrTypeValue::U32(_) => EFI_IFR_TYPE_NUM_SIZE_32,
+            IfrTypeValue::U64(_) => EFI_IFR_TYPE_NUM_SIZE_64,
+            IfrTypeValue::Boolean(_) => EFI_IFR_TYPE_BOOLEAN,
+            IfrTypeValue::Time { .. } => EFI_IFR_TYPE_TIME,
+            IfrTypeValue::Date { .. } => EFI_IFR_TYPE_DATE,
+            IfrTypeValue::String(_) => EFI_IFR_TYPE_STRING,
+            IfrTypeValue::Other => EFI_IFR_TYPE_OTHER,
+            IfrTypeValue::Undefined => EFI_IFR_TYPE_UNDEFINED,
+            IfrTypeValue::Action(_) => EFI_IFR_TYPE_ACTION,
+            IfrTypeValue::Buffer(_) => EFI_IFR_TYPE_BUFFER,
+            IfrTypeValue::Ref(_) => EFI_IFR_TYPE_REF,
+        }
+    }
+
+    /// Numeric view of integer and boolean values
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            IfrTypeValue::U8(v) => Some(*v as u64),
+            IfrTypeValue::U16(v) => Some(*v as u64),
+            IfrTypeValue::U32(v) => Some(*v as u64),
+            IfrTypeValue::U64(v) => Some(*v),
+            IfrTypeValue::Boolean(v) => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    /// Decode the data of an EFI_IFR_TYPE_VALUE of type `r#type`
+    pub fn from_bytes(r#type: u8, data: &[u8]) -> Option<Self> {
+        Self::decode(r#type, &mut Reader::new(data))
+    }
+
+    /// Data of the EFI_IFR_TYPE_VALUE, without the type
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        self.encode(&mut writer);
+        writer.into_bytes()
+    }
+
+    /// Value data without the type byte
+    fn encode(&self, writer: &mut Writer) {
+        match self {
+            IfrTypeValue::U8(v) => writer.u8(*v),
+            IfrTypeValue::U16(v) => writer.u16(*v),
+            IfrTypeValue::U32(v) => writer.u32(*v),
+            IfrTypeValue::U64(v) => writer.u64(*v),
+            IfrTypeValue::Boolean(v) => writer.u8(*v as u8),
+            IfrTypeValue::Time {
+                hour,
+                minute,
+                second,
+            } => {
+                writer.u8(*hour);
+                writer.u8(*minute);
+                writer.u8(*second);
+            }
+            IfrTypeValue::Date { year, month, day } => {
+                writer.u16(*year);
+                writer.u8(*month);
+                writer.u8(*day);
+            }
+            IfrTypeValue::String(id) | IfrTypeValue::Action(id) => writer.u16(*id),
+            IfrTypeValue::Other | IfrTypeValue::Undefined => {}
+            IfrTypeValue::Buffer(buffer) => writer.bytes(buffer),
+            IfrTypeValue::Ref(reference) => {
+                writer.u16(reference.question_id);
+                writer.u16(reference.form_id);
+                writer.guid(&reference.form_set_guid);
+                writer.u16(reference.device_path);
+            }
+        }
+    }
+
+    fn decode(r#type: u8, reader: &mut Reader) -> Option<Self> {
+        Some(match r#type {
+            EFI_IFR_TYPE_NUM_SIZE_8 => IfrTypeValue::U8(reader.u8()?),
+            EFI_IFR_TYPE_NUM_SIZE_16 => IfrTypeValue::U16(reader.u16()?),
+            EFI_IFR_TYPE_NUM_SIZE_32 => IfrTypeValue::U32(reader.u32()?),
+            EFI_IFR_TYPE_NUM_SIZE_64 => IfrTypeValue::U64(reader.u64()?),
+            EFI_IFR_TYPE_BOOLEAN => IfrTypeValue::Boolean(reader.u8()? != 0),
+            EFI_IFR_TYPE_TIME => IfrTypeValue::Time {
+                hour: reader.u8()?,
+                minute: reader.u8()?,
+                second: reader.u8()?,
+            },
+            EFI_IFR_TYPE_DATE => IfrTypeValue::Date {
+                year: reader.u16()?,
+                month: reader.u8()?,
+                day: reader.u8()?,
+            },
+            EFI_IFR_TYPE_STRING => IfrTypeValue::String(reader.u16()?),
+            EFI_IFR_TYPE_OTHER => IfrTypeValue::Other,
+            EFI_IFR_TYPE_ACTION => IfrTypeValue::Action(reader.u16()?),
+            EFI_IFR_TYPE_BUFFER => IfrTypeValue::Buffer(reader.rest().to_vec()),
+            EFI_IFR_TYPE_REF => IfrTypeValue::Ref(IfrHiiRef {
+                question_id: reader.u16()?,
+                form_id: reader.u16()?,
+                form_set_guid: reader.guid()?,
+                device_path: reader.u16()?,
+            }),
+            _ => IfrTypeValue::Undefined,
+        })
+    }
+}
+
+/// EFI_IFR_FORM_SET.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrFormSet {
+    pub guid: Guid,
+    pub form_set_title: u16,
+    pub help: u16,
+    pub flags: u8,
+    pub class_guid: Vec<Guid>,
+}
+
+/// EFI_IFR_FORM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrForm {
+    pub form_id: u16,
+    pub form_title: u16,
+}
+
+/// EFI_IFR_ONE_OF and EFI_IFR_NUMERIC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrNumeric {
+    pub question: IfrQuestionHeader,
+    pub flags: u8,
+    pub data: IfrMinMaxStep,
+    /// Width of MINMAXSTEP in bytes when it differs from the storage width,
+    /// UINT32 for questions of a bit varstore
+    pub data_width: Option<usize>,
+}
+
+impl IfrNumeric {
+    /// Storage width in bytes given by EFI_IFR_NUMERIC_SIZE
+    pub fn width(&self) -> usize {
+        1 << (self.flags & EFI_IFR_NUMERIC_SIZE)
+    }
+
+    /// Width of MINMAXSTEP in bytes
+    pub fn data_width(&self) -> usize {
+        self.data_width.unwrap_or(self.width())
+    }
+}
+
+/// EFI_IFR_CHECKBOX, EFI_IFR_DATE and EFI_IFR_TIME.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrFlaggedQuestion {
+    pub question: IfrQuestionHeader,
+    pub flags: u8,
+}
+
+/// EFI_IFR_PASSWORD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrPassword {
+    pub question: IfrQuestionHeader,
+    pub min_size: u16,
+    pub max_size: u16,
+}
+
+/// EFI_IFR_STRING.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrString {
+    pub question: IfrQuestionHeader,
+    pub min_size: u8,
+    pub max_size: u8,
+    pub flags: u8,
+}
+
+/// EFI_IFR_ORDERED_LIST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrOrderedList {
+    pub question: IfrQuestionHeader,
+    pub max_containers: u8,
+    pub flags: u8,
+}
+
+/// EFI_IFR_ACTION (`question_config` is absent in EFI_IFR_ACTION_1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrAction {
+    pub question: IfrQuestionHeader,
+    pub question_config: Option<u16>,
+}
+
+/// EFI_IFR_REF .. EFI_IFR_REF5, every following field requires the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrRef {
+    pub question: IfrQuestionHeader,
+    pub form_id: Option<u16>,
+    pub question_id: Option<u16>,
+    pub form_set_id: Option<Guid>,
+    pub device_path: Option<u16>,
+}
+
+/// EFI_IFR_ONE_OF_OPTION.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrOneOfOption {
+    pub option: u16,
+    pub flags: u8,
+    pub value: IfrTypeValue,
+}
+
+/// EFI_IFR_VARSTORE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrVarStore {
+    pub guid: Guid,
+    pub var_store_id: u16,
+    pub size: u16,
+    pub name: String,
+}
+
+/// EFI_IFR_VARSTORE_NAME_VALUE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrVarStoreNameValue {
+    pub var_store_id: u16,
+    pub guid: Guid,
+}
+
+/// EFI_IFR_VARSTORE_EFI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrVarStoreEfi {
+    pub var_store_id: u16,
+    pub guid: Guid,
+    pub attributes: u32,
+    pub size: u16,
+    pub name: String,
+}
+
+/// EFI_IFR_DEFAULT (`value` is [`IfrTypeValue::Other`] for EFI_IFR_DEFAULT_2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrDefault {
+    pub default_id: u16,
+    pub value: IfrTypeValue,
+}
+
+/// EFI_IFR_FORM_MAP_METHOD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IfrFormMapMethod {
+    pub method_title: u16,
+    pub method_identifier: Guid,
+}
+
+/// Decoded IFR opcode, one variant per [`EfiIfrOpCode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfrOp {
+    Form(IfrForm),
+    Subtitle { statement: IfrStatementHeader, flags: u8 },
+    Text { statement: IfrStatementHeader, text_two: u16 },
+    Image { id: u16 },
+    OneOf(IfrNumeric),
+    Checkbox(IfrFlaggedQuestion),
+    Numeric(IfrNumeric),
+    Password(IfrPassword),
+    OneOfOption(IfrOneOfOption),
+    SuppressIf,
+    Locked,
+    Action(IfrAction),
+    ResetButton { statement: IfrStatementHeader, default_id: u16 },
+    FormSet(IfrFormSet),
+    Ref(IfrRef),
+    NoSubmitIf { error: u16 },
+    InconsistentIf { error: u16 },
+    EqIdVal { question_id: u16, value: u16 },
+    EqIdId { question_id_1: u16, question_id_2: u16 },
+    EqIdValList { question_id: u16, value_list: Vec<u16> },
+    And,
+    Or,
+    Not,
+    Rule { rule_id: u8 },
+    GrayOutIf,
+    Date(IfrFlaggedQuestion),
+    Time(IfrFlaggedQuestion),
+    String(IfrString),
+    Refresh { refresh_interval: u8 },
+    DisableIf,
+    Animation { id: u16 },
+    ToLower,
+    ToUpper,
+    Map,
+    OrderedList(IfrOrderedList),
+    VarStore(IfrVarStore),
+    VarStoreNameValue(IfrVarStoreNameValue),
+    VarStoreEfi(IfrVarStoreEfi),
+    VarStoreDevice { device_path: u16 },
+    Version,
+    End,
+    Match,
+    Get { var_store_id: u16, var_store_info: u16, var_store_type: u8 },
+    Set { var_store_id: u16, var_store_info: u16, var_store_type: u8 },
+    Read,
+    Write,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterEqual,
+    LessThan,
+    LessEqual,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseNot,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    RuleRef { rule_id: u8 },
+    QuestionRef1 { question_id: u16 },
+    QuestionRef2,
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    True,
+    False,
+    ToUint,
+    ToString { format: u8 },
+    ToBoolean,
+    Mid,
+    Find { format: u8 },
+    Token,
+    StringRef1 { string_id: u16 },
+    StringRef2,
+    Conditional,
+    /// EFI_IFR_QUESTION_REF3, _2 adds `device_path`, _3 adds `guid`
+    QuestionRef3 { device_path: Option<u16>, guid: Option<Guid> },
+    Zero,
+    One,
+    Ones,
+    Undefined,
+    Length,
+    Dup,
+    This,
+    Span { flags: u8 },
+    Value,
+    Default(IfrDefault),
+    DefaultStore { default_name: u16, default_id: u16 },
+    FormMap { form_id: u16, methods: Vec<IfrFormMapMethod> },
+    Catenate,
+    Guid { guid: Guid, data: Vec<u8> },
+    Security { permissions: Guid },
+    ModalTag,
+    RefreshId { refresh_event_group_id: Guid },
+    WarningIf { warning: u16, time_out: u8 },
+    Match2 { syntax_type: Guid },
+}
+
+/// Little-endian reader over the data of a single opcode.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.data.split_first_chunk::<N>()?;
+        self.data = tail;
+        Some(*head)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.take::<2>().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.take::<4>().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.take::<8>().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn guid(&mut self) -> Option<Guid> {
+        self.take::<16>().map(Guid::from_bytes)
+    }
+
+    /// Unsigned value of `width` bytes (1, 2, 4 or 8)
+    pub(crate) fn uint(&mut self, width: usize) -> Option<u64> {
+        match width {
+            1 => self.u8().map(u64::from),
+            2 => self.u16().map(u64::from),
+            4 => self.u32().map(u64::from),
+            8 => self.u64(),
+            _ => None,
+        }
+    }
+
+    /// NUL-terminated ASCII string, the terminator is consumed
+    pub(crate) fn ascii(&mut self) -> String {
+        let len = self.data.iter().position(|b| *b == 0).unwrap_or(self.data.len());
+        let name = self.data[..len].iter().map(|b| *b as char).collect();
+        self.data = &self.data[(len + 1).min(self.data.len())..];
+        name
+    }
+
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let rest = self.data;
+        self.data = &[];
+        rest
+    }
+}
+
+/// Little-endian writer building the data of a single opcode.
+pub(crate) struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn guid(&mut self, value: &Guid) {
+        self.data.extend_from_slice(&value.to_bytes());
+    }
+
+    /// Unsigned value truncated to `width` bytes (1, 2, 4 or 8)
+    pub(crate) fn uint(&mut self, width: usize, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes()[..width.min(8)]);
+    }
+
+    /// NUL-terminated ASCII string
+    pub(crate) fn ascii(&mut self, value: &str) {
+        self.data.extend(value.chars().map(|c| c as u8));
+        self.data.push(0);
+    }
+
+    pub(crate) fn bytes(&mut self, value: &[u8]) {
+        self.data.extend_from_slice(value);
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl IfrStatementHeader {
+    fn encode(&self, writer: &mut Writer) {
+        writer.u16(self.prompt);
+        writer.u16(self.help);
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Self> {
+        Some(Self {
+            prompt: reader.u16()?,
+            help: reader.u16()?,
+        })
+    }
+}
+
+impl IfrQuestionHeader {
+    /// Question without storage and flags
+    pub fn new(question_id: u16, prompt: u16, help: u16) -> Self {
+        Self {
+            header: IfrStatementHeader { prompt, help },
+            question_id,
+            ..Default::default()
+        }
+    }
+
+    /// Store the question in `var_store_id` at offset (or name) `var_store_info`
+    pub fn with_storage(mut self, var_store_id: u16, var_store_info: u16) -> Self {
+        self.var_store_id = var_store_id;
+        self.var_store_info = var_store_info;
+        self
+    }
+
+    pub fn with_flags(mut self, flags: u8) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    fn encode(&self, writer: &mut Writer) {
+        self.header.encode(writer);
+        writer.u16(self.question_id);
+        writer.u16(self.var_store_id);
+        writer.u16(self.var_store_info);
+        writer.u8(self.flags);
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Self> {
+        Some(Self {
+            header: IfrStatementHeader::decode(reader)?,
+            question_id: reader.u16()?,
+            var_store_id: reader.u16()?,
+            var_store_info: reader.u16()?,
+            flags: reader.u8()?,
+        })
+    }
+}
+
+impl IfrNumeric {
+    fn encode(&self, writer: &mut Writer) {
+        self.question.encode(writer);
+        writer.u8(self.flags);
+        let width = self.data_width();
+        writer.uint(width, self.data.min_value);
+        writer.uint(width, self.data.max_value);
+        writer.uint(width, self.data.step);
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Self> {
+        let question = IfrQuestionHeader::decode(reader)?;
+        let flags = reader.u8()?;
+        // Bit varstore questions reuse the flags for the bit width, the
+        // length of the opcode tells the width of MINMAXSTEP
+        let flags_width = 1 << (flags & EFI_IFR_NUMERIC_SIZE);
+        let width = match reader.len() {
+            len @ (3 | 6 | 12 | 24) => len / 3,
+            _ => flags_width,
+        };
+        Some(Self {
+            question,
+            flags,
+            data: IfrMinMaxStep {
+                min_value: reader.uint(width)?,
+                max_value: reader.uint(width)?,
+                step: reader.uint(width)?,
+            },
+            data_width: (width != flags_width).then_some(width),
+        })
+    }
+}
+
+impl IfrFlaggedQuestion {
+    fn encode(&self, writer: &mut Writer) {
+        self.question.encode(writer);
+        writer.u8(self.flags);
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Self> {
+        Some(Self {
+            question: IfrQuestionHeader::decode(reader)?,
+            flags: reader.u8()?,
+        })
+    }
+}
+
+impl IfrOp {
+    /// Decode a single opcode; `bytes` starts with EFI_IFR_OP_HEADER and holds
+    /// at least the whole opcode.
+    pub fn decode(bytes: &[u8]) -> Result<IfrOp, IfrError> {
+        if bytes.len() < EFI_IFR_OP_HEADER_SIZE {
+            return Err(IfrError::Truncated { offset: 0 });
+        }
+        let length = (bytes[1] & 0x7f) as usize;
+        if length < EFI_IFR_OP_HEADER_SIZE || length > bytes.len() {
+            return Err(IfrError::Truncated { offset: 0 });
+        }
+
+        let op_code = EfiIfrOpCode::try_from(bytes[0]).map_err(|op_code| {
+            IfrError::UnknownOpCode { offset: 0, op_code }
+        })?;
+
+        let mut reader = Reader::new(&bytes[EFI_IFR_OP_HEADER_SIZE..length]);
+        Self::decode_data(op_code, &mut reader).ok_or(IfrError::ShortOpCode {
+            offset: 0,
+            op_code,
+            length,
+        })
+    }
+
+    fn decode_data(op_code: EfiIfrOpCode, r: &mut Reader) -> Option<IfrOp> {
+        use EfiIfrOpCode as Op;
+
+        Some(match op_code {
+            Op::FormOp => IfrOp::Form(IfrForm {
+                form_id: r.u16()?,
+                form_title: r.u16()?,
+            }),
+            Op::SubtitleOp => IfrOp::Subtitle {
+                statement: IfrStatementHeader::decode(r)?,
+                flags: r.u8()?,
+            },
+            Op::TextOp => IfrOp::Text {
+                statement: IfrStatementHeader::decode(r)?,
+                text_two: r.u16()?,
+            },
+            Op::ImageOp => IfrOp::Image { id: r.u16()? },
+            Op::OneOfOp => IfrOp::OneOf(IfrNumeric::decode(r)?),
+            Op::CheckboxOp => IfrOp::Checkbox(IfrFlaggedQuestion::decode(r)?),
+            Op::NumericOp => IfrOp::Numeric(IfrNumeric::decode(r)?),
+            Op::PasswordOp => IfrOp::Password(IfrPassword {
+                question: IfrQuestionHeader::decode(r)?,
+                min_size: r.u16()?,
+                max_size: r.u16()?,
+            }),
+            Op::OneOfOptionOp => {
+                let option = r.u16()?;
+                let flags = r.u8()?;
+                let r#type = r.u8()?;
+                IfrOp::OneOfOption(IfrOneOfOption {
+                    option,
+                    flags,
+                    value: IfrTypeValue::decode(r#type, r)?,
+                })
+            }
+            Op::SuppressIfOp => IfrOp::SuppressIf,
+            Op::LockedOp => IfrOp::Locked,
+            Op::ActionOp => IfrOp::Action(IfrAction {
+                question: IfrQuestionHeader::decode(r)?,
+                question_config: r.u16(),
+            }),
+            Op::ResetButtonOp => IfrOp::ResetButton {
+                statement: IfrStatementHeader::decode(r)?,
+                default_id: r.u16()?,
+            },
+            Op::FormSetOp => {
+                let guid = r.guid()?;
+                let form_set_title = r.u16()?;
+                let help = r.u16()?;
+                let flags = r.u8()?;
+                let class_guid = (0..(flags & 0x03))
+                    .map(|_| r.guid())
+                    .collect::<Option<Vec<Guid>>>()?;
+                IfrOp::FormSet(IfrFormSet {
+                    guid,
+                    form_set_title,
+                    help,
+                    flags,
+                    class_guid,
+                })
+            }
+            Op::RefOp => {
+                let question = IfrQuestionHeader::decode(r)?;
+                let form_id = r.u16();
+                let question_id = form_id.and_then(|_| r.u16());
+                let form_set_id = question_id.and_then(|_| r.guid());
+                let device_path = form_set_id.and_then(|_| r.u16());
+                IfrOp::Ref(IfrRef {
+                    question,
+                    form_id,
+                    question_id,
+                    form_set_id,
+                    device_path,
+                })
+            }
+            Op::NoSubmitIfOp => IfrOp::NoSubmitIf { error: r.u16()? },
+            Op::InconsistentIfOp => IfrOp::InconsistentIf { error: r.u16()? },
+            Op::EqIdValOp => IfrOp::EqIdVal {
+                question_id: r.u16()?,
+                value: r.u16()?,
+            },
+            Op::EqIdIdOp => IfrOp::EqIdId {
+                question_id_1: r.u16()?,
+                question_id_2: r.u16()?,
+            },
+            Op::EqIdValListOp => {
+                let question_id = r.u16()?;
+                let list_length = r.u16()?;
+                let value_list = (0..list_length)
+                    .map(|_| r.u16())
+                    .collect::<Option<Vec<u16>>>()?;
+                IfrOp::EqIdValList {
+                    question_id,
+                    value_list,
+                }
+            }
+            Op::AndOp => IfrOp::And,
+            Op::OrOp => IfrOp::Or,
+            Op::NotOp => IfrOp::Not,
+            Op::RuleOp => IfrOp::Rule { rule_id: r.u8()? },
+            Op::GrayOutIfOp => IfrOp::GrayOutIf,
+            Op::DateOp => IfrOp::Date(IfrFlaggedQuestion::decode(r)?),
+            Op::TimeOp => IfrOp::Time(IfrFlaggedQuestion::decode(r)?),
+            Op::StringOp => IfrOp::String(IfrString {
+                question: IfrQuestionHeader::decode(r)?,
+                min_size: r.u8()?,
+                max_size: r.u8()?,
+                flags: r.u8()?,
+            }),
+            Op::RefreshOp => IfrOp::Refresh {
+                refresh_interval: r.u8()?,
+            },
+            Op::DisableIfOp => IfrOp::DisableIf,
+            Op::AnimationOp => IfrOp::Animation { id: r.u16()? },
+            Op::ToLowerOp => IfrOp::ToLower,
+            Op::ToUpperOp => IfrOp::ToUpper,
+            Op::MapOp => IfrOp::Map,
+            Op::OrderedListOp => IfrOp::OrderedList(IfrOrderedList {
+                question: IfrQuestionHeader::decode(r)?,
+                max_containers: r.u8()?,
+                flags: r.u8()?,
+            }),
+            Op::VarstoreOp => IfrOp::VarStore(IfrVarStore {
+                guid: r.guid()?,
+                var_store_id: r.u16()?,
+                size: r.u16()?,
+                name: r.ascii(),
+            }),
+            Op::VarstoreNameValueOp => IfrOp::VarStoreNameValue(IfrVarStoreNameValue {
+                var_store_id: r.u16()?,
+                guid: r.guid()?,
+            }),
+            Op::VarstoreEfiOp => {
+                let var_store_id = r.u16()?;
+                let guid = r.guid()?;
+                let attributes = r.u32()?;
+                // Size and Name are absent in the UEFI 2.1 layout
+                let size = if r.is_empty() { 0 } else { r.u16()? };
+                IfrOp::VarStoreEfi(IfrVarStoreEfi {
+                    var_store_id,
+                    guid,
+                    attributes,
+                    size,
+                    name: r.ascii(),
+                })
+            }
+            Op::VarstoreDeviceOp => IfrOp::VarStoreDevice {
+                device_path: r.u16()?,
+            },
+            Op::VersionOp => IfrOp::Version,
+            Op::EndOp => IfrOp::End,
+            Op::MatchOp => IfrOp::Match,
+            Op::GetOp => IfrOp::Get {
+                var_store_id: r.u16()?,
+                var_store_info: r.u16()?,
+                var_store_type: r.u8()?,
+            },
+            Op::SetOp => IfrOp::Set {
+                var_store_id: r.u16()?,
+                var_store_info: r.u16()?,
+                var_store_type: r.u8()?,
+            },
+            Op::ReadOp => IfrOp::Read,
+            Op::WriteOp => IfrOp::Write,
+            Op::EqualOp => IfrOp::Equal,
+            Op::NotEqualOp => IfrOp::NotEqual,
+            Op::GreaterThanOp => IfrOp::GreaterThan,
+            Op::GreaterEqualOp => IfrOp::GreaterEqual,
+            Op::LessThanOp => IfrOp::LessThan,
+            Op::LessEqualOp => IfrOp::LessEqual,
+            Op::BitwiseAndOp => IfrOp::BitwiseAnd,
+            Op::BitwiseOrOp => IfrOp::BitwiseOr,
+            Op::BitwiseNotOp => IfrOp::BitwiseNot,
+            Op::ShiftLeftOp => IfrOp::ShiftLeft,
+            Op::ShiftRightOp => IfrOp::ShiftRight,
+            Op::AddOp => IfrOp::Add,
+            Op::SubtractOp => IfrOp::Subtract,
+            Op::MultiplyOp => IfrOp::Multiply,
+            Op::DivideOp => IfrOp::Divide,
+            Op::ModuloOp => IfrOp::Modulo,
+            Op::RuleRefOp => IfrOp::RuleRef { rule_id: r.u8()? },
+            Op::QuestionRef1Op => IfrOp::QuestionRef1 {
+                question_id: r.u16()?,
+            },
+            Op::QuestionRef2Op => IfrOp::QuestionRef2,
+            Op::Uint8Op => IfrOp::Uint8(r.u8()?),
+            Op::Uint16Op => IfrOp::Uint16(r.u16()?),
+            Op::Uint32Op => IfrOp::Uint32(r.u32()?),
+            Op::Uint64Op => IfrOp::Uint64(r.u64()?),
+            Op::TrueOp => IfrOp::True,
+            Op::FalseOp => IfrOp::False,
+            Op::ToUintOp => IfrOp::ToUint,
+            Op::ToStringOp => IfrOp::ToString { format: r.u8()? },
+            Op::ToBooleanOp => IfrOp::ToBoolean,
+            Op::MidOp => IfrOp::Mid,
+            Op::FindOp => IfrOp::Find { format: r.u8()? },
+            Op::TokenOp => IfrOp::Token,
+            Op::StringRef1Op => IfrOp::StringRef1 {
+                string_id: r.u16()?,
+            },
+            Op::StringRef2Op => IfrOp::StringRef2,
+            Op::ConditionalOp => IfrOp::Conditional,
+            Op::QuestionRef3Op => {
+                let device_path = r.u16();
+                let guid = device_path.and_then(|_| r.guid());
+                IfrOp::QuestionRef3 { device_path, guid }
+            }
+            Op::ZeroOp => IfrOp::Zero,
+            Op::OneOp => IfrOp::One,
+            Op::OnesOp => IfrOp::Ones,
+            Op::UndefinedOp => IfrOp::Undefined,
+            Op::LengthOp => IfrOp::Length,
+            Op::DupOp => IfrOp::Dup,
+            Op::ThisOp => IfrOp::This,
+            Op::SpanOp => IfrOp::Span { flags: r.u8()? },
+            Op::ValueOp => IfrOp::Value,
+            Op::DefaultOp => {
+                let default_id = r.u16()?;
+                let r#type = r.u8()?;
+                IfrOp::Default(IfrDefault {
+                    default_id,
+                    value: IfrTypeValue::decode(r#type, r)?,
+                })
+            }
+            Op::DefaultStoreOp => IfrOp::DefaultStore {
+                default_name: r.u16()?,
+                default_id: r.u16()?,
+            },
+            Op::FormMapOp => {
+                let form_id = r.u16()?;
+                let mut methods = Vec::new();
+                while !r.is_empty() {
+                    methods.push(IfrFormMapMethod {
+                        method_title: r.u16()?,
+                        method_identifier: r.guid()?,
+                    });
+                }
+                IfrOp::FormMap { form_id, methods }
+            }
+            Op::CatenateOp => IfrOp::Catenate,
+            Op::GuidOp => IfrOp::Guid {
+                guid: r.guid()?,
+                data: r.rest().to_vec(),
+            },
+            Op::SecurityOp => IfrOp::Security {
+                permissions: r.guid()?,
+            },
+            Op::ModalTagOp => IfrOp::ModalTag,
+            Op::RefreshIdOp => IfrOp::RefreshId {
+                refresh_event_group_id: r.guid()?,
+            },
+            Op::WarningIfOp => IfrOp::WarningIf {
+                warning: r.u16()?,
+                time_out: r.u8()?,
+            },
+            Op::Match2Op => IfrOp::Match2 {
+                syntax_type: r.guid()?,
+            },
+        })
+    }
+
+    /// Encode the opcode with its EFI_IFR_OP_HEADER, inverse of [`IfrOp::decode`].
+    ///
+    /// `scope` sets the scope bit; the matching EFI_IFR_END is up to the caller.
+    pub fn encode(&self, scope: bool) -> Result<Vec<u8>, IfrError> {
+        let mut writer = Writer::new();
+        writer.u8(self.op_code() as u8);
+        writer.u8(0);
+        self.encode_data(&mut writer);
+
+        let mut bytes = writer.into_bytes();
+        if bytes.len() > 0x7f {
+            return Err(IfrError::Oversized {
+                offset: 0,
+                op_code: self.op_code(),
+                length: bytes.len(),
+            });
+        }
+        bytes[1] = bytes.len() as u8 | if scope { 0x80 } else { 0 };
+        Ok(bytes)
+    }
+
+    fn encode_data(&self, w: &mut Writer) {
+        match self {
+            IfrOp::Form(op) => {
+                w.u16(op.form_id);
+                w.u16(op.form_title);
+            }
+            IfrOp::Subtitle { statement, flags } => {
+                statement.encode(w);
+                w.u8(*flags);
+            }
+            IfrOp::Text {
+                statement,
+                text_two,
+            } => {
+                statement.encode(w);
+                w.u16(*text_two);
+            }
+            IfrOp::Image { id } | IfrOp::Animation { id } => w.u16(*id),
+            IfrOp::OneOf(op) | IfrOp::Numeric(op) => op.encode(w),
+            IfrOp::Checkbox(op) | IfrOp::Date(op) | IfrOp::Time(op) => op.encode(w),
+            IfrOp::Password(op) => {
+                op.question.encode(w);
+                w.u16(op.min_size);
+                w.u16(op.max_size);
+            }
+            IfrOp::OneOfOption(op) => {
+                w.u16(op.option);
+                w.u8(op.flags);
+                w.u8(op.value.type_code());
+                op.value.encode(w);
+            }
+            IfrOp::Action(op) => {
+                op.question.encode(w);
+                if let Some(config) = op.question_config {
+                    w.u16(config);
+                }
+            }
+            IfrOp::ResetButton {
+                statement,
+                default_id,
+            } => {
+                statement.encode(w);
+                w.u16(*default_id);
+            }
+            IfrOp::FormSet(op) => {
+                let class_guid = &op.class_guid[..op.class_guid.len().min(3)];
+                w.guid(&op.guid);
+                w.u16(op.form_set_title);
+                w.u16(op.help);
+                w.u8((op.flags & !0x03) | class_guid.len() as u8);
+                class_guid.iter().for_each(|guid| w.guid(guid));
+            }
+            IfrOp::Ref(op) => {
+                op.question.encode(w);
+                // REF2..REF5 extend REF by one field each
+                if let Some(form_id) = op.form_id {
+                    w.u16(form_id);
+                    if let Some(question_id) = op.question_id {
+                        w.u16(question_id);
+                        if let Some(form_set_id) = op.form_set_id {
+                            w.guid(&form_set_id);
+                            if let Some(device_path) = op.device_path {
+                                w.u16(device_path);
+                            }
+                        }
+                    }
+                }
+            }
+            IfrOp::NoSubmitIf { error } | IfrOp::InconsistentIf { error } => w.u16(*error),
+            IfrOp::EqIdVal { question_id, value } => {
+                w.u16(*question_id);
+                w.u16(*value);
+            }
+            IfrOp::EqIdId {
+                question_id_1,
+                question_id_2,
+            } => {
+                w.u16(*question_id_1);
+                w.u16(*question_id_2);
+            }
+            IfrOp::EqIdValList {
+                question_id,
+                value_list,
+            } => {
+                w.u16(*question_id);
+                w.u16(value_list.len() as u16);
+                value_list.iter().for_each(|value| w.u16(*value));
+            }
+            IfrOp::Rule { rule_id } | IfrOp::RuleRef { rule_id } => w.u8(*rule_id),
+            IfrOp::String(op) => {
+                op.question.encode(w);
+                w.u8(op.min_size);
+                w.u8(op.max_size);
+                w.u8(op.flags);
+            }
+            IfrOp::Refresh { refresh_interval } => w.u8(*refresh_interval),
+            IfrOp::OrderedList(op) => {
+                op.question.encode(w);
+                w.u8(op.max_containers);
+                w.u8(op.flags);
+            }
+            IfrOp::VarStore(op) => {
+                w.guid(&op.guid);
+                w.u16(op.var_store_id);
+                w.u16(op.size);
+                w.ascii(&op.name);
+            }
+            IfrOp::VarStoreNameValue(op) => {
+                w.u16(op.var_store_id);
+                w.guid(&op.guid);
+            }
+            IfrOp::VarStoreEfi(op) => {
+                w.u16(op.var_store_id);
+                w.guid(&op.guid);
+                w.u32(op.attributes);
+                w.u16(op.size);
+                w.ascii(&op.name);
+            }
+            IfrOp::VarStoreDevice { device_path } => w.u16(*device_path),
+            IfrOp::Get {
+                var_store_id,
+                var_store_info,
+                var_store_type,
+            }
+            | IfrOp::Set {
+                var_store_id,
+                var_store_info,
+                var_store_type,
+            } => {
+                w.u16(*var_store_id);
+                w.u16(*var_store_info);
+                w.u8(*var_store_type);
+            }
+            IfrOp::QuestionRef1 { question_id } => w.u16(*question_id),
+            IfrOp::Uint8(v) => w.u8(*v),
+            IfrOp::Uint16(v) => w.u16(*v),
+            IfrOp::Uint32(v) => w.u32(*v),
+            IfrOp::Uint64(v) => w.u64(*v),
+            IfrOp::ToString { format } | IfrOp::Find { format } => w.u8(*format),
+            IfrOp::StringRef1 { string_id } => w.u16(*string_id),
+            IfrOp::QuestionRef3 { device_path, guid } => match (device_path, guid) {
+                (Some(device_path), Some(guid)) => {
+                    w.u16(*device_path);
+                    w.guid(guid);
+                }
+                (Some(device_path), None) => w.u16(*device_path),
+                _ => {}
+            },
+            IfrOp::Span { flags } => w.u8(*flags),
+            IfrOp::Default(op) => {
+                w.u16(op.default_id);
+                w.u8(op.value.type_code());
+                op.value.encode(w);
+            }
+            IfrOp::DefaultStore {
+                default_name,
+                default_id,
+            } => {
+                w.u16(*default_name);
+                w.u16(*default_id);
+            }
+            IfrOp::FormMap { form_id, methods } => {
+                w.u16(*form_id);
+                for method in methods {
+                    w.u16(method.method_title);
+                    w.guid(&method.method_identifier);
+                }
+            }
+            IfrOp::Guid { guid, data } => {
+                w.guid(guid);
+                w.bytes(data);
+            }
+            IfrOp::Security { permissions } => w.guid(permissions),
+            IfrOp::RefreshId {
+                refresh_event_group_id,
+            } => w.guid(refresh_event_group_id),
+            IfrOp::WarningIf { warning, time_out } => {
+                w.u16(*warning);
+                w.u8(*time_out);
+            }
+            IfrOp::Match2 { syntax_type } => w.guid(syntax_type),
+            // Opcodes without data
+            _ => {}
+        }
+    }
+
+    /// Opcode value of the decoded opcode
+    pub fn op_code(&self) -> EfiIfrOpCode {
+        use EfiIfrOpCode as Op;
+
+        match self {
+            IfrOp::Form(_) => Op::FormOp,
+            IfrOp::Subtitle { .. } => Op::SubtitleOp,
+            IfrOp::Text { .. } => Op::TextOp,
+            IfrOp::Image { .. } => Op::ImageOp,
+            IfrOp::OneOf(_) => Op::OneOfOp,
+            IfrOp::Checkbox(_) => Op::CheckboxOp,
+            IfrOp::Numeric(_) => Op::NumericOp,
+            IfrOp::Password(_) => Op::PasswordOp,
+            IfrOp::OneOfOption(_) => Op::OneOfOptionOp,
+            IfrOp::SuppressIf => Op::SuppressIfOp,
+            IfrOp::Locked => Op::LockedOp,
+            IfrOp::Action(_) => Op::ActionOp,
+            IfrOp::ResetButton { .. } => Op::ResetButtonOp,
+            IfrOp::FormSet(_) => Op::FormSetOp,
+            IfrOp::Ref(_) => Op::RefOp,
+            IfrOp::NoSubmitIf { .. } => Op::NoSubmitIfOp,
+            IfrOp::InconsistentIf { .. } => Op::InconsistentIfOp,
+            IfrOp::EqIdVal { .. } => Op::EqIdValOp,
+            IfrOp::EqIdId { .. } => Op::EqIdIdOp,
+            IfrOp::EqIdValList { .. } => Op::EqIdValListOp,
+            IfrOp::And => Op::AndOp,
+            IfrOp::Or => Op::OrOp,
+            IfrOp::Not => Op::NotOp,
+            IfrOp::Rule { .. } => Op::RuleOp,
+            IfrOp::GrayOutIf => Op::GrayOutIfOp,
+            IfrOp::Date(_) => Op::DateOp,
+            IfrOp::Time(_) => Op::TimeOp,
+            IfrOp::String(_) => Op::StringOp,
+            IfrOp::Refresh { .. } => Op::RefreshOp,
+            IfrOp::DisableIf => Op::DisableIfOp,
+            IfrOp::Animation { .. } => Op::AnimationOp,
+            IfrOp::ToLower => Op::ToLowerOp,
+            IfrOp::ToUpper => Op::ToUpperOp,
+            IfrOp::Map => Op::MapOp,
+            IfrOp::OrderedList(_) => Op::OrderedListOp,
+            IfrOp::VarStore(_) => Op::VarstoreOp,
+            IfrOp::VarStoreNameValue(_) => Op::VarstoreNameValueOp,
+            IfrOp::VarStoreEfi(_) => Op::VarstoreEfiOp,
+            IfrOp::VarStoreDevice { .. } => Op::VarstoreDeviceOp,
+            IfrOp::Version => Op::VersionOp,
+            IfrOp::End => Op::EndOp,
+            IfrOp::Match => Op::MatchOp,
+            IfrOp::Get { .. } => Op::GetOp,
+            IfrOp::Set { .. } => Op::SetOp,
+            IfrOp::Read => Op::ReadOp,
+            IfrOp::Write => Op::WriteOp,
+            IfrOp::Equal => Op::EqualOp,
+            IfrOp::NotEqual => Op::NotEqualOp,
+            IfrOp::GreaterThan => Op::GreaterThanOp,
+            IfrOp::GreaterEqual => Op::GreaterEqualOp,
+            IfrOp::LessThan => Op::LessThanOp,
+            IfrOp::LessEqual => Op::LessEqualOp,
+            IfrOp::BitwiseAnd => Op::BitwiseAndOp,
+            IfrOp::BitwiseOr => Op::BitwiseOrOp,
+            IfrOp::BitwiseNot => Op::BitwiseNotOp,
+            IfrOp::ShiftLeft => Op::ShiftLeftOp,
+            IfrOp::ShiftRight => Op::ShiftRightOp,
+            IfrOp::Add => Op::AddOp,
+            IfrOp::Subtract => Op::SubtractOp,
+            IfrOp::Multiply => Op::MultiplyOp,
+            IfrOp::Divide => Op::DivideOp,
+            IfrOp::Modulo => Op::ModuloOp,
+            IfrOp::RuleRef { .. } => Op::RuleRefOp,
+            IfrOp::QuestionRef1 { .. } => Op::QuestionRef1Op,
+            IfrOp::QuestionRef2 => Op::QuestionRef2Op,
+            IfrOp::Uint8(_) => Op::Uint8Op,
+            IfrOp::Uint16(_) => Op::Uint16Op,
+            IfrOp::Uint32(_) => Op::Uint32Op,
+            IfrOp::Uint64(_) => Op::Uint64Op,
+            IfrOp::True => Op::TrueOp,
+            IfrOp::False => Op::FalseOp,
+            IfrOp::ToUint => Op::ToUintOp,
+            IfrOp::ToString { .. } => Op::ToStringOp,
+            IfrOp::ToBoolean => Op::ToBooleanOp,
+            IfrOp::Mid => Op::MidOp,
+            IfrOp::Find { .. } => Op::FindOp,
+            IfrOp::Token => Op::TokenOp,
+            IfrOp::StringRef1 { .. } => Op::StringRef1Op,
+            IfrOp::StringRef2 => Op::StringRef2Op,
+            IfrOp::Conditional => Op::ConditionalOp,
+            IfrOp::QuestionRef3 { .. } => Op::QuestionRef3Op,
+            IfrOp::Zero => Op::ZeroOp,
+            IfrOp::One => Op::OneOp,
+            IfrOp::Ones => Op::OnesOp,
+            IfrOp::Undefined => Op::UndefinedOp,
+            IfrOp::Length => Op::LengthOp,
+            IfrOp::Dup => Op::DupOp,
+            IfrOp::This => Op::ThisOp,
+            IfrOp::Span { .. } => Op::SpanOp,
+            IfrOp::Value => Op::ValueOp,
+            IfrOp::Default(_) => Op::DefaultOp,
+            IfrOp::DefaultStore { .. } => Op::DefaultStoreOp,
+            IfrOp::FormMap { .. } => Op::FormMapOp,
+            IfrOp::Catenate => Op::CatenateOp,
+            IfrOp::Guid { .. } => Op::GuidOp,
+            IfrOp::Security { .. } => Op::SecurityOp,
+            IfrOp::ModalTag => Op::ModalTagOp,
+            IfrOp::RefreshId { .. } => Op::RefreshIdOp,
+            IfrOp::WarningIf { .. } => Op::WarningIfOp,
+            IfrOp::Match2 { .. } => Op::Match2Op,
+        }
+    }
+
+    /// Question header of question opcodes
+    pub fn question(&self) -> Option<&IfrQuestionHeader> {
+        match self {
+            IfrOp::OneOf(op) | IfrOp::Numeric(op) => Some(&op.question),
+            IfrOp::Checkbox(op) | IfrOp::Date(op) | IfrOp::Time(op) => Some(&op.question),
+            IfrOp::Password(op) => Some(&op.question),
+            IfrOp::Action(op) => Some(&op.question),
+            IfrOp::Ref(op) => Some(&op.question),
+            IfrOp::String(op) => Some(&op.question),
+            IfrOp::OrderedList(op) => Some(&op.question),
+            _ => None,
+        }
+    }
+}
+
+/// Opcode decoded by [`IfrOpIter`] together with its position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrOpEntry {
+    /// Offset of the opcode in the form package data
+    pub offset: usize,
+    /// Scope bit of EFI_IFR_OP_HEADER
+    pub scope: bool,
+    pub op: IfrOp,
+}
+
+/// Iterator decoding the opcodes of a form package, stops after the first error.
+pub struct IfrOpIter<'a> {
+    data: &'a [u8],
+    offset: usize,
+    failed: bool,
+}
+
+impl<'a> IfrOpIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            failed: false,
+        }
+    }
+}
+
+impl Iterator for IfrOpIter<'_> {
+    type Item = Result<IfrOpEntry, IfrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.offset >= self.data.len() {
+            return None;
+        }
+
+        let bytes = &self.data[self.offset..];
+        match IfrOp::decode(bytes) {
+            Ok(op) => {
+                let entry = IfrOpEntry {
+                    offset: self.offset,
+                    scope: bytes[1] & 0x80 != 0,
+                    op,
+                };
+                self.offset += (bytes[1] & 0x7f) as usize;
+                Some(Ok(entry))
+            }
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err.with_offset(self.offset)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use uefi::guid;
+
+    fn question() -> IfrQuestionHeader {
+        IfrQuestionHeader::new(0x1000, 0x10, 0x11)
+            .with_storage(1, 4)
+            .with_flags(EFI_IFR_FLAG_CALLBACK)
+    }
+
+    fn round_trip(op: IfrOp, scope: bool) {
+        let bytes = op.encode(scope).unwrap();
+        assert_eq!(bytes[0], op.op_code() as u8);
+        assert_eq!(bytes[1] & 0x7f, bytes.len() as u8);
+        assert_eq!(bytes[1] & 0x80 != 0, scope);
+        assert_eq!(IfrOp::decode(&bytes), Ok(op));
+    }
+
+    #[test]
+    fn question_ops_round_trip() {
+        let data = IfrMinMaxStep {
+            min_value: 1,
+            max_value: 0x1234,
+            step: 2,
+        };
+        for flags in [
+            EFI_IFR_NUMERIC_SIZE_2,
+            EFI_IFR_NUMERIC_SIZE_4 | EFI_IFR_DISPLAY_UINT_HEX,
+            EFI_IFR_NUMERIC_SIZE_8,
+        ] {
+            let numeric = IfrNumeric {
+                question: question(),
+                flags,
+                data,
+                data_width: None,
+            };
+            round_trip(IfrOp::Numeric(numeric), false);
+            round_trip(IfrOp::OneOf(numeric), true);
+        }
+
+        let flagged = IfrFlaggedQuestion {
+            question: question(),
+            flags: EFI_IFR_CHECKBOX_DEFAULT,
+        };
+        round_trip(IfrOp::Checkbox(flagged), false);
+        round_trip(IfrOp::Date(flagged), true);
+        round_trip(IfrOp::Time(flagged), true);
+        round_trip(
+            IfrOp::Password(IfrPassword {
+                question: question(),
+                min_size: 6,
+                max_size: 20,
+            }),
+            false,
+        );
+        round_trip(
+            IfrOp::String(IfrString {
+                question: question(),
+                min_size: 1,
+                max_size: 32,
+                flags: 0,
+            }),
+            false,
+        );
+        round_trip(
+            IfrOp::OrderedList(IfrOrderedList {
+                question: question(),
+                max_containers: 4,
+                flags: 0,
+            }),
+            true,
+        );
+        for question_config in [None, Some(0x20)] {
+            round_trip(
+                IfrOp::Action(IfrAction {
+                    question: question(),
+                    question_config,
+                }),
+                false,
+            );
+        }
+    }
+
+    #[test]
+    fn ref_variants_round_trip() {
+        let guid = guid!("ce4f5b0c-dc00-4a32-97ed-2966981c7725");
+        let full = IfrRef {
+            question: question(),
+            form_id: Some(2),
+            question_id: Some(0x1001),
+            form_set_id: Some(guid),
+            device_path: Some(0x30),
+        };
+        round_trip(IfrOp::Ref(full), false);
+        round_trip(
+            IfrOp::Ref(IfrRef {
+                device_path: None,
+                ..full
+            }),
+            false,
+        );
+        round_trip(
+            IfrOp::Ref(IfrRef {
+                form_set_id: None,
+                device_path: None,
+                ..full
+            }),
+            false,
+        );
+        round_trip(
+            IfrOp::Ref(IfrRef {
+                question_id: None,
+                form_set_id: None,
+                device_path: None,
+                ..full
+            }),
+            false,
+        );
+    }
+
+    #[test]
+    fn min_max_step_width_of_bit_varstore() {
+        // 3 bit question of a bit varstore, MINMAXSTEP is UINT32
+        let numeric = IfrNumeric {
+            question: question(),
+            flags: 3 | EDKII_IFR_DISPLAY_UINT_DEC_BIT,
+            data: IfrMinMaxStep {
+                min_value: 0,
+                max_value: 7,
+                step: 1,
+            },
+            data_width: Some(4),
+        };
+        assert_eq!(numeric.width(), 8);
+        let bytes = IfrOp::Numeric(numeric).encode(false).unwrap();
+        assert_eq!(bytes.len(), 2 + 11 + 1 + 12);
+
+        let Ok(IfrOp::Numeric(decoded)) = IfrOp::decode(&bytes) else {
+            panic!("not a numeric");
+        };
+        assert_eq!(decoded.data_width, Some(4));
+        assert_eq!(decoded.data_width(), 4);
+        assert_eq!(decoded, numeric);
+    }
+
+    #[test]
+    fn min_max_step_width_of_flags() {
+        let numeric = IfrNumeric {
+            question: question(),
+            flags: EFI_IFR_NUMERIC_SIZE_1,
+            data: IfrMinMaxStep {
+                min_value: 0,
+                max_value: 0xff,
+                step: 0,
+            },
+            data_width: None,
+        };
+        let bytes = IfrOp::OneOf(numeric).encode(false).unwrap();
+        assert_eq!(bytes.len(), 2 + 11 + 1 + 3);
+        let Ok(IfrOp::OneOf(decoded)) = IfrOp::decode(&bytes) else {
+            panic!("not a one of");
+        };
+        assert_eq!(decoded.data_width, None);
+        assert_eq!(decoded.data_width(), 1);
+    }
+
+    #[test]
+    fn iter_stops_after_zero_length_op() {
+        let mut data = IfrOp::True.encode(false).unwrap();
+        let zero = data.len();
+        data.extend_from_slice(&[EfiIfrOpCode::FalseOp as u8, 0x00]);
+        data.extend(IfrOp::True.encode(false).unwrap());
+
+        let ops: Vec<_> = IfrOpIter::new(&data).collect();
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[0].as_ref().map(|entry| &entry.op), Ok(&IfrOp::True));
+        assert_eq!(ops[1], Err(IfrError::Truncated { offset: zero }));
+    }
+
+    #[test]
+    fn iter_stops_after_truncated_op() {
+        let mut data = IfrOp::Uint8(1).encode(true).unwrap();
+        data.extend(IfrOp::Uint16(0x1234).encode(false).unwrap());
+        data.pop();
+
+        let ops: Vec<_> = IfrOpIter::new(&data).collect();
+        assert_eq!(ops.len(), 2);
+        let first = ops[0].as_ref().unwrap();
+        assert_eq!(
+            (first.offset, first.scope, &first.op),
+            (0, true, &IfrOp::Uint8(1))
+        );
+        assert_eq!(ops[1], Err(IfrError::Truncated { offset: 3 }));
+    }
+
+    #[test]
+    fn short_op_is_reported() {
+        // EFI_IFR_UINT16 with only one data byte
+        let data = vec![EfiIfrOpCode::Uint16Op as u8, 0x03, 0x34];
+        assert_eq!(
+            IfrOp::decode(&data),
+            Err(IfrError::ShortOpCode {
+                offset: 0,
+                op_code: EfiIfrOpCode::Uint16Op,
+                length: 3,
+            })
+        );
+    }
+}
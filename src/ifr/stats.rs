@@ -0,0 +1,323 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::*;
+use crate::package_header::ifr_parse::{EfiIfrOpCode, is_expression_op_code};
+use crate::package_header::{HiiFormPackageHdr, StringTable};
+
+/// Opcodes of questions
+const QUESTION_OP_CODES: &[EfiIfrOpCode] = &[
+    EfiIfrOpCode::OneOfOp,
+    EfiIfrOpCode::CheckboxOp,
+    EfiIfrOpCode::NumericOp,
+    EfiIfrOpCode::PasswordOp,
+    EfiIfrOpCode::ActionOp,
+    EfiIfrOpCode::RefOp,
+    EfiIfrOpCode::DateOp,
+    EfiIfrOpCode::TimeOp,
+    EfiIfrOpCode::StringOp,
+    EfiIfrOpCode::OrderedListOp,
+];
+
+/// Bytes of a varstore referenced by questions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarStoreCoverage {
+    pub var_store_id: u16,
+    pub name: String,
+    /// Size of the varstore, 0 for name/value varstores
+    pub size: usize,
+    /// Bytes covered by at least one question
+    pub covered: usize,
+    /// Questions stored in the varstore
+    pub questions: usize,
+}
+
+/// Statistics of the opcodes of a form package.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IfrStats {
+    /// Number of opcodes per opcode value
+    pub op_codes: BTreeMap<EfiIfrOpCode, usize>,
+    /// Number of questions per question opcode
+    pub questions: BTreeMap<EfiIfrOpCode, usize>,
+    /// Opcodes that are neither expressions nor EFI_IFR_END
+    pub statements: usize,
+    pub expressions: usize,
+    /// EFI_IFR_FORM and EFI_IFR_FORM_MAP opcodes
+    pub forms: usize,
+    /// Deepest nesting of scopes, the form set scope is depth 1
+    pub max_depth: usize,
+    /// Offsets and values of opcodes not defined by the specification
+    pub unknown: Vec<(usize, u8)>,
+    /// Varstore coverage of the opcodes decoded before the first error
+    pub varstores: Vec<VarStoreCoverage>,
+    /// First structural error; counting stops at a truncated opcode
+    pub error: Option<IfrError>,
+}
+
+impl IfrStats {
+    pub fn from_package(package: &HiiFormPackageHdr) -> Self {
+        Self::from_data(package.as_data())
+    }
+
+    /// Statistics of the opcodes in `data`, the data of a form package
+    pub fn from_data(data: &[u8]) -> Self {
+        let mut stats = IfrStats::default();
+        let mut depth = 0;
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let length = data.get(offset + 1).map_or(0, |b| (b & 0x7f) as usize);
+            if length < EFI_IFR_OP_HEADER_SIZE || offset + length > data.len() {
+                stats.error.get_or_insert(IfrError::Truncated { offset });
+                break;
+            }
+            let scope = data[offset + 1] & 0x80 != 0;
+
+            match EfiIfrOpCode::try_from(data[offset]) {
+                Ok(op_code) => stats.count(op_code),
+                Err(op_code) => stats.unknown.push((offset, op_code)),
+            }
+
+            if data[offset] == EfiIfrOpCode::EndOp as u8 {
+                match depth {
+                    0 => {
+                        stats
+                            .error
+                            .get_or_insert(IfrError::UnbalancedEnd { offset });
+                    }
+                    _ => depth -= 1,
+                }
+            }
+            if scope {
+                depth += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+            }
+            offset += length;
+        }
+
+        if depth > 0 && stats.error.is_none() {
+            stats.error = Some(IfrError::UnclosedScope { offset });
+        }
+        stats.varstores = coverage(&partial_tree(data));
+        stats
+    }
+
+    fn count(&mut self, op_code: EfiIfrOpCode) {
+        *self.op_codes.entry(op_code).or_default() += 1;
+
+        if QUESTION_OP_CODES.contains(&op_code) {
+            *self.questions.entry(op_code).or_default() += 1;
+        }
+        match op_code {
+            EfiIfrOpCode::FormOp | EfiIfrOpCode::FormMapOp => self.forms += 1,
+            _ => {}
+        }
+        match op_code {
+            EfiIfrOpCode::EndOp => {}
+            op_code if is_expression_op_code(op_code as u8) => self.expressions += 1,
+            _ => self.statements += 1,
+        }
+    }
+
+    /// Number of opcodes, unknown ones included
+    pub fn total_op_codes(&self) -> usize {
+        self.op_codes.values().sum::<usize>() + self.unknown.len()
+    }
+
+    pub fn total_questions(&self) -> usize {
+        self.questions.values().sum()
+    }
+
+    /// Covered bytes and total size of all buffer varstores
+    pub fn varstore_coverage(&self) -> (usize, usize) {
+        self.varstores
+            .iter()
+            .fold((0, 0), |(covered, size), varstore| {
+                (covered + varstore.covered, size + varstore.size)
+            })
+    }
+}
+
+/// Tree of the opcodes decoded before the first error, an unbalanced
+/// EFI_IFR_END is skipped and scopes still open at the end are closed
+fn partial_tree(data: &[u8]) -> IfrTree {
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    let mut offset = 0;
+
+    for entry in IfrOpIter::new(data).map_while(Result::ok) {
+        offset = entry.offset;
+        if entry.op == IfrOp::End {
+            if depth == 0 {
+                continue;
+            }
+            depth -= 1;
+        }
+        if entry.scope {
+            depth += 1;
+        }
+        entries.push(Ok(entry));
+    }
+    entries.extend((0..depth).map(|_| {
+        Ok(IfrOpEntry {
+            offset,
+            scope: false,
+            op: IfrOp::End,
+        })
+    }));
+    // Balanced by construction
+    IfrTree::from_entries(entries).unwrap_or_default()
+}
+
+/// Bytes of each varstore referenced by the questions of the form sets
+fn coverage(tree: &IfrTree) -> Vec<VarStoreCoverage> {
+    let strings = StringTable::new(String::new());
+    let mut varstores = Vec::new();
+
+    for form_set in FormSet::from_tree(tree, &strings) {
+        for varstore in &form_set.varstores {
+            let size = match varstore.kind {
+                VarStoreKind::NameValue => 0,
+                _ => varstore.size as usize,
+            };
+            let mut bytes = vec![false; size];
+            let mut questions = 0;
+
+            for question in form_set.questions() {
+                if question.varstore != Some(varstore.id) {
+                    continue;
+                }
+                questions += 1;
+                if let Some(offset) = question.byte_offset() {
+                    let start = offset.min(size);
+                    let end = (offset + question.width).min(size);
+                    bytes[start..end].iter_mut().for_each(|byte| *byte = true);
+                }
+            }
+
+            varstores.push(VarStoreCoverage {
+                var_store_id: varstore.id,
+                name: varstore.name.clone(),
+                size,
+                covered: bytes.iter().filter(|byte| **byte).count(),
+                questions,
+            });
+        }
+    }
+    varstores
+}
+
+impl Display for IfrStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "opcodes={} statements={} expressions={} forms={} questions={} max_depth={}",
+            self.total_op_codes(),
+            self.statements,
+            self.expressions,
+            self.forms,
+            self.total_questions(),
+            self.max_depth
+        )?;
+        for (op_code, count) in &self.op_codes {
+            writeln!(f, "  {:?}: {}", op_code, count)?;
+        }
+        for (offset, op_code) in &self.unknown {
+            writeln!(f, "  unknown opcode 0x{:02X} at 0x{:04X}", op_code, offset)?;
+        }
+        for varstore in &self.varstores {
+            writeln!(
+                f,
+                "  varstore 0x{:04X} {}: {}/{} bytes, {} questions",
+                varstore.var_store_id,
+                varstore.name,
+                varstore.covered,
+                varstore.size,
+                varstore.questions
+            )?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "  error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uefi::guid;
+
+    fn form_set() -> Vec<u8> {
+        let varstore = guid!("3a3aab90-7886-4f2e-88f8-597a951b78bc");
+        FormSetBuilder::new(guid!("32c66610-94df-4d6d-984f-8cbe44519b87"), 0x02, 0x03)
+            .varstore(1, varstore, "Setup", 8)
+            .form(1, 0x04, |f| {
+                f.one_of(
+                    IfrQuestionHeader::new(1, 0x10, 0x11).with_storage(1, 0),
+                    EFI_IFR_NUMERIC_SIZE_1,
+                    |f| f.option(0x12, IfrTypeValue::U8(0), 0),
+                )
+                .numeric(
+                    IfrQuestionHeader::new(2, 0x13, 0x14).with_storage(1, 2),
+                    EFI_IFR_NUMERIC_SIZE_2,
+                    0,
+                    100,
+                    1,
+                    |f| f,
+                )
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn coverage(stats: &IfrStats) -> Vec<(u16, usize, usize, usize)> {
+        stats
+            .varstores
+            .iter()
+            .map(|v| (v.var_store_id, v.size, v.covered, v.questions))
+            .collect()
+    }
+
+    #[test]
+    fn counts_and_coverage() {
+        let stats = IfrStats::from_data(&form_set());
+        assert_eq!(stats.error, None);
+        assert_eq!(stats.forms, 1);
+        assert_eq!(stats.total_questions(), 2);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(coverage(&stats), [(1, 8, 3, 2)]);
+        assert_eq!(stats.varstore_coverage(), (3, 8));
+    }
+
+    #[test]
+    fn coverage_up_to_first_error() {
+        // Form and form set scopes are never closed, a zero-length opcode follows
+        let mut data = form_set();
+        data.truncate(data.len() - 4);
+        let offset = data.len();
+        data.extend_from_slice(&[EfiIfrOpCode::TextOp as u8, 0x00]);
+
+        let stats = IfrStats::from_data(&data);
+        assert_eq!(stats.error, Some(IfrError::Truncated { offset }));
+        assert!(IfrTree::parse(&data).is_err());
+        assert_eq!(coverage(&stats), [(1, 8, 3, 2)]);
+    }
+
+    #[test]
+    fn unbalanced_end_keeps_coverage() {
+        let mut data = form_set();
+        let offset = data.len();
+        data.extend(IfrOp::End.encode(false).unwrap());
+
+        let stats = IfrStats::from_data(&data);
+        assert_eq!(stats.error, Some(IfrError::UnbalancedEnd { offset }));
+        assert_eq!(coverage(&stats), [(1, 8, 3, 2)]);
+    }
+}
@@ -0,0 +1,223 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::{IfrError, IfrOp, IfrOpEntry, IfrOpIter};
+use crate::package_header::HiiFormPackageHdr;
+
+/// Opcode with the opcodes nested in its scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IfrNode {
+    pub entry: IfrOpEntry,
+    /// Opcodes inside the scope, the closing EFI_IFR_END is not included
+    pub children: Vec<IfrNode>,
+    /// Offset of the EFI_IFR_END closing the scope
+    pub end_offset: Option<usize>,
+}
+
+impl IfrNode {
+    fn new(entry: IfrOpEntry) -> Self {
+        Self {
+            entry,
+            children: Vec::new(),
+            end_offset: None,
+        }
+    }
+
+    pub fn op(&self) -> &IfrOp {
+        &self.entry.op
+    }
+
+    pub fn offset(&self) -> usize {
+        self.entry.offset
+    }
+
+    /// Depth-first iterator over this node and all nested nodes
+    pub fn walk(&self) -> IfrNodeWalk<'_> {
+        IfrNodeWalk {
+            stack: alloc::vec![(self, 0)],
+        }
+    }
+
+    /// First child matching `predicate`
+    pub fn find_child(&self, predicate: impl Fn(&IfrOp) -> bool) -> Option<&IfrNode> {
+        self.children.iter().find(|child| predicate(child.op()))
+    }
+}
+
+/// Depth-first iterator yielding nodes with their nesting depth.
+pub struct IfrNodeWalk<'a> {
+    stack: Vec<(&'a IfrNode, usize)>,
+}
+
+impl<'a> Iterator for IfrNodeWalk<'a> {
+    type Item = (&'a IfrNode, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.stack.pop()?;
+        self.stack
+            .extend(node.children.iter().rev().map(|child| (child, depth + 1)));
+        Some((node, depth))
+    }
+}
+
+/// Scope-aware tree of the opcodes of a form package.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IfrTree {
+    /// Top level opcodes, normally a single EFI_IFR_FORM_SET
+    pub roots: Vec<IfrNode>,
+}
+
+impl IfrTree {
+    /// Build the tree from the data of a form package (without the package header)
+    pub fn parse(data: &[u8]) -> Result<Self, IfrError> {
+        Self::from_entries(IfrOpIter::new(data))
+    }
+
+    pub fn from_package(package: &HiiFormPackageHdr) -> Result<Self, IfrError> {
+        Self::parse(package.as_data())
+    }
+
+    /// Build the tree from already decoded opcodes
+    pub fn from_entries<I>(entries: I) -> Result<Self, IfrError>
+    where
+        I: IntoIterator<Item = Result<IfrOpEntry, IfrError>>,
+    {
+        let mut roots = Vec::new();
+        // Opcodes with an open scope, innermost last
+        let mut open: Vec<IfrNode> = Vec::new();
+
+        for entry in entries {
+            let entry = entry?;
+
+            if entry.op == IfrOp::End {
+                let mut node = open
+                    .pop()
+                    .ok_or(IfrError::UnbalancedEnd { offset: entry.offset })?;
+                node.end_offset = Some(entry.offset);
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+                continue;
+            }
+
+            let node = IfrNode::new(entry);
+            if node.entry.scope {
+                open.push(node);
+            } else {
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            }
+        }
+
+        if let Some(unclosed) = open.last() {
+            return Err(IfrError::UnclosedScope {
+                offset: unclosed.offset(),
+            });
+        }
+        Ok(Self { roots })
+    }
+
+    /// Depth-first iterator over every node of the tree
+    pub fn walk(&self) -> impl Iterator<Item = (&IfrNode, usize)> {
+        self.roots.iter().flat_map(|root| root.walk())
+    }
+
+    /// Top level EFI_IFR_FORM_SET nodes
+    pub fn form_sets(&self) -> impl Iterator<Item = &IfrNode> {
+        self.roots
+            .iter()
+            .filter(|node| matches!(node.op(), IfrOp::FormSet(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ifr::IfrForm;
+
+    fn ops(ops: &[(IfrOp, bool)]) -> Vec<u8> {
+        ops.iter()
+            .flat_map(|(op, scope)| op.encode(*scope).unwrap())
+            .collect()
+    }
+
+    fn form(form_id: u16) -> IfrOp {
+        IfrOp::Form(IfrForm {
+            form_id,
+            form_title: 0x10,
+        })
+    }
+
+    #[test]
+    fn nested_scopes() {
+        // FORM { SUPPRESS_IF { TRUE } TRUE } FALSE
+        let data = ops(&[
+            (form(1), true),
+            (IfrOp::SuppressIf, true),
+            (IfrOp::True, false),
+            (IfrOp::End, false),
+            (IfrOp::True, false),
+            (IfrOp::End, false),
+            (IfrOp::False, false),
+        ]);
+        let tree = IfrTree::parse(&data).unwrap();
+
+        assert_eq!(tree.roots.len(), 2);
+        let form = &tree.roots[0];
+        assert_eq!(form.op(), &self::form(1));
+        assert_eq!(form.children.len(), 2);
+        assert_eq!(form.end_offset, Some(14));
+        assert_eq!(form.children[0].end_offset, Some(10));
+        assert_eq!(tree.roots[1].op(), &IfrOp::False);
+        assert_eq!(tree.roots[1].end_offset, None);
+
+        let walk: Vec<_> = tree
+            .walk()
+            .map(|(node, depth)| (node.offset(), depth))
+            .collect();
+        assert_eq!(walk, [(0, 0), (6, 1), (8, 2), (12, 1), (16, 0)]);
+    }
+
+    #[test]
+    fn unbalanced_end() {
+        let data = ops(&[
+            (IfrOp::SuppressIf, true),
+            (IfrOp::End, false),
+            (IfrOp::End, false),
+        ]);
+        assert_eq!(
+            IfrTree::parse(&data),
+            Err(IfrError::UnbalancedEnd { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn unclosed_scope() {
+        // The innermost open scope is reported
+        let data = ops(&[
+            (form(1), true),
+            (IfrOp::SuppressIf, true),
+            (IfrOp::True, false),
+        ]);
+        assert_eq!(
+            IfrTree::parse(&data),
+            Err(IfrError::UnclosedScope { offset: 6 })
+        );
+    }
+
+    #[test]
+    fn decode_error_stops_parse() {
+        let mut data = ops(&[(form(1), true), (IfrOp::End, false)]);
+        data.extend_from_slice(&[0xFE, 0x02]);
+        assert_eq!(
+            IfrTree::parse(&data),
+            Err(IfrError::UnknownOpCode {
+                offset: 8,
+                op_code: 0xFE,
+            })
+        );
+    }
+}
@@ -0,0 +1,122 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use uefi::Guid;
+
+use super::*;
+
+/// Errors of [`update_form`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateFormError {
+    /// Form package or the new opcodes do not decode
+    Decode(IfrError),
+    FormSetNotFound {
+        guid: Guid,
+    },
+    FormNotFound {
+        form_id: u16,
+    },
+    /// Start label missing from the form, or end label missing from the scope of the start label
+    LabelNotFound {
+        number: u16,
+    },
+}
+
+impl From<IfrError> for UpdateFormError {
+    fn from(error: IfrError) -> Self {
+        UpdateFormError::Decode(error)
+    }
+}
+
+impl Display for UpdateFormError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateFormError::Decode(error) => write!(f, "{}", error),
+            UpdateFormError::FormSetNotFound { guid } => write!(f, "form set {} not found", guid),
+            UpdateFormError::FormNotFound { form_id } => {
+                write!(f, "form 0x{:04X} not found", form_id)
+            }
+            UpdateFormError::LabelNotFound { number } => {
+                write!(f, "label 0x{:04X} not found", number)
+            }
+        }
+    }
+}
+
+/// Replace the opcodes following a label of a form, like HiiUpdateForm of EDK2.
+///
+/// The opcodes between label `start_label` and label `end_label` are replaced
+/// by `ops`; both labels are kept. Without `end_label` the replaced opcodes end
+/// at the next label or at the end of the scope holding the start label.
+/// `ops` must have balanced scopes, [`FormBuilder::build`] produces such opcodes.
+///
+/// `data` is the data of a form package, the updated data is returned.
+pub fn update_form(
+    data: &[u8],
+    form_set_guid: Guid,
+    form_id: u16,
+    start_label: u16,
+    end_label: Option<u16>,
+    ops: &[u8],
+) -> Result<Vec<u8>, UpdateFormError> {
+    IfrTree::parse(ops)?;
+    let tree = IfrTree::parse(data)?;
+
+    let form_set = tree
+        .form_sets()
+        .find(|node| matches!(node.op(), IfrOp::FormSet(op) if op.guid == form_set_guid))
+        .ok_or(UpdateFormError::FormSetNotFound {
+            guid: form_set_guid,
+        })?;
+    let form = form_set
+        .walk()
+        .map(|(node, _)| node)
+        .find(|node| match node.op() {
+            IfrOp::Form(form) => form.form_id == form_id,
+            IfrOp::FormMap { form_id: id, .. } => *id == form_id,
+            _ => false,
+        })
+        .ok_or(UpdateFormError::FormNotFound { form_id })?;
+    let (parent, index) = find_label(form, start_label).ok_or(UpdateFormError::LabelNotFound {
+        number: start_label,
+    })?;
+
+    // The parent holds the label, so its scope is closed by an EFI_IFR_END
+    let scope_end = parent.end_offset.unwrap_or(data.len());
+    let following = &parent.children[index + 1..];
+    let start = following.first().map_or(scope_end, IfrNode::offset);
+    let end = match end_label {
+        Some(number) => following
+            .iter()
+            .find(|node| node.op().label() == Some(number))
+            .map(IfrNode::offset)
+            .ok_or(UpdateFormError::LabelNotFound { number })?,
+        None => following
+            .iter()
+            .find(|node| node.op().label().is_some())
+            .map_or(scope_end, IfrNode::offset),
+    };
+
+    let mut updated = Vec::with_capacity(data.len() - (end - start) + ops.len());
+    updated.extend_from_slice(&data[..start]);
+    updated.extend_from_slice(ops);
+    updated.extend_from_slice(&data[end..]);
+    Ok(updated)
+}
+
+/// Node holding label `number` and the index of the label among its children
+fn find_label(node: &IfrNode, number: u16) -> Option<(&IfrNode, usize)> {
+    if let Some(index) = node
+        .children
+        .iter()
+        .position(|child| child.op().label() == Some(number))
+    {
+        return Some((node, index));
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_label(child, number))
+}
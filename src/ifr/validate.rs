@@ -0,0 +1,364 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::*;
+use crate::package_header::{HiiFormPackageHdr, StringTable};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Form package does not decode
+    Decode(IfrError),
+    /// QuestionId already used by the question at `first`
+    DuplicateQuestionId { question_id: u16, first: usize },
+    /// Reference to a QuestionId not declared in the form set
+    UnknownQuestion { question_id: u16 },
+    /// Reference to a FormId not declared in the form set
+    UnknownForm { form_id: u16 },
+    /// Question stored in a VarStoreId not declared in the form set
+    UndeclaredVarStore { var_store_id: u16 },
+    /// Offset plus width of a question is past the end of its varstore
+    StorageOutOfRange {
+        var_store_id: u16,
+        offset: u16,
+        width: usize,
+        size: u16,
+    },
+    /// Storage shares bytes with the question at `other`
+    OverlappingStorage {
+        var_store_id: u16,
+        question_id: u16,
+        other_question_id: u16,
+        other: usize,
+    },
+    /// String id missing from the string packages
+    MissingString { string_id: u16 },
+}
+
+/// Diagnostic with the offset of the opcode it applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub offset: usize,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "0x{:04X}: {}: ", self.offset, severity)?;
+
+        match &self.kind {
+            DiagnosticKind::Decode(error) => write!(f, "{}", error),
+            DiagnosticKind::DuplicateQuestionId { question_id, first } => write!(
+                f,
+                "question id 0x{:04X} already used at 0x{:04X}",
+                question_id, first
+            ),
+            DiagnosticKind::UnknownQuestion { question_id } => {
+                write!(f, "reference to unknown question 0x{:04X}", question_id)
+            }
+            DiagnosticKind::UnknownForm { form_id } => {
+                write!(f, "reference to unknown form 0x{:04X}", form_id)
+            }
+            DiagnosticKind::UndeclaredVarStore { var_store_id } => {
+                write!(f, "varstore 0x{:04X} is not declared", var_store_id)
+            }
+            DiagnosticKind::StorageOutOfRange {
+                var_store_id,
+                offset,
+                width,
+                size,
+            } => write!(
+                f,
+                "storage 0x{:04X}+{} is outside varstore 0x{:04X} of {} bytes",
+                offset, width, var_store_id, size
+            ),
+            DiagnosticKind::OverlappingStorage {
+                var_store_id,
+                question_id,
+                other_question_id,
+                other,
+            } => write!(
+                f,
+                "question 0x{:04X} overlaps question 0x{:04X} (at 0x{:04X}) in varstore 0x{:04X}",
+                question_id, other_question_id, other, var_store_id
+            ),
+            DiagnosticKind::MissingString { string_id } => {
+                write!(f, "string 0x{:04X} is not defined", string_id)
+            }
+        }
+    }
+}
+
+/// Validate a form package, `strings` enables the string id checks.
+pub fn validate_package(
+    package: &HiiFormPackageHdr,
+    strings: Option<&StringTable>,
+) -> Vec<Diagnostic> {
+    match IfrTree::from_package(package) {
+        Ok(tree) => validate(&tree, strings),
+        Err(error) => alloc::vec![Diagnostic {
+            offset: error.offset(),
+            severity: Severity::Error,
+            kind: DiagnosticKind::Decode(error),
+        }],
+    }
+}
+
+/// Cross-reference checks of the questions, forms, varstores and strings of
+/// every form set of `tree`, ordered by offset.
+pub fn validate(tree: &IfrTree, strings: Option<&StringTable>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for node in tree.form_sets() {
+        Validator::new(node, &mut diagnostics).run(node, strings);
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.offset);
+    diagnostics
+}
+
+struct Validator<'a> {
+    /// QuestionId and offset of its first declaration
+    questions: BTreeMap<u16, usize>,
+    forms: Vec<u16>,
+    varstores: BTreeMap<u16, &'a IfrOp>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(form_set: &'a IfrNode, diagnostics: &'a mut Vec<Diagnostic>) -> Self {
+        let mut validator = Self {
+            questions: BTreeMap::new(),
+            forms: Vec::new(),
+            varstores: BTreeMap::new(),
+            diagnostics,
+        };
+
+        for (node, _) in form_set.walk() {
+            match node.op() {
+                IfrOp::Form(form) => validator.forms.push(form.form_id),
+                IfrOp::FormMap { form_id, .. } => validator.forms.push(*form_id),
+                op @ IfrOp::VarStore(IfrVarStore { var_store_id, .. })
+                | op @ IfrOp::VarStoreEfi(IfrVarStoreEfi { var_store_id, .. })
+                | op @ IfrOp::VarStoreNameValue(IfrVarStoreNameValue { var_store_id, .. }) => {
+                    validator.varstores.insert(*var_store_id, op);
+                }
+                op => {
+                    let Some(question) = op.question() else {
+                        continue;
+                    };
+                    if let Some(first) = validator.questions.get(&question.question_id) {
+                        let kind = DiagnosticKind::DuplicateQuestionId {
+                            question_id: question.question_id,
+                            first: *first,
+                        };
+                        validator.push(node.offset(), Severity::Error, kind);
+                    } else {
+                        validator
+                            .questions
+                            .insert(question.question_id, node.offset());
+                    }
+                }
+            }
+        }
+        validator
+    }
+
+    fn push(&mut self, offset: usize, severity: Severity, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            offset,
+            severity,
+            kind,
+        });
+    }
+
+    fn run(mut self, form_set: &IfrNode, strings: Option<&StringTable>) {
+        let IfrOp::FormSet(op) = form_set.op() else {
+            return;
+        };
+        let guid = op.guid;
+
+        for (node, _) in form_set.walk() {
+            self.references(node, guid);
+            if let Some(strings) = strings {
+                for string_id in string_ids(node.op(), &self.varstores) {
+                    if string_id != 0 && !strings.contains(string_id) {
+                        let kind = DiagnosticKind::MissingString { string_id };
+                        self.push(node.offset(), Severity::Error, kind);
+                    }
+                }
+            }
+        }
+
+        if let Some(model) = FormSet::from_node(form_set, &StringTable::new(String::new())) {
+            self.storage(&model);
+        }
+    }
+
+    fn references(&mut self, node: &IfrNode, form_set_guid: uefi::Guid) {
+        let mut question_ids = Vec::new();
+        match node.op() {
+            IfrOp::Ref(op) => {
+                let same_form_set = op.form_set_id.is_none_or(|guid| guid == form_set_guid);
+                if same_form_set {
+                    if let Some(form_id) =
+                        op.form_id.filter(|id| *id != 0 && !self.forms.contains(id))
+                    {
+                        self.push(
+                            node.offset(),
+                            Severity::Error,
+                            DiagnosticKind::UnknownForm { form_id },
+                        );
+                    }
+                    question_ids.extend(op.question_id.filter(|id| *id != 0));
+                }
+            }
+            IfrOp::EqIdVal { question_id, .. }
+            | IfrOp::EqIdValList { question_id, .. }
+            | IfrOp::QuestionRef1 { question_id } => question_ids.push(*question_id),
+            IfrOp::EqIdId {
+                question_id_1,
+                question_id_2,
+            } => question_ids.extend([*question_id_1, *question_id_2]),
+            _ => {}
+        }
+
+        if let Some(question) = node.op().question() {
+            let var_store_id = question.var_store_id;
+            if var_store_id != 0 && !self.varstores.contains_key(&var_store_id) {
+                let kind = DiagnosticKind::UndeclaredVarStore { var_store_id };
+                self.push(node.offset(), Severity::Error, kind);
+            }
+        }
+
+        for question_id in question_ids {
+            if !self.questions.contains_key(&question_id) {
+                let kind = DiagnosticKind::UnknownQuestion { question_id };
+                self.push(node.offset(), Severity::Error, kind);
+            }
+        }
+    }
+
+    /// Range and overlap of questions stored at an offset of a buffer varstore,
+    /// bit fields of a bit varstore overlap only when they share bits
+    fn storage(&mut self, model: &FormSet) {
+        // First bit and number of bits of the storage of each question
+        let mut stored: BTreeMap<u16, Vec<(usize, usize, &Question)>> = BTreeMap::new();
+
+        for question in model.questions() {
+            let bits = match question.storage {
+                QuestionStorage::Offset(offset) => (offset as usize * 8, question.width * 8),
+                QuestionStorage::Bits { offset, width } => (offset as usize, width as usize),
+                QuestionStorage::Name(_) | QuestionStorage::None => continue,
+            };
+            let (Some(var_store_id), Some(offset)) = (question.varstore, question.byte_offset())
+            else {
+                continue;
+            };
+            let Some(varstore) = model.varstore(var_store_id) else {
+                continue;
+            };
+            if offset + question.width > varstore.size as usize {
+                let kind = DiagnosticKind::StorageOutOfRange {
+                    var_store_id,
+                    offset: offset as u16,
+                    width: question.width,
+                    size: varstore.size,
+                };
+                self.push(question.offset, Severity::Error, kind);
+            }
+            stored
+                .entry(var_store_id)
+                .or_default()
+                .push((bits.0, bits.1, question));
+        }
+
+        for (var_store_id, mut questions) in stored {
+            questions.sort_by_key(|(start, bits, question)| (*start, *bits, question.offset));
+
+            for (index, (start, bits, question)) in questions.iter().enumerate() {
+                // The first earlier question still covering `start`
+                let other = questions[..index]
+                    .iter()
+                    .find(|(other_start, other_bits, _)| other_start + other_bits > *start);
+                let Some((other_start, other_bits, other)) = other else {
+                    continue;
+                };
+                // Several questions on the very same storage are common, partial overlaps are not
+                let severity = match (*other_start, *other_bits) == (*start, *bits) {
+                    true => Severity::Warning,
+                    false => Severity::Error,
+                };
+                let kind = DiagnosticKind::OverlappingStorage {
+                    var_store_id,
+                    question_id: question.id,
+                    other_question_id: other.id,
+                    other: other.offset,
+                };
+                self.push(question.offset, severity, kind);
+            }
+        }
+    }
+}
+
+/// String ids referenced by an opcode
+fn string_ids(op: &IfrOp, varstores: &BTreeMap<u16, &IfrOp>) -> Vec<u16> {
+    let mut ids = match op {
+        IfrOp::FormSet(op) => alloc::vec![op.form_set_title, op.help],
+        IfrOp::Form(op) => alloc::vec![op.form_title],
+        IfrOp::FormMap { methods, .. } => {
+            methods.iter().map(|method| method.method_title).collect()
+        }
+        IfrOp::Subtitle { statement, .. } | IfrOp::ResetButton { statement, .. } => {
+            alloc::vec![statement.prompt, statement.help]
+        }
+        IfrOp::Text {
+            statement,
+            text_two,
+        } => alloc::vec![statement.prompt, statement.help, *text_two],
+        IfrOp::OneOfOption(option) => match option.value {
+            IfrTypeValue::String(id) => alloc::vec![option.option, id],
+            _ => alloc::vec![option.option],
+        },
+        IfrOp::Default(IfrDefault {
+            value: IfrTypeValue::String(id),
+            ..
+        }) => alloc::vec![*id],
+        IfrOp::DefaultStore { default_name, .. } => alloc::vec![*default_name],
+        IfrOp::NoSubmitIf { error } | IfrOp::InconsistentIf { error } => alloc::vec![*error],
+        IfrOp::WarningIf { warning, .. } => alloc::vec![*warning],
+        IfrOp::StringRef1 { string_id } => alloc::vec![*string_id],
+        IfrOp::VarStoreDevice { device_path } => alloc::vec![*device_path],
+        _ => Vec::new(),
+    };
+
+    if let Some(question) = op.question() {
+        ids.extend([question.header.prompt, question.header.help]);
+        // VarStoreInfo of a name/value varstore is the string id of the name
+        if let Some(IfrOp::VarStoreNameValue(_)) = varstores.get(&question.var_store_id) {
+            ids.push(question.var_store_info);
+        }
+    }
+    if let IfrOp::Action(IfrAction {
+        question_config: Some(config),
+        ..
+    }) = op
+    {
+        ids.push(*config);
+    }
+    ids
+}
@@ -0,0 +1,1022 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::*;
+
+/// Errors of setting the value of a question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueError {
+    /// Question has no storage in the varstore contents
+    NoStorage { question_id: u16 },
+    /// Value or text does not fit the type of the question
+    InvalidValue { question_id: u16 },
+    /// Number outside the minimum and maximum of a numeric
+    OutOfRange {
+        question_id: u16,
+        value: i128,
+        min: i128,
+        max: i128,
+    },
+    /// Value matches no option of a one-of or ordered list
+    NoOption { question_id: u16, value: u64 },
+    /// Storage of the question is past the end of the varstore contents
+    OutOfStorage {
+        question_id: u16,
+        offset: usize,
+        width: usize,
+        size: usize,
+    },
+}
+
+impl Display for ValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::NoStorage { question_id } => {
+                write!(
+                    f,
+                    "question 0x{:04X} has no storage in the varstore",
+                    question_id
+                )
+            }
+            ValueError::InvalidValue { question_id } => {
+                write!(
+                    f,
+                    "value does not fit the type of question 0x{:04X}",
+                    question_id
+                )
+            }
+            ValueError::OutOfRange {
+                question_id,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "value {} of question 0x{:04X} is outside {}..={}",
+                value, question_id, min, max
+            ),
+            ValueError::NoOption { question_id, value } => {
+                write!(
+                    f,
+                    "question 0x{:04X} has no option with value {}",
+                    question_id, value
+                )
+            }
+            ValueError::OutOfStorage {
+                question_id,
+                offset,
+                width,
+                size,
+            } => write!(
+                f,
+                "storage 0x{:04X}+{} of question 0x{:04X} is outside the {} bytes of the varstore",
+                offset, width, question_id, size
+            ),
+        }
+    }
+}
+
+/// Contents of a varstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VarStoreContents {
+    /// Buffer of a buffer or EFI varstore
+    Buffer(Vec<u8>),
+    /// Values of a name/value varstore keyed by name, in storage byte order
+    NameValue(BTreeMap<String, Vec<u8>>),
+}
+
+/// Difference between two contents of a varstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentDiff {
+    /// Byte of a buffer
+    Byte { offset: usize, old: u8, new: u8 },
+    /// Value of a name, `None` if the name is not set
+    Name {
+        name: String,
+        old: Option<Vec<u8>>,
+        new: Option<Vec<u8>>,
+    },
+}
+
+impl Display for ContentDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentDiff::Byte { offset, old, new } => {
+                write!(f, "0x{:04X}: {:02X} -> {:02X}", offset, old, new)
+            }
+            ContentDiff::Name { name, old, new } => {
+                let hex = |value: &Option<Vec<u8>>| match value {
+                    Some(value) => hex_string(value),
+                    None => String::from("<unset>"),
+                };
+                write!(f, "{}: {} -> {}", name, hex(old), hex(new))
+            }
+        }
+    }
+}
+
+/// Contents of a varstore before and after setting the value of a question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarStoreChange {
+    pub var_store_id: u16,
+    pub old: VarStoreContents,
+    pub new: VarStoreContents,
+}
+
+impl VarStoreChange {
+    /// Changed bytes of buffers, changed values of name/value varstores
+    pub fn diff(&self) -> Vec<ContentDiff> {
+        match (&self.old, &self.new) {
+            (VarStoreContents::Buffer(old), VarStoreContents::Buffer(new)) => old
+                .iter()
+                .zip(new)
+                .enumerate()
+                .filter(|(_, (old, new))| old != new)
+                .map(|(offset, (old, new))| ContentDiff::Byte {
+                    offset,
+                    old: *old,
+                    new: *new,
+                })
+                .collect(),
+            (VarStoreContents::NameValue(old), VarStoreContents::NameValue(new)) => {
+                let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+                names.sort();
+                names.dedup();
+                names
+                    .into_iter()
+                    .filter(|name| old.get(*name) != new.get(*name))
+                    .map(|name| ContentDiff::Name {
+                        name: name.clone(),
+                        old: old.get(name).cloned(),
+                        new: new.get(name).cloned(),
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Display for VarStoreChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let diff = self.diff();
+        write!(
+            f,
+            "VarStore 0x{:04X}: {} changes",
+            self.var_store_id,
+            diff.len()
+        )?;
+        for change in diff {
+            write!(f, "\n    {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Current value of a question decoded from the contents of its varstore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionValue<'a> {
+    pub question: &'a Question,
+    /// `None` if the storage of the question is past the end of the contents
+    pub value: Option<IfrTypeValue>,
+}
+
+impl QuestionValue<'_> {
+    /// Option of a one-of matching the value
+    pub fn option(&self) -> Option<&QuestionOption> {
+        self.question.option_for(self.value.as_ref()?)
+    }
+
+    /// Value on the expression stack, strings are decoded from their storage
+    pub fn expr_value(&self) -> ExprValue {
+        match (&self.question.kind, &self.value) {
+            (_, None) => ExprValue::Undefined,
+            (QuestionKind::String | QuestionKind::Password, Some(value)) => {
+                ExprValue::String(self.question.format_value(value))
+            }
+            (_, Some(value)) => ExprValue::from_type_value(value, None),
+        }
+    }
+}
+
+/// Question values for expression evaluation, keyed by QuestionId
+pub fn expr_values(values: &[QuestionValue]) -> QuestionValues {
+    values
+        .iter()
+        .map(|value| (value.question.id, value.expr_value()))
+        .collect()
+}
+
+impl Display for QuestionValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}", self.question.format_value(value)),
+            None => write!(f, "<unavailable>"),
+        }
+    }
+}
+
+impl Question {
+    /// Decode the value of the question from the contents of its varstore.
+    ///
+    /// Bit fields decode to UINT32 (BOOLEAN for checkboxes), strings, passwords
+    /// and ordered lists to their raw buffer. Values of names are zero extended
+    /// to the width of the question.
+    pub fn read_value(&self, contents: &VarStoreContents) -> Option<IfrTypeValue> {
+        match contents {
+            VarStoreContents::Buffer(buffer) => {
+                let start = self.byte_offset()?;
+                self.decode_bytes(buffer.get(start..start.checked_add(self.width)?)?)
+            }
+            VarStoreContents::NameValue(values) => {
+                let mut bytes = values.get(self.storage_name()?)?.clone();
+                bytes.resize(self.width, 0);
+                self.decode_bytes(&bytes)
+            }
+        }
+    }
+
+    /// Decode the bytes of the storage of the question
+    fn decode_bytes(&self, bytes: &[u8]) -> Option<IfrTypeValue> {
+        if let QuestionStorage::Bits { offset, width } = self.storage {
+            let mut field = [0u8; 8];
+            let len = bytes.len().min(field.len());
+            field[..len].copy_from_slice(&bytes[..len]);
+            let value = (u64::from_le_bytes(field) >> (offset % 8)) & bit_mask(width);
+            return Some(match self.kind {
+                QuestionKind::Checkbox => IfrTypeValue::Boolean(value != 0),
+                _ => IfrTypeValue::U32(value as u32),
+            });
+        }
+
+        let r#type = match self.kind {
+            QuestionKind::OneOf | QuestionKind::Numeric => match self.width {
+                1 => EFI_IFR_TYPE_NUM_SIZE_8,
+                2 => EFI_IFR_TYPE_NUM_SIZE_16,
+                4 => EFI_IFR_TYPE_NUM_SIZE_32,
+                _ => EFI_IFR_TYPE_NUM_SIZE_64,
+            },
+            QuestionKind::Checkbox => EFI_IFR_TYPE_BOOLEAN,
+            QuestionKind::Date => EFI_IFR_TYPE_DATE,
+            QuestionKind::Time => EFI_IFR_TYPE_TIME,
+            QuestionKind::Ref { .. } => EFI_IFR_TYPE_REF,
+            QuestionKind::String | QuestionKind::Password | QuestionKind::OrderedList => {
+                EFI_IFR_TYPE_BUFFER
+            }
+            QuestionKind::Action => return None,
+        };
+        match r#type {
+            EFI_IFR_TYPE_BUFFER => Some(IfrTypeValue::Buffer(bytes.to_vec())),
+            _ => IfrTypeValue::from_bytes(r#type, bytes),
+        }
+    }
+
+    /// Option whose value equals `value`
+    pub fn option_for(&self, value: &IfrTypeValue) -> Option<&QuestionOption> {
+        let value = value.as_u64()?;
+        self.options
+            .iter()
+            .find(|option| option.value.as_u64() == Some(value))
+    }
+
+    /// Text of a value of the question: option text, number in the display
+    /// format of the question, date, time or string
+    pub fn format_value(&self, value: &IfrTypeValue) -> String {
+        if let Some(option) = self.option_for(value) {
+            return format!("{}", option.text);
+        }
+
+        match (&self.kind, value) {
+            (QuestionKind::Checkbox, value) => match value.as_u64() {
+                Some(0) => String::from("Disabled"),
+                Some(_) => String::from("Enabled"),
+                None => format!("{:?}", value),
+            },
+            (QuestionKind::OneOf | QuestionKind::Numeric, value) => match value.as_u64() {
+                Some(number) => self.format_number(number),
+                None => format!("{:?}", value),
+            },
+            (QuestionKind::String | QuestionKind::Password, IfrTypeValue::Buffer(buffer)) => {
+                let units = buffer
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .take_while(|unit| *unit != 0);
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            }
+            (QuestionKind::OrderedList, IfrTypeValue::Buffer(buffer)) => {
+                let item_width = match self.max as usize {
+                    0 => return hex_string(buffer),
+                    max => buffer.len() / max,
+                };
+                let Some(r#type) = self.options.first().map(|option| option.value.type_code())
+                else {
+                    return hex_string(buffer);
+                };
+                let items: Vec<String> = buffer
+                    .chunks_exact(item_width.max(1))
+                    .filter_map(|item| IfrTypeValue::from_bytes(r#type, item))
+                    .take_while(|item| item.as_u64() != Some(0))
+                    .map(|item| match self.option_for(&item) {
+                        Some(option) => format!("{}", option.text),
+                        None => format!("{}", item.as_u64().unwrap_or_default()),
+                    })
+                    .collect();
+                items.join(", ")
+            }
+            (_, IfrTypeValue::Date { year, month, day }) => {
+                format!("{:04}/{:02}/{:02}", year, month, day)
+            }
+            (
+                _,
+                IfrTypeValue::Time {
+                    hour,
+                    minute,
+                    second,
+                },
+            ) => format!("{:02}:{:02}:{:02}", hour, minute, second),
+            (_, IfrTypeValue::Ref(reference)) => format!(
+                "question 0x{:04X} form 0x{:04X} formset {}",
+                reference.question_id, reference.form_id, reference.form_set_guid
+            ),
+            (_, IfrTypeValue::Buffer(buffer)) => hex_string(buffer),
+            (_, value) => format!("{:?}", value),
+        }
+    }
+
+    /// Parse the text of a value: option text, number (decimal, `0x` hex or
+    /// negative), `Enabled`/`Disabled` for checkboxes, `YYYY/MM/DD`, `HH:MM:SS`,
+    /// string, or comma separated items of an ordered list
+    pub fn parse_value(&self, text: &str) -> Result<IfrTypeValue, ValueError> {
+        let invalid = ValueError::InvalidValue {
+            question_id: self.id,
+        };
+        let text = text.trim();
+
+        if let Some(option) = self.option_by_text(text) {
+            return Ok(option.value.clone());
+        }
+
+        match self.kind {
+            QuestionKind::Checkbox => match text.to_ascii_lowercase().as_str() {
+                "enabled" | "true" | "1" => Ok(IfrTypeValue::Boolean(true)),
+                "disabled" | "false" | "0" => Ok(IfrTypeValue::Boolean(false)),
+                _ => Err(invalid),
+            },
+            QuestionKind::OneOf | QuestionKind::Numeric => {
+                let number = parse_number(text).ok_or(invalid.clone())?;
+                let (_, bits) = self.number_format();
+                // Negative numbers are stored in two's complement of the width
+                let stored = match text.starts_with('-') {
+                    true if sign_extend(number & bit_mask(bits), bits) == number as i64 => {
+                        number & bit_mask(bits)
+                    }
+                    true => return Err(invalid),
+                    false => number,
+                };
+                self.number_value(stored).ok_or(invalid)
+            }
+            QuestionKind::Date => {
+                let mut parts = text.split('/').map(str::parse::<u16>);
+                match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                    (Some(Ok(year)), Some(Ok(month)), Some(Ok(day)), None) => {
+                        Ok(IfrTypeValue::Date {
+                            year,
+                            month: u8::try_from(month).map_err(|_| invalid.clone())?,
+                            day: u8::try_from(day).map_err(|_| invalid)?,
+                        })
+                    }
+                    _ => Err(invalid),
+                }
+            }
+            QuestionKind::Time => {
+                let mut parts = text.split(':').map(str::parse::<u8>);
+                match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                    (Some(Ok(hour)), Some(Ok(minute)), Some(Ok(second)), None) => {
+                        Ok(IfrTypeValue::Time {
+                            hour,
+                            minute,
+                            second,
+                        })
+                    }
+                    _ => Err(invalid),
+                }
+            }
+            QuestionKind::String | QuestionKind::Password => Ok(IfrTypeValue::Buffer(
+                text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            )),
+            QuestionKind::OrderedList => {
+                let mut buffer = Vec::new();
+                for item in text.split(',').map(str::trim) {
+                    let value = match self.option_by_text(item) {
+                        Some(option) => option.value.clone(),
+                        None => {
+                            let number = parse_number(item).ok_or(invalid.clone())?;
+                            let r#type = self
+                                .options
+                                .first()
+                                .map_or(EFI_IFR_TYPE_NUM_SIZE_8, |option| option.value.type_code());
+                            number_of_type(r#type, number).ok_or(invalid.clone())?
+                        }
+                    };
+                    buffer.extend(value.to_bytes());
+                }
+                Ok(IfrTypeValue::Buffer(buffer))
+            }
+            QuestionKind::Ref { .. } | QuestionKind::Action => Err(invalid),
+        }
+    }
+
+    /// Check `value` against the type, range and options of the question
+    pub fn validate_value(&self, value: &IfrTypeValue) -> Result<(), ValueError> {
+        let invalid = ValueError::InvalidValue {
+            question_id: self.id,
+        };
+
+        match (&self.kind, value) {
+            (QuestionKind::OneOf, value) => {
+                let number = value.as_u64().ok_or(invalid)?;
+                match self.option_for(value) {
+                    Some(_) => Ok(()),
+                    None => Err(ValueError::NoOption {
+                        question_id: self.id,
+                        value: number,
+                    }),
+                }
+            }
+            (QuestionKind::Numeric, value) => {
+                let number = value.as_u64().ok_or(invalid.clone())?;
+                let (display, bits) = self.number_format();
+                if number & !bit_mask(bits) != 0 {
+                    return Err(invalid);
+                }
+                // Signed numerics compare in two's complement of their width
+                let signed = |number| match display {
+                    EFI_IFR_DISPLAY_INT_DEC => sign_extend(number, bits) as i128,
+                    _ => number as i128,
+                };
+                let (min, max) = (signed(self.min), signed(self.max));
+                match (min..=max).contains(&signed(number)) || self.min == self.max {
+                    true => Ok(()),
+                    false => Err(ValueError::OutOfRange {
+                        question_id: self.id,
+                        value: signed(number),
+                        min,
+                        max,
+                    }),
+                }
+            }
+            (QuestionKind::Checkbox, value) => match value.as_u64() {
+                Some(0 | 1) => Ok(()),
+                _ => Err(invalid),
+            },
+            (QuestionKind::Date, IfrTypeValue::Date { .. })
+            | (QuestionKind::Time, IfrTypeValue::Time { .. })
+            | (QuestionKind::Ref { .. }, IfrTypeValue::Ref(_)) => Ok(()),
+            (QuestionKind::String | QuestionKind::Password, IfrTypeValue::Buffer(buffer)) => {
+                let units = buffer.len() / 2;
+                match buffer.len() % 2 == 0 && units <= self.max as usize {
+                    true if units >= self.min as usize => Ok(()),
+                    _ => Err(invalid),
+                }
+            }
+            (QuestionKind::OrderedList, IfrTypeValue::Buffer(buffer)) => {
+                if buffer.len() > self.width {
+                    return Err(invalid);
+                }
+                let Some(r#type) = self.options.first().map(|option| option.value.type_code())
+                else {
+                    return Err(invalid);
+                };
+                let item_width = self.width / (self.max as usize).max(1);
+                for item in buffer.chunks(item_width.max(1)) {
+                    let item = IfrTypeValue::from_bytes(r#type, item).ok_or(invalid.clone())?;
+                    if self.option_for(&item).is_none() {
+                        return Err(ValueError::NoOption {
+                            question_id: self.id,
+                            value: item.as_u64().unwrap_or_default(),
+                        });
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(invalid),
+        }
+    }
+
+    /// Validate `value` and encode it into the contents of the varstore of the
+    /// question, other bits of a bit field are kept
+    pub fn write_value(
+        &self,
+        contents: &mut VarStoreContents,
+        value: &IfrTypeValue,
+    ) -> Result<(), ValueError> {
+        self.validate_value(value)?;
+        self.encode_value(contents, value)
+    }
+
+    /// Encode `value` into the contents of the varstore without validation
+    pub(crate) fn encode_value(
+        &self,
+        contents: &mut VarStoreContents,
+        value: &IfrTypeValue,
+    ) -> Result<(), ValueError> {
+        let no_storage = ValueError::NoStorage {
+            question_id: self.id,
+        };
+        match contents {
+            VarStoreContents::Buffer(buffer) => {
+                let start = self.byte_offset().ok_or(no_storage)?;
+                let size = buffer.len();
+                let bytes = start
+                    .checked_add(self.width)
+                    .and_then(|end| buffer.get_mut(start..end))
+                    .ok_or(ValueError::OutOfStorage {
+                        question_id: self.id,
+                        offset: start,
+                        width: self.width,
+                        size,
+                    })?;
+                self.encode_bytes(bytes, value)
+            }
+            VarStoreContents::NameValue(values) => {
+                let name = self.storage_name().ok_or(no_storage)?;
+                let mut bytes = vec![0u8; self.width];
+                self.encode_bytes(&mut bytes, value)?;
+                values.insert(String::from(name), bytes);
+                Ok(())
+            }
+        }
+    }
+
+    /// Encode `value` into the bytes of the storage of the question
+    fn encode_bytes(&self, bytes: &mut [u8], value: &IfrTypeValue) -> Result<(), ValueError> {
+        match (&self.storage, value) {
+            (QuestionStorage::Bits { offset, width }, value) => {
+                let number = value.as_u64().unwrap_or_default();
+                let len = bytes.len().min(8);
+                let mut field = [0u8; 8];
+                field[..len].copy_from_slice(&bytes[..len]);
+                let shift = offset % 8;
+                let mask = bit_mask(*width) << shift;
+                let field = (u64::from_le_bytes(field) & !mask) | ((number << shift) & mask);
+                bytes[..len].copy_from_slice(&field.to_le_bytes()[..len]);
+            }
+            (_, IfrTypeValue::Buffer(buffer)) => {
+                let len = buffer.len().min(bytes.len());
+                bytes.fill(0);
+                bytes[..len].copy_from_slice(&buffer[..len]);
+            }
+            (_, value) if value.as_u64().is_some() => {
+                let number = value.as_u64().unwrap_or_default().to_le_bytes();
+                let len = bytes.len().min(number.len());
+                bytes[..len].copy_from_slice(&number[..len]);
+            }
+            (_, value) => {
+                let data = value.to_bytes();
+                if data.len() != bytes.len() {
+                    return Err(ValueError::InvalidValue {
+                        question_id: self.id,
+                    });
+                }
+                bytes.copy_from_slice(&data);
+            }
+        }
+        Ok(())
+    }
+
+    /// Contents of the varstore of the question before and after writing `value`
+    pub fn change_value(
+        &self,
+        contents: &VarStoreContents,
+        value: &IfrTypeValue,
+    ) -> Result<VarStoreChange, ValueError> {
+        let var_store_id = self.varstore.ok_or(ValueError::NoStorage {
+            question_id: self.id,
+        })?;
+        let mut new = contents.clone();
+        self.write_value(&mut new, value)?;
+        Ok(VarStoreChange {
+            var_store_id,
+            old: contents.clone(),
+            new,
+        })
+    }
+
+    /// Option by its text, case is ignored if there is no exact match
+    fn option_by_text(&self, text: &str) -> Option<&QuestionOption> {
+        self.options
+            .iter()
+            .find(|option| option.text.text.as_deref() == Some(text))
+            .or_else(|| {
+                self.options.iter().find(|option| {
+                    (option.text.text.as_deref())
+                        .is_some_and(|option| option.eq_ignore_ascii_case(text))
+                })
+            })
+    }
+
+    /// Value of a number in the storage type of a numeric or one-of
+    pub(crate) fn number_value(&self, number: u64) -> Option<IfrTypeValue> {
+        let r#type = match (&self.storage, self.width) {
+            (QuestionStorage::Bits { .. }, _) => EFI_IFR_TYPE_NUM_SIZE_32,
+            (_, 1) => EFI_IFR_TYPE_NUM_SIZE_8,
+            (_, 2) => EFI_IFR_TYPE_NUM_SIZE_16,
+            (_, 4) => EFI_IFR_TYPE_NUM_SIZE_32,
+            _ => EFI_IFR_TYPE_NUM_SIZE_64,
+        };
+        number_of_type(r#type, number)
+    }
+
+    /// Number in the EFI_IFR_DISPLAY format of a numeric or one-of
+    fn format_number(&self, number: u64) -> String {
+        let (display, bits) = self.number_format();
+        match display {
+            EFI_IFR_DISPLAY_UINT_HEX => format!("0x{:X}", number),
+            EFI_IFR_DISPLAY_INT_DEC => format!("{}", sign_extend(number, bits)),
+            _ => format!("{}", number),
+        }
+    }
+
+    /// EFI_IFR_DISPLAY_x format and width in bits of a numeric or one-of
+    fn number_format(&self) -> (u8, u8) {
+        match self.storage {
+            QuestionStorage::Bits { width, .. } => (
+                match self.op_flags & EDKII_IFR_DISPLAY_BIT {
+                    EDKII_IFR_DISPLAY_INT_DEC_BIT => EFI_IFR_DISPLAY_INT_DEC,
+                    EDKII_IFR_DISPLAY_UINT_HEX_BIT => EFI_IFR_DISPLAY_UINT_HEX,
+                    _ => EFI_IFR_DISPLAY_UINT_DEC,
+                },
+                width,
+            ),
+            _ => (self.op_flags & EFI_IFR_DISPLAY, self.width as u8 * 8),
+        }
+    }
+}
+
+impl FormSet {
+    /// Current values of the questions stored in varstores, `contents` holds
+    /// the varstore contents keyed by VarStoreId.
+    ///
+    /// Questions of varstores missing from `contents` are skipped.
+    pub fn question_values<'a>(
+        &'a self,
+        contents: &BTreeMap<u16, VarStoreContents>,
+    ) -> Vec<QuestionValue<'a>> {
+        self.questions()
+            .filter(|question| question.storage != QuestionStorage::None)
+            .filter_map(|question| {
+                let data = contents.get(&question.varstore?)?;
+                Some(QuestionValue {
+                    question,
+                    value: question.read_value(data),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decimal, `0x` hexadecimal or negative decimal number, negative numbers in two's complement
+fn parse_number(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16).ok();
+    }
+    match text.strip_prefix('-') {
+        Some(_) => text.parse::<i64>().ok().map(|number| number as u64),
+        None => text.parse().ok(),
+    }
+}
+
+/// Integer value of EFI_IFR_TYPE_NUM_SIZE_x `r#type`, `None` if `number` does not fit
+fn number_of_type(r#type: u8, number: u64) -> Option<IfrTypeValue> {
+    Some(match r#type {
+        EFI_IFR_TYPE_NUM_SIZE_8 => IfrTypeValue::U8(u8::try_from(number).ok()?),
+        EFI_IFR_TYPE_NUM_SIZE_16 => IfrTypeValue::U16(u16::try_from(number).ok()?),
+        EFI_IFR_TYPE_NUM_SIZE_32 => IfrTypeValue::U32(u32::try_from(number).ok()?),
+        EFI_IFR_TYPE_NUM_SIZE_64 => IfrTypeValue::U64(number),
+        _ => return None,
+    })
+}
+
+/// Two's complement value of the low `bits` bits of `number`
+fn sign_extend(number: u64, bits: u8) -> i64 {
+    match bits {
+        1..64 if number >> (bits - 1) & 1 != 0 => (number | !bit_mask(bits)) as i64,
+        _ => number as i64,
+    }
+}
+
+/// Mask of the low `width` bits
+fn bit_mask(width: u8) -> u64 {
+    match width {
+        64.. => u64::MAX,
+        width => (1 << width) - 1,
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_header::StringTable;
+    use uefi::{Guid, guid};
+
+    const FORM_SET: Guid = guid!("32c66610-94df-4d6d-984f-8cbe44519b87");
+    const VARSTORE: Guid = guid!("3a3aab90-7886-4f2e-88f8-597a951b78bc");
+    const SETUP: u16 = 1;
+    const SPEED_STORE: u16 = 2;
+
+    /// Form set with an 8 byte buffer varstore, a name/value varstore and the questions of
+    /// `body` in its form
+    fn form_set<F>(body: F) -> FormSet
+    where
+        F: FnOnce(&mut FormBuilder) -> &mut FormBuilder,
+    {
+        let data = FormSetBuilder::new(FORM_SET, 0x02, 0x03)
+            .varstore(SETUP, VARSTORE, "Setup", 8)
+            .varstore_name_value(SPEED_STORE, VARSTORE)
+            .form(1, 0x04, body)
+            .build()
+            .unwrap();
+        let mut strings = StringTable::new(String::from("en-US"));
+        strings.insert(0x20, String::from("Speed"));
+        strings.insert(0x30, String::from("First"));
+        strings.insert(0x31, String::from("Second"));
+        strings.insert(0x32, String::from("Third"));
+        FormSet::from_tree(&IfrTree::parse(&data).unwrap(), &strings).remove(0)
+    }
+
+    fn question(form_set: &FormSet, question_id: u16) -> &Question {
+        form_set
+            .questions()
+            .find(|question| question.id == question_id)
+            .unwrap()
+    }
+
+    fn header(question_id: u16, var_store_info: u16) -> IfrQuestionHeader {
+        IfrQuestionHeader::new(question_id, 0x10, 0x11).with_storage(SETUP, var_store_info)
+    }
+
+    fn byte(offset: usize, old: u8, new: u8) -> ContentDiff {
+        ContentDiff::Byte { offset, old, new }
+    }
+
+    /// Questions in the scope of EDKII_IFR_BIT_VARSTORE_GUID, VarOffset is in bits
+    fn bit_fields(f: &mut FormBuilder) -> &mut FormBuilder {
+        let scope = IfrOp::Guid {
+            guid: EDKII_IFR_BIT_VARSTORE_GUID,
+            data: Vec::new(),
+        };
+        f.scope(scope, |f| {
+            f.checkbox(header(1, 3), 0, |f| f).numeric(
+                header(2, 6),
+                4 | EDKII_IFR_DISPLAY_UINT_DEC_BIT,
+                0,
+                15,
+                1,
+                |f| f,
+            )
+        })
+    }
+
+    #[test]
+    fn bit_field_keeps_other_bits() {
+        let form_set = form_set(bit_fields);
+        let checkbox = question(&form_set, 1);
+        assert_eq!(
+            checkbox.storage,
+            QuestionStorage::Bits {
+                offset: 3,
+                width: 1,
+            }
+        );
+
+        let contents = VarStoreContents::Buffer(vec![0xFF; 8]);
+        let change = checkbox
+            .change_value(&contents, &IfrTypeValue::Boolean(false))
+            .unwrap();
+        assert_eq!(change.diff(), [byte(0, 0xFF, 0xF7)]);
+        assert_eq!(
+            checkbox.read_value(&change.new),
+            Some(IfrTypeValue::Boolean(false))
+        );
+    }
+
+    #[test]
+    fn bit_field_straddling_a_byte() {
+        let form_set = form_set(bit_fields);
+        let numeric = question(&form_set, 2);
+        // Bits 6..10 span the first two bytes
+        assert_eq!(numeric.width, 2);
+
+        let contents = VarStoreContents::Buffer(vec![0xFF; 8]);
+        let change = numeric
+            .change_value(&contents, &IfrTypeValue::U32(0))
+            .unwrap();
+        assert_eq!(change.diff(), [byte(0, 0xFF, 0x3F), byte(1, 0xFF, 0xFC)]);
+
+        let contents = VarStoreContents::Buffer(vec![0; 8]);
+        let value = numeric.parse_value("10").unwrap();
+        assert_eq!(value, IfrTypeValue::U32(10));
+        let change = numeric.change_value(&contents, &value).unwrap();
+        assert_eq!(change.diff(), [byte(0, 0x00, 0x80), byte(1, 0x00, 0x02)]);
+        assert_eq!(numeric.read_value(&change.new), Some(value));
+
+        // Wider than the 4 bits of the field
+        assert_eq!(
+            numeric.change_value(&contents, &IfrTypeValue::U32(16)),
+            Err(ValueError::InvalidValue { question_id: 2 })
+        );
+    }
+
+    #[test]
+    fn ordered_list_items_have_option_width() {
+        let form_set = form_set(|f| {
+            let list = IfrOp::OrderedList(IfrOrderedList {
+                question: header(3, 2),
+                max_containers: 3,
+                flags: 0,
+            });
+            f.question(list, |f| {
+                f.option(0x30, IfrTypeValue::U16(0x101), 0)
+                    .option(0x31, IfrTypeValue::U16(0x202), 0)
+                    .option(0x32, IfrTypeValue::U16(0x303), 0)
+            })
+        });
+        let list = question(&form_set, 3);
+        assert_eq!(list.width, 6);
+
+        let value = list.parse_value("Third, First").unwrap();
+        assert_eq!(value, IfrTypeValue::Buffer(vec![0x03, 0x03, 0x01, 0x01]));
+
+        let contents = VarStoreContents::Buffer(vec![0xFF; 8]);
+        let change = list.change_value(&contents, &value).unwrap();
+        // Unused containers are cleared
+        assert_eq!(
+            change.diff(),
+            [
+                byte(2, 0xFF, 0x03),
+                byte(3, 0xFF, 0x03),
+                byte(4, 0xFF, 0x01),
+                byte(5, 0xFF, 0x01),
+                byte(6, 0xFF, 0x00),
+                byte(7, 0xFF, 0x00),
+            ]
+        );
+        let read = list.read_value(&change.new).unwrap();
+        assert_eq!(list.format_value(&read), "Third, First");
+
+        assert_eq!(
+            list.parse_value("Second, 0x404")
+                .and_then(|value| list.validate_value(&value)),
+            Err(ValueError::NoOption {
+                question_id: 3,
+                value: 0x404,
+            })
+        );
+    }
+
+    #[test]
+    fn signed_numeric_range() {
+        let form_set = form_set(|f| {
+            f.numeric(
+                header(4, 0),
+                EFI_IFR_NUMERIC_SIZE_1 | EFI_IFR_DISPLAY_INT_DEC,
+                -10i8 as u8 as u64,
+                10,
+                1,
+                |f| f,
+            )
+        });
+        let numeric = question(&form_set, 4);
+
+        let value = numeric.parse_value("-5").unwrap();
+        assert_eq!(value, IfrTypeValue::U8(0xFB));
+        assert_eq!(numeric.validate_value(&value), Ok(()));
+        assert_eq!(numeric.format_value(&value), "-5");
+
+        let contents = VarStoreContents::Buffer(vec![0; 8]);
+        let change = numeric.change_value(&contents, &value).unwrap();
+        assert_eq!(change.diff(), [byte(0, 0x00, 0xFB)]);
+
+        // Compared unsigned, the minimum 0xF6 would be above the maximum
+        let below = numeric.parse_value("-11").unwrap();
+        assert_eq!(
+            numeric.validate_value(&below),
+            Err(ValueError::OutOfRange {
+                question_id: 4,
+                value: -11,
+                min: -10,
+                max: 10,
+            })
+        );
+        assert_eq!(
+            numeric.validate_value(&IfrTypeValue::U8(0x0B)),
+            Err(ValueError::OutOfRange {
+                question_id: 4,
+                value: 11,
+                min: -10,
+                max: 10,
+            })
+        );
+        // Does not fit in one byte
+        assert_eq!(
+            numeric.parse_value("-200"),
+            Err(ValueError::InvalidValue { question_id: 4 })
+        );
+    }
+
+    #[test]
+    fn signed_bit_field() {
+        let form_set = form_set(|f| {
+            let scope = IfrOp::Guid {
+                guid: EDKII_IFR_BIT_VARSTORE_GUID,
+                data: Vec::new(),
+            };
+            f.scope(scope, |f| {
+                f.numeric(
+                    header(5, 4),
+                    4 | EDKII_IFR_DISPLAY_INT_DEC_BIT,
+                    0x8,
+                    0x7,
+                    1,
+                    |f| f,
+                )
+            })
+        });
+        let numeric = question(&form_set, 5);
+
+        // Two's complement of the 4 bit width, not of the U32 it is read as
+        let value = numeric.parse_value("-3").unwrap();
+        assert_eq!(value, IfrTypeValue::U32(13));
+        assert_eq!(numeric.validate_value(&value), Ok(()));
+        assert_eq!(numeric.format_value(&value), "-3");
+        assert_eq!(
+            numeric.parse_value("-9"),
+            Err(ValueError::InvalidValue { question_id: 5 })
+        );
+
+        let contents = VarStoreContents::Buffer(vec![0x0F; 8]);
+        let change = numeric.change_value(&contents, &value).unwrap();
+        assert_eq!(change.diff(), [byte(0, 0x0F, 0xDF)]);
+        assert_eq!(numeric.read_value(&change.new), Some(value));
+    }
+
+    #[test]
+    fn name_value_zero_extended() {
+        let form_set = form_set(|f| {
+            let question = IfrQuestionHeader::new(6, 0x10, 0x11).with_storage(SPEED_STORE, 0x20);
+            let flags = EFI_IFR_NUMERIC_SIZE_2 | EFI_IFR_DISPLAY_UINT_HEX;
+            f.numeric(question, flags, 0, 0xFFFF, 1, |f| f)
+        });
+        let numeric = question(&form_set, 6);
+        assert_eq!(
+            numeric.storage,
+            QuestionStorage::Name(HiiString {
+                id: 0x20,
+                text: Some(String::from("Speed")),
+            })
+        );
+
+        // Values shorter than the question are zero extended
+        let contents =
+            VarStoreContents::NameValue(BTreeMap::from([(String::from("Speed"), vec![0x2C])]));
+        assert_eq!(numeric.read_value(&contents), Some(IfrTypeValue::U16(0x2C)));
+
+        let change = numeric
+            .change_value(&contents, &IfrTypeValue::U16(0x1234))
+            .unwrap();
+        assert_eq!(
+            change.diff(),
+            [ContentDiff::Name {
+                name: String::from("Speed"),
+                old: Some(vec![0x2C]),
+                new: Some(vec![0x34, 0x12]),
+            }]
+        );
+
+        let unset = VarStoreContents::NameValue(BTreeMap::new());
+        let change = numeric
+            .change_value(&unset, &IfrTypeValue::U16(0x2C))
+            .unwrap();
+        assert_eq!(
+            change.diff(),
+            [ContentDiff::Name {
+                name: String::from("Speed"),
+                old: None,
+                new: Some(vec![0x2C, 0x00]),
+            }]
+        );
+    }
+}
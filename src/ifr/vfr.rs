@@ -0,0 +1,909 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::fmt::Write;
+use uefi::Guid;
+
+use super::*;
+use crate::package_header::{HiiFormPackageHdr, StringTable};
+
+/// Options of the VFR decompiler.
+#[derive(Debug, Clone, Copy)]
+pub struct VfrOptions {
+    /// Print the text of resolved strings instead of STRING_TOKEN(..)
+    pub literal_strings: bool,
+    /// Spaces per nesting level
+    pub indent: usize,
+}
+
+impl Default for VfrOptions {
+    fn default() -> Self {
+        Self {
+            literal_strings: true,
+            indent: 2,
+        }
+    }
+}
+
+/// Decompile a form package into VFR-like source.
+pub fn decompile_package(
+    package: &HiiFormPackageHdr,
+    strings: &StringTable,
+) -> Result<String, IfrError> {
+    Ok(decompile(&IfrTree::from_package(package)?, strings))
+}
+
+/// Decompile an IFR tree into VFR-like source.
+pub fn decompile(tree: &IfrTree, strings: &StringTable) -> String {
+    decompile_with(tree, strings, VfrOptions::default())
+}
+
+pub fn decompile_with(tree: &IfrTree, strings: &StringTable, options: VfrOptions) -> String {
+    decompile_with_registry(tree, strings, options, None)
+}
+
+/// Decompile an IFR tree, EFI_IFR_GUID opcodes are decoded with the decoders of
+/// `registry` and vendor views are printed as a comment.
+pub fn decompile_with_registry(
+    tree: &IfrTree,
+    strings: &StringTable,
+    options: VfrOptions,
+    registry: Option<&IfrGuidOpRegistry>,
+) -> String {
+    let mut writer = VfrWriter {
+        strings,
+        options,
+        registry,
+        varstores: BTreeMap::new(),
+        out: String::new(),
+        depth: 0,
+    };
+
+    // Varstores are referenced by name in question declarations
+    for (node, _) in tree.walk() {
+        let (id, name) = match node.op() {
+            IfrOp::VarStore(op) => (op.var_store_id, op.name.clone()),
+            IfrOp::VarStoreEfi(op) => (op.var_store_id, op.name.clone()),
+            IfrOp::VarStoreNameValue(op) => (
+                op.var_store_id,
+                format!("NameValue_0x{:04X}", op.var_store_id),
+            ),
+            _ => continue,
+        };
+        writer.varstores.insert(id, name);
+    }
+
+    for root in &tree.roots {
+        writer.node(root);
+    }
+    writer.out
+}
+
+/// GUID in VFR notation
+pub fn vfr_guid(guid: &Guid) -> String {
+    let b = guid.to_bytes();
+    format!(
+        "{{0x{:08x}, 0x{:04x}, 0x{:04x}, {{0x{:02x}, 0x{:02x}, 0x{:02x}, 0x{:02x}, 0x{:02x}, 0x{:02x}, 0x{:02x}, 0x{:02x}}}}}",
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        u16::from_le_bytes([b[4], b[5]]),
+        u16::from_le_bytes([b[6], b[7]]),
+        b[8],
+        b[9],
+        b[10],
+        b[11],
+        b[12],
+        b[13],
+        b[14],
+        b[15]
+    )
+}
+
+/// Name given to a question in the decompiled source
+pub fn vfr_question_name(question_id: u16) -> String {
+    format!("Q_0x{:04X}", question_id)
+}
+
+fn flag_list(flags: u8, names: &[(u8, &str)]) -> String {
+    let mut list: Vec<String> = names
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| String::from(*name))
+        .collect();
+    let known = names.iter().fold(0, |acc, (bit, _)| acc | bit);
+    if flags & !known != 0 {
+        list.push(format!("0x{:02X}", flags & !known));
+    }
+    list.join(" | ")
+}
+
+const QUESTION_FLAGS: &[(u8, &str)] = &[
+    (EFI_IFR_FLAG_READ_ONLY, "READ_ONLY"),
+    (EFI_IFR_FLAG_CALLBACK, "INTERACTIVE"),
+    (EFI_IFR_FLAG_RESET_REQUIRED, "RESET_REQUIRED"),
+    (0x20, "REST_STYLE"),
+    (0x40, "RECONNECT_REQUIRED"),
+    (0x80, "OPTIONS_ONLY"),
+];
+
+fn numeric_flags(flags: u8) -> String {
+    let size = [
+        "NUMERIC_SIZE_1",
+        "NUMERIC_SIZE_2",
+        "NUMERIC_SIZE_4",
+        "NUMERIC_SIZE_8",
+    ][(flags & EFI_IFR_NUMERIC_SIZE) as usize];
+    match flags & EFI_IFR_DISPLAY {
+        EFI_IFR_DISPLAY_INT_DEC => format!("{} | DISPLAY_INT_DEC", size),
+        EFI_IFR_DISPLAY_UINT_DEC => format!("{} | DISPLAY_UINT_DEC", size),
+        EFI_IFR_DISPLAY_UINT_HEX => format!("{} | DISPLAY_UINT_HEX", size),
+        other => format!("{} | 0x{:02X}", size, other),
+    }
+}
+
+struct VfrWriter<'a> {
+    strings: &'a StringTable,
+    options: VfrOptions,
+    registry: Option<&'a IfrGuidOpRegistry>,
+    varstores: BTreeMap<u16, String>,
+    out: String,
+    depth: usize,
+}
+
+impl VfrWriter<'_> {
+    fn line(&mut self, text: &str) {
+        if text.is_empty() {
+            self.out.push('\n');
+            return;
+        }
+        let _ = writeln!(
+            self.out,
+            "{:indent$}{}",
+            "",
+            text,
+            indent = self.depth * self.options.indent
+        );
+    }
+
+    fn string(&self, id: u16) -> String {
+        match self.strings.get(id) {
+            Some(text) if self.options.literal_strings => format!("{:?}", text),
+            _ => format!("STRING_TOKEN(0x{:04X})", id),
+        }
+    }
+
+    fn value(&self, value: &IfrTypeValue) -> String {
+        match value {
+            IfrTypeValue::U8(v) => format!("{}", v),
+            IfrTypeValue::U16(v) => format!("{}", v),
+            IfrTypeValue::U32(v) => format!("{}", v),
+            IfrTypeValue::U64(v) => format!("{}", v),
+            IfrTypeValue::Boolean(v) => String::from(if *v { "TRUE" } else { "FALSE" }),
+            IfrTypeValue::Time {
+                hour,
+                minute,
+                second,
+            } => format!("{:02}:{:02}:{:02}", hour, minute, second),
+            IfrTypeValue::Date { year, month, day } => format!("{}/{}/{}", year, month, day),
+            IfrTypeValue::String(id) | IfrTypeValue::Action(id) => self.string(*id),
+            IfrTypeValue::Buffer(buffer) => {
+                let bytes: Vec<String> = buffer.iter().map(|b| format!("0x{:02x}", b)).collect();
+                format!("{{{}}}", bytes.join(", "))
+            }
+            IfrTypeValue::Ref(reference) => format!(
+                "{};{};{};{}",
+                reference.question_id,
+                reference.form_id,
+                vfr_guid(&reference.form_set_guid),
+                reference.device_path
+            ),
+            IfrTypeValue::Other | IfrTypeValue::Undefined => String::from("UNDEFINED"),
+        }
+    }
+
+    fn varid(&self, question: &IfrQuestionHeader) -> Option<String> {
+        if question.var_store_id == 0 {
+            return None;
+        }
+        let name = self
+            .varstores
+            .get(&question.var_store_id)
+            .cloned()
+            .unwrap_or_else(|| format!("VarStore_0x{:04X}", question.var_store_id));
+        Some(format!("{}[0x{:04X}]", name, question.var_store_info))
+    }
+
+    /// Render a postfix expression as VFR infix text
+    fn expression(&self, ops: &[IfrOp]) -> String {
+        let mut stack: Vec<String> = Vec::new();
+        let mut index = 0;
+
+        while index < ops.len() {
+            let op = &ops[index];
+            index += 1;
+
+            let mut pop = || stack.pop().unwrap_or_else(|| String::from("?"));
+            let text = match op {
+                IfrOp::End | IfrOp::Value => continue,
+                IfrOp::EqIdVal { question_id, value } => {
+                    format!("ideqval {} == {}", vfr_question_name(*question_id), value)
+                }
+                IfrOp::EqIdId {
+                    question_id_1,
+                    question_id_2,
+                } => format!(
+                    "ideqid {} == {}",
+                    vfr_question_name(*question_id_1),
+                    vfr_question_name(*question_id_2)
+                ),
+                IfrOp::EqIdValList {
+                    question_id,
+                    value_list,
+                } => {
+                    let values: Vec<String> = value_list.iter().map(|v| format!("{}", v)).collect();
+                    format!(
+                        "ideqvallist {} == {}",
+                        vfr_question_name(*question_id),
+                        values.join(" ")
+                    )
+                }
+                IfrOp::Not => format!("NOT ({})", pop()),
+                IfrOp::BitwiseNot => format!("~({})", pop()),
+                IfrOp::And
+                | IfrOp::Or
+                | IfrOp::Equal
+                | IfrOp::NotEqual
+                | IfrOp::GreaterThan
+                | IfrOp::GreaterEqual
+                | IfrOp::LessThan
+                | IfrOp::LessEqual
+                | IfrOp::BitwiseAnd
+                | IfrOp::BitwiseOr
+                | IfrOp::ShiftLeft
+                | IfrOp::ShiftRight
+                | IfrOp::Add
+                | IfrOp::Subtract
+                | IfrOp::Multiply
+                | IfrOp::Divide
+                | IfrOp::Modulo => {
+                    let right = pop();
+                    let left = pop();
+                    let operator = match op {
+                        IfrOp::And => "AND",
+                        IfrOp::Or => "OR",
+                        IfrOp::Equal => "==",
+                        IfrOp::NotEqual => "!=",
+                        IfrOp::GreaterThan => ">",
+                        IfrOp::GreaterEqual => ">=",
+                        IfrOp::LessThan => "<",
+                        IfrOp::LessEqual => "<=",
+                        IfrOp::BitwiseAnd => "&",
+                        IfrOp::BitwiseOr => "|",
+                        IfrOp::ShiftLeft => "<<",
+                        IfrOp::ShiftRight => ">>",
+                        IfrOp::Add => "+",
+                        IfrOp::Subtract => "-",
+                        IfrOp::Multiply => "*",
+                        IfrOp::Divide => "/",
+                        _ => "%",
+                    };
+                    format!("({} {} {})", left, operator, right)
+                }
+                IfrOp::Uint8(v) => format!("{}", v),
+                IfrOp::Uint16(v) => format!("{}", v),
+                IfrOp::Uint32(v) => format!("{}", v),
+                IfrOp::Uint64(v) => format!("{}", v),
+                IfrOp::True => String::from("TRUE"),
+                IfrOp::False => String::from("FALSE"),
+                IfrOp::Zero => String::from("ZERO"),
+                IfrOp::One => String::from("ONE"),
+                IfrOp::Ones => String::from("ONES"),
+                IfrOp::Undefined => String::from("UNDEFINED"),
+                IfrOp::Version => String::from("version"),
+                IfrOp::This => String::from("pushthis"),
+                IfrOp::Read => String::from("read"),
+                IfrOp::Write => format!("write({})", pop()),
+                IfrOp::Dup => String::from("dup"),
+                IfrOp::Security { permissions } => format!("security({})", vfr_guid(permissions)),
+                IfrOp::QuestionRef1 { question_id } => {
+                    format!("questionref({})", vfr_question_name(*question_id))
+                }
+                IfrOp::QuestionRef2 => format!("questionref({})", pop()),
+                IfrOp::QuestionRef3 { device_path, guid } => {
+                    let id = pop();
+                    match (device_path, guid) {
+                        (Some(path), Some(guid)) => format!(
+                            "questionref({}, path = {}, uuid = {})",
+                            id,
+                            self.string(*path),
+                            vfr_guid(guid)
+                        ),
+                        (Some(path), None) => {
+                            format!("questionref({}, path = {})", id, self.string(*path))
+                        }
+                        _ => format!("questionref({})", id),
+                    }
+                }
+                IfrOp::RuleRef { rule_id } => format!("ruleref(Rule_0x{:02X})", rule_id),
+                IfrOp::StringRef1 { string_id } => {
+                    format!("stringref({})", self.string(*string_id))
+                }
+                IfrOp::StringRef2 => format!("stringref({})", pop()),
+                IfrOp::Get {
+                    var_store_id,
+                    var_store_info,
+                    ..
+                } => format!(
+                    "get(VarStore_0x{:04X}[0x{:04X}])",
+                    var_store_id, var_store_info
+                ),
+                IfrOp::Set {
+                    var_store_id,
+                    var_store_info,
+                    ..
+                } => format!(
+                    "set(VarStore_0x{:04X}[0x{:04X}], {})",
+                    var_store_id,
+                    var_store_info,
+                    pop()
+                ),
+                IfrOp::ToBoolean => format!("boolval({})", pop()),
+                IfrOp::ToUint => format!("uintval({})", pop()),
+                IfrOp::ToString { format } => format!("stringval(format = {}, {})", format, pop()),
+                IfrOp::ToLower => format!("tolower({})", pop()),
+                IfrOp::ToUpper => format!("toupper({})", pop()),
+                IfrOp::Length => format!("length({})", pop()),
+                IfrOp::Catenate => {
+                    let right = pop();
+                    let left = pop();
+                    format!("catenate({}, {})", left, right)
+                }
+                IfrOp::Match => {
+                    let string = pop();
+                    let pattern = pop();
+                    format!("match({}, {})", pattern, string)
+                }
+                IfrOp::Match2 { syntax_type } => {
+                    let pattern = pop();
+                    let string = pop();
+                    format!("match2({}, {}, {})", string, pattern, vfr_guid(syntax_type))
+                }
+                IfrOp::Mid | IfrOp::Token | IfrOp::Conditional => {
+                    let third = pop();
+                    let second = pop();
+                    let first = pop();
+                    match op {
+                        IfrOp::Mid => format!("mid({}, {}, {})", first, second, third),
+                        IfrOp::Token => format!("token({}, {}, {})", first, second, third),
+                        _ => format!("cond({} ? {} : {})", first, second, third),
+                    }
+                }
+                IfrOp::Find { format } => {
+                    let index = pop();
+                    let needle = pop();
+                    let haystack = pop();
+                    format!(
+                        "find(format = {}, {}, {}, {})",
+                        format, haystack, needle, index
+                    )
+                }
+                IfrOp::Span { flags } => {
+                    let index = pop();
+                    let charset = pop();
+                    let source = pop();
+                    format!(
+                        "span(flags = 0x{:02X}, {}, {}, {})",
+                        flags, source, charset, index
+                    )
+                }
+                IfrOp::Map => {
+                    let key = pop();
+                    let mut depth = 0;
+                    let end = ops[index..]
+                        .iter()
+                        .position(|op| match op {
+                            IfrOp::Map => {
+                                depth += 1;
+                                false
+                            }
+                            IfrOp::End if depth == 0 => true,
+                            IfrOp::End => {
+                                depth -= 1;
+                                false
+                            }
+                            _ => false,
+                        })
+                        .map_or(ops.len(), |end| index + end);
+                    let pairs = self.expression_list(&ops[index..end]);
+                    index = end + 1;
+
+                    let pairs: Vec<String> = pairs
+                        .chunks(2)
+                        .map(|pair| format!("{}, {};", pair[0], pair.get(1).map_or("?", |v| v)))
+                        .collect();
+                    format!("map({} : {})", key, pairs.join(" "))
+                }
+                op => format!("/* {:?} */", op.op_code()),
+            };
+            stack.push(text);
+        }
+        stack.join(" ")
+    }
+
+    /// Render a list of expressions, one entry per value left on the stack
+    fn expression_list(&self, ops: &[IfrOp]) -> Vec<String> {
+        // Each complete sub-expression pushes exactly one value; split by replaying
+        // the stack depth of every opcode
+        let mut list = Vec::new();
+        let mut start = 0;
+        let mut depth: isize = 0;
+        let mut scope = 0;
+
+        for (index, op) in ops.iter().enumerate() {
+            if scope > 0 {
+                match op {
+                    IfrOp::Map => scope += 1,
+                    IfrOp::End => scope -= 1,
+                    _ => {}
+                }
+                if scope == 0 {
+                    depth += 1;
+                }
+            } else {
+                match op {
+                    IfrOp::Map => {
+                        // Pops the key, pushes the result once its scope closes
+                        depth -= 1;
+                        scope = 1;
+                    }
+                    op => depth += stack_effect(op),
+                }
+            }
+            if scope == 0 && depth == 1 {
+                list.push(self.expression(&ops[start..=index]));
+                start = index + 1;
+                depth = 0;
+            }
+        }
+        list
+    }
+
+    fn condition(&mut self, keyword: &str, node: &IfrNode, prompt: Option<String>) {
+        let (expression, body) = split_condition(&node.children);
+        let expression = self.expression(&expression);
+        match prompt {
+            Some(prompt) => self.line(&format!("{} {} {};", keyword, prompt, expression)),
+            None => self.line(&format!("{} {};", keyword, expression)),
+        }
+        self.depth += 1;
+        for child in body {
+            self.node(child);
+        }
+        self.depth -= 1;
+        self.line("endif;");
+    }
+
+    fn children(&mut self, node: &IfrNode) {
+        self.depth += 1;
+        for child in &node.children {
+            self.node(child);
+        }
+        self.depth -= 1;
+    }
+
+    fn question(
+        &mut self,
+        keyword: &str,
+        question: &IfrQuestionHeader,
+        op_flags: Option<String>,
+        extra: &[String],
+        node: &IfrNode,
+    ) {
+        let mut head = format!(
+            "{} name = {},",
+            keyword,
+            vfr_question_name(question.question_id)
+        );
+        if let Some(varid) = self.varid(question) {
+            head += &format!(" varid = {},", varid);
+        }
+        head += &format!(" questionid = 0x{:04X},", question.question_id);
+        self.line(&head);
+
+        self.depth += 1;
+        self.line(&format!(
+            "prompt = {},",
+            self.string(question.header.prompt)
+        ));
+        self.line(&format!("help = {},", self.string(question.header.help)));
+        let flags: Vec<String> = [Some(flag_list(question.flags, QUESTION_FLAGS)), op_flags]
+            .into_iter()
+            .flatten()
+            .filter(|flags| !flags.is_empty())
+            .collect();
+        if !flags.is_empty() {
+            self.line(&format!("flags = {},", flags.join(" | ")));
+        }
+        for line in extra {
+            self.line(line);
+        }
+        self.depth -= 1;
+
+        self.children(node);
+        match keyword {
+            "orderedlist" => self.line("endlist;"),
+            keyword => self.line(&format!("end{};", keyword)),
+        }
+    }
+
+    fn node(&mut self, node: &IfrNode) {
+        match node.op() {
+            IfrOp::FormSet(op) => {
+                self.line("formset");
+                self.depth += 1;
+                self.line(&format!("guid = {},", vfr_guid(&op.guid)));
+                self.line(&format!("title = {},", self.string(op.form_set_title)));
+                let class_guids: Vec<String> = op.class_guid.iter().map(vfr_guid).collect();
+                match class_guids.is_empty() {
+                    true => self.line(&format!("help = {};", self.string(op.help))),
+                    false => {
+                        self.line(&format!("help = {},", self.string(op.help)));
+                        self.line(&format!("classguid = {};", class_guids.join(" | ")));
+                    }
+                }
+                self.depth -= 1;
+                self.line("");
+                self.children(node);
+                self.line("endformset;");
+            }
+            IfrOp::DefaultStore {
+                default_name,
+                default_id,
+            } => self.line(&format!(
+                "defaultstore DefaultStore_0x{:04X}, prompt = {}, attribute = 0x{:04X};",
+                default_id,
+                self.string(*default_name),
+                default_id
+            )),
+            IfrOp::VarStore(op) => self.line(&format!(
+                "varstore {}, varid = 0x{:04X}, name = {}, guid = {};  // size 0x{:X}",
+                op.name,
+                op.var_store_id,
+                op.name,
+                vfr_guid(&op.guid),
+                op.size
+            )),
+            IfrOp::VarStoreEfi(op) => self.line(&format!(
+                "efivarstore {}, attribute = 0x{:08X}, varid = 0x{:04X}, name = {}, guid = {};  // size 0x{:X}",
+                op.name,
+                op.attributes,
+                op.var_store_id,
+                op.name,
+                vfr_guid(&op.guid),
+                op.size
+            )),
+            IfrOp::VarStoreNameValue(op) => self.line(&format!(
+                "namevaluevarstore NameValue_0x{:04X}, varid = 0x{:04X}, guid = {};",
+                op.var_store_id,
+                op.var_store_id,
+                vfr_guid(&op.guid)
+            )),
+            IfrOp::VarStoreDevice { device_path } => {
+                self.line(&format!("varstoredevice = {};", self.string(*device_path)));
+                self.children(node);
+            }
+            IfrOp::Form(op) => {
+                self.line(&format!("form formid = 0x{:04X},", op.form_id));
+                self.depth += 1;
+                self.line(&format!("title = {};", self.string(op.form_title)));
+                self.depth -= 1;
+                self.children(node);
+                self.line("endform;");
+                self.line("");
+            }
+            IfrOp::FormMap { form_id, methods } => {
+                self.line(&format!("formmap formid = 0x{:04X},", form_id));
+                self.depth += 1;
+                for method in methods {
+                    self.line(&format!("maptitle = {};", self.string(method.method_title)));
+                    self.line(&format!("mapguid = {};", vfr_guid(&method.method_identifier)));
+                }
+                self.depth -= 1;
+                self.children(node);
+                self.line("endform;");
+                self.line("");
+            }
+            IfrOp::Subtitle { statement, flags } => {
+                let flags = match flags {
+                    0 => String::new(),
+                    flags => format!(", flags = 0x{:02X}", flags),
+                };
+                self.line(&format!("subtitle text = {}{};", self.string(statement.prompt), flags));
+                self.children(node);
+            }
+            IfrOp::Text {
+                statement,
+                text_two,
+            } => {
+                let mut text = format!(
+                    "text help = {}, text = {}",
+                    self.string(statement.help),
+                    self.string(statement.prompt)
+                );
+                if *text_two != 0 {
+                    text += &format!(", text = {}", self.string(*text_two));
+                }
+                self.line(&(text + ";"));
+                self.children(node);
+            }
+            IfrOp::Image { id } => self.line(&format!("image = IMAGE_TOKEN(0x{:04X});", id)),
+            IfrOp::Animation { id } => self.line(&format!("animation = ANIMATION_TOKEN(0x{:04X});", id)),
+            IfrOp::ResetButton {
+                statement,
+                default_id,
+            } => {
+                self.line(&format!("resetbutton defaultstore = DefaultStore_0x{:04X},", default_id));
+                self.depth += 1;
+                self.line(&format!("prompt = {},", self.string(statement.prompt)));
+                self.line(&format!("help = {},", self.string(statement.help)));
+                self.depth -= 1;
+                self.children(node);
+                self.line("endresetbutton;");
+            }
+            IfrOp::OneOf(op) | IfrOp::Numeric(op) => {
+                let keyword = match node.op() {
+                    IfrOp::OneOf(_) => "oneof",
+                    _ => "numeric",
+                };
+                let mut extra = Vec::new();
+                if keyword == "numeric" || op.data.max_value != 0 {
+                    extra.push(format!("minimum = {},", op.data.min_value));
+                    extra.push(format!("maximum = {},", op.data.max_value));
+                    extra.push(format!("step = {},", op.data.step));
+                }
+                self.question(keyword, &op.question, Some(numeric_flags(op.flags)), &extra, node);
+            }
+            IfrOp::Checkbox(op) => {
+                let flags = flag_list(
+                    op.flags,
+                    &[
+                        (EFI_IFR_CHECKBOX_DEFAULT, "CHECKBOX_DEFAULT"),
+                        (EFI_IFR_CHECKBOX_DEFAULT_MFG, "CHECKBOX_DEFAULT_MFG"),
+                    ],
+                );
+                self.question("checkbox", &op.question, Some(flags), &[], node);
+            }
+            IfrOp::Date(op) | IfrOp::Time(op) => {
+                let keyword = match node.op() {
+                    IfrOp::Date(_) => "date",
+                    _ => "time",
+                };
+                let flags = match op.flags {
+                    0 => None,
+                    flags => Some(format!("0x{:02X}", flags)),
+                };
+                self.question(keyword, &op.question, flags, &[], node);
+            }
+            IfrOp::String(op) => {
+                let extra = alloc::vec![
+                    format!("minsize = {},", op.min_size),
+                    format!("maxsize = {},", op.max_size),
+                ];
+                let flags = match op.flags {
+                    0 => None,
+                    flags => Some(flag_list(flags, &[(0x01, "MULTI_LINE")])),
+                };
+                self.question("string", &op.question, flags, &extra, node);
+            }
+            IfrOp::Password(op) => {
+                let extra = alloc::vec![
+                    format!("minsize = {},", op.min_size),
+                    format!("maxsize = {},", op.max_size),
+                ];
+                self.question("password", &op.question, None, &extra, node);
+            }
+            IfrOp::OrderedList(op) => {
+                let extra = alloc::vec![format!("maxcontainers = {},", op.max_containers)];
+                let flags = match op.flags {
+                    0 => None,
+                    flags => Some(flag_list(flags, &[(0x01, "UNIQUE"), (0x02, "NOEMPTY")])),
+                };
+                self.question("orderedlist", &op.question, flags, &extra, node);
+            }
+            IfrOp::Action(op) => {
+                let extra = match op.question_config {
+                    Some(config) => alloc::vec![format!("config = {},", self.string(config))],
+                    None => Vec::new(),
+                };
+                self.question("action", &op.question, None, &extra, node);
+            }
+            IfrOp::Ref(op) => {
+                let mut target = String::new();
+                if let Some(guid) = op.form_set_id {
+                    target += &format!("formsetguid = {}, ", vfr_guid(&guid));
+                }
+                if let Some(form_id) = op.form_id {
+                    target += &format!("0x{:04X}", form_id);
+                }
+                if let Some(question_id) = op.question_id {
+                    target += &format!(", question = {}", vfr_question_name(question_id));
+                }
+                let mut head = format!("goto {},", target);
+                if let Some(varid) = self.varid(&op.question) {
+                    head += &format!(" varid = {},", varid);
+                }
+                self.line(&head);
+                self.depth += 1;
+                self.line(&format!("prompt = {},", self.string(op.question.header.prompt)));
+                self.line(&format!("help = {},", self.string(op.question.header.help)));
+                if op.question.flags != 0 {
+                    self.line(&format!("flags = {},", flag_list(op.question.flags, QUESTION_FLAGS)));
+                }
+                self.line(&format!("questionid = 0x{:04X};", op.question.question_id));
+                self.depth -= 1;
+                self.children(node);
+            }
+            IfrOp::OneOfOption(op) => {
+                let flags = flag_list(
+                    op.flags & (EFI_IFR_OPTION_DEFAULT | EFI_IFR_OPTION_DEFAULT_MFG),
+                    &[(EFI_IFR_OPTION_DEFAULT, "DEFAULT"), (EFI_IFR_OPTION_DEFAULT_MFG, "MANUFACTURING")],
+                );
+                let flags = if flags.is_empty() { String::from("0") } else { flags };
+                self.line(&format!(
+                    "option text = {}, value = {}, flags = {};",
+                    self.string(op.option),
+                    self.value(&op.value),
+                    flags
+                ));
+                self.children(node);
+            }
+            IfrOp::Default(op) => {
+                let value = match op.value {
+                    IfrTypeValue::Other => {
+                        let mut expression = Vec::new();
+                        flatten_expression(&node.children, &mut expression);
+                        self.expression(&expression)
+                    }
+                    ref value => self.value(value),
+                };
+                self.line(&format!(
+                    "default = {}, defaultstore = DefaultStore_0x{:04X},",
+                    value, op.default_id
+                ));
+            }
+            IfrOp::SuppressIf => self.condition("suppressif", node, None),
+            IfrOp::GrayOutIf => self.condition("grayoutif", node, None),
+            IfrOp::DisableIf => self.condition("disableif", node, None),
+            IfrOp::NoSubmitIf { error } => {
+                let prompt = format!("prompt = {},", self.string(*error));
+                self.condition("nosubmitif", node, Some(prompt));
+            }
+            IfrOp::InconsistentIf { error } => {
+                let prompt = format!("prompt = {},", self.string(*error));
+                self.condition("inconsistentif", node, Some(prompt));
+            }
+            IfrOp::WarningIf { warning, time_out } => {
+                let prompt = format!("prompt = {}, timeout = {},", self.string(*warning), time_out);
+                self.condition("warningif", node, Some(prompt));
+            }
+            IfrOp::Locked => self.line("locked;"),
+            IfrOp::Refresh { refresh_interval } => {
+                self.line(&format!("refresh interval = {}", refresh_interval))
+            }
+            IfrOp::RefreshId {
+                refresh_event_group_id,
+            } => self.line(&format!("refreshguid = {},", vfr_guid(refresh_event_group_id))),
+            IfrOp::ModalTag => self.line("modal;"),
+            IfrOp::Value | IfrOp::Read | IfrOp::Write => {
+                let keyword = match node.op() {
+                    IfrOp::Value => "value =",
+                    IfrOp::Read => "read",
+                    _ => "write",
+                };
+                let mut expression = Vec::new();
+                flatten_expression(&node.children, &mut expression);
+                let expression = self.expression(&expression);
+                self.line(&format!("{} {};", keyword, expression));
+            }
+            IfrOp::Rule { rule_id } => {
+                let mut expression = Vec::new();
+                flatten_expression(&node.children, &mut expression);
+                let expression = self.expression(&expression);
+                self.line(&format!("rule Rule_0x{:02X}, {}", rule_id, expression));
+                self.line("endrule;");
+            }
+            IfrOp::Guid { guid, data } => {
+                let extension = match self.registry {
+                    Some(registry) => node.op().guid_extension_with(registry),
+                    None => node.op().guid_extension(),
+                };
+                match extension {
+                    Some(IfrGuidExtension::Label { number }) => self.line(&format!("label 0x{:04X};", number)),
+                    Some(IfrGuidExtension::Banner {
+                        title,
+                        line_number,
+                        alignment,
+                    }) => {
+                        let align = match alignment {
+                            EFI_IFR_BANNER_ALIGN_CENTER => "center",
+                            EFI_IFR_BANNER_ALIGN_RIGHT => "right",
+                            _ => "left",
+                        };
+                        self.line(&format!(
+                            "banner title = {}, line {}, align {};",
+                            self.string(title),
+                            line_number,
+                            align
+                        ));
+                    }
+                    Some(IfrGuidExtension::Timeout { timeout }) => self.line(&format!("timeout = {};", timeout)),
+                    Some(IfrGuidExtension::Class { class }) => self.line(&format!("class = 0x{:04X};", class)),
+                    Some(IfrGuidExtension::SubClass { sub_class }) => {
+                        self.line(&format!("subclass = 0x{:04X};", sub_class))
+                    }
+                    extension => {
+                        if let Some(IfrGuidExtension::Vendor(view)) = extension {
+                            self.line(&format!("// {}", view));
+                        }
+                        let bytes: Vec<String> = data.iter().map(|b| format!("0x{:02x}", b)).collect();
+                        self.line(&format!("guidop guid = {}, data = {{{}}};", vfr_guid(guid), bytes.join(", ")));
+                    }
+                }
+                self.children(node);
+            }
+            op => {
+                self.line(&format!("// {:?} at 0x{:04X}", op.op_code(), node.offset()));
+                self.children(node);
+            }
+        }
+    }
+}
+
+/// Number of values an expression opcode leaves on the stack minus the values it pops
+fn stack_effect(op: &IfrOp) -> isize {
+    match op {
+        IfrOp::Mid
+        | IfrOp::Find { .. }
+        | IfrOp::Token
+        | IfrOp::Span { .. }
+        | IfrOp::Conditional => -2,
+        IfrOp::And
+        | IfrOp::Or
+        | IfrOp::Equal
+        | IfrOp::NotEqual
+        | IfrOp::GreaterThan
+        | IfrOp::GreaterEqual
+        | IfrOp::LessThan
+        | IfrOp::LessEqual
+        | IfrOp::BitwiseAnd
+        | IfrOp::BitwiseOr
+        | IfrOp::ShiftLeft
+        | IfrOp::ShiftRight
+        | IfrOp::Add
+        | IfrOp::Subtract
+        | IfrOp::Multiply
+        | IfrOp::Divide
+        | IfrOp::Modulo
+        | IfrOp::Catenate
+        | IfrOp::Match
+        | IfrOp::Match2 { .. } => -1,
+        IfrOp::Not
+        | IfrOp::BitwiseNot
+        | IfrOp::QuestionRef2
+        | IfrOp::QuestionRef3 { .. }
+        | IfrOp::StringRef2
+        | IfrOp::Set { .. }
+        | IfrOp::Write
+        | IfrOp::ToBoolean
+        | IfrOp::ToUint
+        | IfrOp::ToString { .. }
+        | IfrOp::ToLower
+        | IfrOp::ToUpper
+        | IfrOp::Length
+        | IfrOp::End
+        | IfrOp::Value => 0,
+        _ => 1,
+    }
+}
@@ -0,0 +1,117 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+use super::*;
+use crate::package_header::StringTable;
+
+/// State of a statement as shown by the form browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatementState {
+    /// Displayed and editable
+    Shown,
+    /// Displayed, but cannot be changed (EFI_IFR_LOCKED)
+    Locked,
+    /// Displayed grayed out (EFI_IFR_GRAY_OUT_IF)
+    Grayed,
+    /// Not displayed (EFI_IFR_SUPPRESS_IF or EFI_IFR_DISABLE_IF)
+    Hidden,
+}
+
+impl Display for StatementState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StatementState::Shown => "shown",
+            StatementState::Locked => "locked",
+            StatementState::Grayed => "grayed",
+            StatementState::Hidden => "hidden",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatementVisibility<'a> {
+    pub statement: &'a Statement,
+    pub state: StatementState,
+}
+
+/// State of every statement of `form` for the given question values.
+pub fn visible_questions<'a>(form: &'a Form, values: &QuestionValues) -> Vec<StatementVisibility<'a>> {
+    visible_questions_with_strings(form, values, None)
+}
+
+/// Same as [`visible_questions`], string opcodes in conditions are resolved through `strings`.
+pub fn visible_questions_with_strings<'a>(
+    form: &'a Form,
+    values: &QuestionValues,
+    strings: Option<&StringTable>,
+) -> Vec<StatementVisibility<'a>> {
+    let form_state = conditions_state(&form.conditions, form, values, strings, None);
+
+    form.statements
+        .iter()
+        .map(|statement| {
+            let this = statement.question().map(|question| question.id);
+            let mut state = form_state.max(conditions_state(
+                statement.conditions(),
+                form,
+                values,
+                strings,
+                this,
+            ));
+            if statement.question().is_some_and(|question| question.locked) {
+                state = state.max(StatementState::Locked);
+            }
+            StatementVisibility { statement, state }
+        })
+        .collect()
+}
+
+/// Options of a question that are not suppressed for the given question values.
+pub fn visible_options<'a>(
+    form: &Form,
+    question: &'a Question,
+    values: &QuestionValues,
+) -> Vec<&'a QuestionOption> {
+    question
+        .options
+        .iter()
+        .filter(|option| {
+            conditions_state(&option.conditions, form, values, None, Some(question.id))
+                != StatementState::Hidden
+        })
+        .collect()
+}
+
+/// Combined state of nested condition scopes; malformed expressions count as FALSE
+fn conditions_state(
+    conditions: &[Condition],
+    form: &Form,
+    values: &QuestionValues,
+    strings: Option<&StringTable>,
+    this: Option<u16>,
+) -> StatementState {
+    let mut state = StatementState::Shown;
+
+    for condition in conditions {
+        let mut evaluator = Evaluator::new(values).with_rules(&form.rules);
+        if let Some(strings) = strings {
+            evaluator = evaluator.with_strings(strings);
+        }
+        if let Some(this) = this {
+            evaluator = evaluator.with_this(this);
+        }
+
+        if evaluator.eval_bool(&condition.expression).unwrap_or(false) {
+            let condition_state = match condition.kind {
+                ConditionKind::SuppressIf | ConditionKind::DisableIf => StatementState::Hidden,
+                ConditionKind::GrayOutIf => StatementState::Grayed,
+            };
+            state = state.max(condition_state);
+        }
+    }
+    state
+}
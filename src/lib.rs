@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 use uefi::{
     boot::MemoryType,
     prelude::*,
@@ -12,9 +12,12 @@ use alloc::{slice, string::String, vec::Vec};
 
 use core::{ffi::c_void, mem, ptr};
 
+use crate::ifr::{FormSet, IfrTree, UpdateFormError};
 use crate::package_header::*;
 
 pub mod base;
+pub mod config;
+pub mod ifr;
 pub mod package_header;
 
 /// EFI_HII_DATABASE_NOTIFY_TYPE.
@@ -207,12 +210,39 @@ impl HiiDatabaseProtocol {
         self.get_hii_handles(package_list_guid).map(|handles| {
             handles
                 .into_iter()
-                .filter(|head| head.header.get_type() == Ok(T::PACKAGE_TYPE))
+                .filter(|head| T::matches(head.header.get_type()))
                 .map(|t| T::from_undef(&t))
                 .collect()
         })
     }
 
+    /// Get the model of every formset in a package list, strings are
+    /// resolved in the current platform language
+    pub fn get_form_sets(&self, package_list_guid: Guid) -> Option<Vec<FormSet>> {
+        let strings = self.get_string_table(package_list_guid)?;
+
+        let form_sets = self
+            .get_package::<HiiFormPackageHdr>(package_list_guid)?
+            .iter()
+            .filter_map(|fph| IfrTree::from_package(fph).ok())
+            .flat_map(|tree| FormSet::from_tree(&tree, &strings))
+            .collect();
+        Some(form_sets)
+    }
+
+    /// Strings of a package list in the platform language
+    pub fn get_string_table(&self, package_list_guid: Guid) -> Option<StringTable> {
+        let string_packages = self.get_package::<HiiStringPackageHdr>(package_list_guid)?;
+        Some(StringTable::from_packages(&string_packages, &platform_language()?))
+    }
+
+    /// UEFI keywords of a package list, the strings of the "x-UEFI-ns" language
+    pub fn get_keyword_table(&self, package_list_guid: Guid) -> Option<StringTable> {
+        let string_packages = self.get_package::<HiiStringPackageHdr>(package_list_guid)?;
+        let keywords = StringTable::from_packages(&string_packages, UEFI_KEYWORD_LANGUAGE);
+        (keywords.language() == UEFI_KEYWORD_LANGUAGE).then_some(keywords)
+    }
+
     /// Registers a list of packages in the HII Database and returns the HII Handle
     pub fn add_packages(
         &self,
@@ -220,29 +250,7 @@ impl HiiDatabaseProtocol {
         device_hadle: Option<Handle>,
         packegs: Vec<HiiPackage>,
     ) -> Result<Handle> {
-        let size_packegs: usize = packegs.iter().map(|pack| pack.size()).sum();
-
-        // Fill in the GUIDE and Length of the Package List Header
-        let list_header: EfiHiiPackageListHeader = EfiHiiPackageListHeader {
-            package_list_guid,
-            horizontal_resolution: (size_packegs + mem::size_of::<EfiHiiPackageListHeader>())
-                as u32,
-        };
-
-        let bytes_list_header = unsafe {
-            slice::from_raw_parts(
-                (&list_header as *const EfiHiiPackageListHeader) as *const u8,
-                mem::size_of::<EfiHiiPackageListHeader>(),
-            )
-        };
-
-        // Initialize all byte data into one packet
-        let mut list_pack = Vec::with_capacity(list_header.horizontal_resolution as usize);
-        list_pack.extend_from_slice(bytes_list_header);
-        // Copy the data from each package
-        for packeg in packegs {
-            list_pack.extend_from_slice(packeg.as_slice());
-        }
+        let list_pack = package_list_data(package_list_guid, &packegs);
 
         // Register the package list with the HII Database
         unsafe {
@@ -262,6 +270,66 @@ impl HiiDatabaseProtocol {
         }
     }
 
+    /// Replaces the opcodes following label `start_label` of a form with `ops`,
+    /// like HiiUpdateForm of EDK2, and updates the package list of `hii_handle`.
+    ///
+    /// See [`ifr::update_form`] for the replaced range; `ops` can be built with
+    /// [`ifr::FormBuilder::build`].
+    pub fn update_form(
+        &self,
+        hii_handle: Handle,
+        form_set_guid: Guid,
+        form_id: u16,
+        start_label: u16,
+        end_label: Option<u16>,
+        ops: &[u8],
+    ) -> Result {
+        if IfrTree::parse(ops).is_err() {
+            return Status::INVALID_PARAMETER.to_result();
+        }
+        let package_list = self.get_hii_package(hii_handle).ok_or(Status::NOT_FOUND)?;
+
+        let mut updated = false;
+        let mut packages = Vec::new();
+        for package in package_list {
+            if !updated && package.header.get_type() == HiiFormPackageHdr::PACKAGE_TYPE {
+                let form_package = HiiFormPackageHdr::from_undef(&package);
+                match ifr::update_form(
+                    form_package.as_data(),
+                    form_set_guid,
+                    form_id,
+                    start_label,
+                    end_label,
+                    ops,
+                ) {
+                    Ok(data) => {
+                        packages.push(HiiFormPackageHdr::create(&data));
+                        updated = true;
+                        continue;
+                    }
+                    // The form set may be in another form package
+                    Err(UpdateFormError::FormSetNotFound { .. } | UpdateFormError::Decode(_)) => {}
+                    Err(_) => return Status::NOT_FOUND.to_result(),
+                }
+            }
+            let data = unsafe { &*package.get_slice() };
+            packages.push(HiiPackage::new(data.to_vec()));
+        }
+        if !updated {
+            return Status::NOT_FOUND.to_result();
+        }
+
+        let list_pack = package_list_data(package_list.header().package_list_guid, &packages);
+        unsafe {
+            (self.update_package_list)(
+                self,
+                hii_handle,
+                list_pack.as_ptr() as *const EfiHiiPackageListHeader,
+            )
+        }
+        .to_result()
+    }
+
     /// Removes a package list from the HII database.
     pub fn remove_packages(&self, hii_handle: Handle) -> Status {
         unsafe { (self.remove_package_list)(self, hii_handle) }
@@ -281,9 +349,56 @@ impl HiiDatabaseProtocol {
             // Get all packets with strings
             .get_hii_package(hii_handle)?
             .into_iter()
-            .filter(|head| head.header.get_type() == Ok(HiiStringPackageHdr::PACKAGE_TYPE))
+            .filter(|head| HiiStringPackageHdr::matches(head.header.get_type()))
             .map(|t| HiiStringPackageHdr::from_undef(&t))
             .filter_map(|str| str.get_string(message, lang))
             .next() // Retrieve the string from the string package
     }
 }
+
+/// Package list header followed by the data of `packages`
+fn package_list_data(package_list_guid: Guid, packages: &[HiiPackage]) -> Vec<u8> {
+    let size_packages: usize = packages.iter().map(|pack| pack.size()).sum();
+
+    // Fill in the GUIDE and Length of the Package List Header
+    let list_header: EfiHiiPackageListHeader = EfiHiiPackageListHeader {
+        package_list_guid,
+        horizontal_resolution: (size_packages + mem::size_of::<EfiHiiPackageListHeader>()) as u32,
+    };
+
+    let bytes_list_header = unsafe {
+        slice::from_raw_parts(
+            (&list_header as *const EfiHiiPackageListHeader) as *const u8,
+            mem::size_of::<EfiHiiPackageListHeader>(),
+        )
+    };
+
+    // Initialize all byte data into one packet
+    let mut list_pack = Vec::with_capacity(list_header.horizontal_resolution as usize);
+    list_pack.extend_from_slice(bytes_list_header);
+    // Copy the data from each package
+    for package in packages {
+        list_pack.extend_from_slice(package.as_slice());
+    }
+    list_pack
+}
+
+/// Language of the strings holding the UEFI keywords of questions
+const UEFI_KEYWORD_LANGUAGE: &str = "x-UEFI-ns";
+
+/// Current platform language ("PlatformLang" variable) without the terminating NUL
+fn platform_language() -> Option<String> {
+    let (platform_lang, _) = uefi::runtime::get_variable_boxed(
+        cstr16!("PlatformLang"),
+        &VariableVendor::GLOBAL_VARIABLE,
+    )
+    .ok()?;
+
+    Some(
+        platform_lang
+            .iter()
+            .take_while(|b| **b != 0)
+            .map(|b| *b as char)
+            .collect(),
+    )
+}
@@ -11,6 +11,7 @@ mod package_list;
 pub use package_list::*;
 
 extern crate alloc;
+use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -26,7 +27,7 @@ impl EfiHiiPackageHeader {
         let bytes = length.to_le_bytes();
         Self {
             length: [bytes[0], bytes[1], bytes[2]],
-            r#type: pack_type as u8,
+            r#type: u8::from(pack_type),
         }
     }
 
@@ -34,8 +35,8 @@ impl EfiHiiPackageHeader {
         (self.length[2] as u32) << 16 | (self.length[1] as u32) << 8 | (self.length[0] as u32)
     }
 
-    pub fn get_type(&self) -> Result<HiiPackageType, ()> {
-        HiiPackageType::try_from(self.r#type)
+    pub fn get_type(&self) -> HiiPackageType {
+        HiiPackageType::from_raw(self.r#type)
     }
 
     pub fn to_bytes(&self) -> [u8; 4] {
@@ -55,55 +56,135 @@ impl Display for EfiHiiPackageHeader {
 }
 
 // Value of HII package type
-#[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum HiiPackageType {
-    TypeAll = 0x00,
-    TypeGuid = 0x01,
-    Forms = 0x02,
-    Strings = 0x04,
-    Fonts = 0x05,
-    Images = 0x06,
-    SimpleFonts = 0x07,
-    DevicePath = 0x08,
-    KeyboardLayout = 0x09,
-    Animations = 0x0A,
-    End = 0xDF,
-    TypeSystemBegin = 0x0E,
-    TypeSystemEnd = 0xFF,
+    TypeAll,
+    TypeGuid,
+    Forms,
+    Strings,
+    Fonts,
+    Images,
+    SimpleFonts,
+    DevicePath,
+    KeyboardLayout,
+    Animations,
+    End,
+    // EFI_HII_PACKAGE_TYPE_SYSTEM_BEGIN..=EFI_HII_PACKAGE_TYPE_SYSTEM_END
+    System(u8),
+    // Any value not defined by the specification
+    Unknown(u8),
 }
 
 impl HiiPackageType {
-    fn try_from(orig: u8) -> Result<Self, ()> {
+    pub const SYSTEM_BEGIN: u8 = 0xE0;
+    pub const SYSTEM_END: u8 = 0xFF;
+
+    /// Name of the package type as used in the specification (without the EFI_HII_PACKAGE_ prefix)
+    pub fn name(&self) -> &'static str {
+        match self {
+            HiiPackageType::TypeAll => "ALL",
+            HiiPackageType::TypeGuid => "GUID",
+            HiiPackageType::Forms => "FORMS",
+            HiiPackageType::Strings => "STRINGS",
+            HiiPackageType::Fonts => "FONTS",
+            HiiPackageType::Images => "IMAGES",
+            HiiPackageType::SimpleFonts => "SIMPLE_FONTS",
+            HiiPackageType::DevicePath => "DEVICE_PATH",
+            HiiPackageType::KeyboardLayout => "KEYBOARD_LAYOUT",
+            HiiPackageType::Animations => "ANIMATIONS",
+            HiiPackageType::End => "END",
+            HiiPackageType::System(Self::SYSTEM_BEGIN) => "SYSTEM_BEGIN",
+            HiiPackageType::System(Self::SYSTEM_END) => "SYSTEM_END",
+            HiiPackageType::System(_) => "SYSTEM",
+            HiiPackageType::Unknown(_) => "UNKNOWN",
+        }
+    }
+}
+
+impl HiiPackageType {
+    /// Package type of a raw byte, values outside the specification map to `Unknown`
+    pub fn from_raw(orig: u8) -> Self {
         match orig {
-            0x00 => Ok(HiiPackageType::TypeAll),
-            0x01 => Ok(HiiPackageType::TypeGuid),
-            0x02 => Ok(HiiPackageType::Forms),
-            0x04 => Ok(HiiPackageType::Strings),
-            0x05 => Ok(HiiPackageType::Fonts),
-            0x06 => Ok(HiiPackageType::Images),
-            0x07 => Ok(HiiPackageType::SimpleFonts),
-            0x08 => Ok(HiiPackageType::DevicePath),
-            0x09 => Ok(HiiPackageType::KeyboardLayout),
-            0x0A => Ok(HiiPackageType::Animations),
-            0xDF => Ok(HiiPackageType::End),
-            0x0E => Ok(HiiPackageType::TypeSystemBegin),
-            0xFF => Ok(HiiPackageType::TypeSystemEnd),
-            _ => Err(()),
+            0x00 => HiiPackageType::TypeAll,
+            0x01 => HiiPackageType::TypeGuid,
+            0x02 => HiiPackageType::Forms,
+            0x04 => HiiPackageType::Strings,
+            0x05 => HiiPackageType::Fonts,
+            0x06 => HiiPackageType::Images,
+            0x07 => HiiPackageType::SimpleFonts,
+            0x08 => HiiPackageType::DevicePath,
+            0x09 => HiiPackageType::KeyboardLayout,
+            0x0A => HiiPackageType::Animations,
+            0xDF => HiiPackageType::End,
+            Self::SYSTEM_BEGIN..=Self::SYSTEM_END => HiiPackageType::System(orig),
+            _ => HiiPackageType::Unknown(orig),
+        }
+    }
+}
+
+impl TryFrom<u8> for HiiPackageType {
+    type Error = u8;
+
+    /// Fails for values outside the defined types and the system range
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match HiiPackageType::from_raw(value) {
+            HiiPackageType::Unknown(value) => Err(value),
+            pack_type => Ok(pack_type),
         }
     }
 }
 
+impl From<HiiPackageType> for u8 {
+    fn from(orig: HiiPackageType) -> Self {
+        match orig {
+            HiiPackageType::TypeAll => 0x00,
+            HiiPackageType::TypeGuid => 0x01,
+            HiiPackageType::Forms => 0x02,
+            HiiPackageType::Strings => 0x04,
+            HiiPackageType::Fonts => 0x05,
+            HiiPackageType::Images => 0x06,
+            HiiPackageType::SimpleFonts => 0x07,
+            HiiPackageType::DevicePath => 0x08,
+            HiiPackageType::KeyboardLayout => 0x09,
+            HiiPackageType::Animations => 0x0A,
+            HiiPackageType::End => 0xDF,
+            HiiPackageType::System(value) | HiiPackageType::Unknown(value) => value,
+        }
+    }
+}
+
+impl Display for HiiPackageType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 /// EFI_HII_PACKAGE_TYPE_x.
 pub enum DefinedStruct {
+    // EFI_HII_PACKAGE_TYPE_GUID = 0x01
+    GuidPackage(HiiGuidPackageHdr),
+    // EFI_HII_PACKAGE_TYPE_GUID = 0x01 decoded by a registered decoder
+    VendorPackage(Box<dyn GuidPackageView>),
     // EFI_HII_PACKAGE_FORM = 0x02
     FormPackage(HiiFormPackageHdr),
     // EFI_HII_PACKAGE_STRINGS = 0x04
     StringPackage(HiiStringPackageHdr),
     // EFI_HII_PACKAGE_FONTS = 0x05
     FontPackage(HiiFontPackageHdr),
+    // EFI_HII_PACKAGE_IMAGES = 0x06
+    ImagePackage(HiiImagePackageHdr),
+    // EFI_HII_PACKAGE_SIMPLE_FONTS = 0x07
+    SimpleFontPackage(HiiSimpleFontPackageHdr),
+    // EFI_HII_PACKAGE_DEVICE_PATH = 0x08
+    DevicePathPackage(HiiDevicePathPackageHdr),
+    // EFI_HII_PACKAGE_KEYBOARD_LAYOUT = 0x09
+    KeyboardLayoutPackage(HiiKeyboardLayoutPackageHdr),
+    // EFI_HII_PACKAGE_ANIMATIONS = 0x0A
+    AnimationPackage(HiiAnimationPackageHdr),
     // EFI_HII_PACKAGE_END = 0xDF
     EndPackage(HiiEndPackageHdr),
+    // EFI_HII_PACKAGE_TYPE_SYSTEM_BEGIN = 0xE0 .. EFI_HII_PACKAGE_TYPE_SYSTEM_END = 0xFF
+    SystemPackage(HiiSystemPackageHdr),
     // UNKNOWN = ?
     Undefined(UndefineHiiPackageHdr),
 }
@@ -111,6 +192,11 @@ pub enum DefinedStruct {
 pub trait PackageHeader {
     const PACKAGE_TYPE: HiiPackageType;
 
+    /// Whether a package of type `ty` is viewed through this header
+    fn matches(ty: HiiPackageType) -> bool {
+        ty == Self::PACKAGE_TYPE
+    }
+
     fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self;
 
     fn header(&self) -> EfiHiiPackageHeader;
@@ -119,23 +205,163 @@ pub trait PackageHeader {
 impl From<&UndefineHiiPackageHdr> for DefinedStruct {
     fn from(item: &UndefineHiiPackageHdr) -> Self {
         match item.header.get_type() {
-            Ok(HiiFormPackageHdr::PACKAGE_TYPE) => {
+            HiiGuidPackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::GuidPackage(HiiGuidPackageHdr::from_undef(item))
+            }
+            HiiFormPackageHdr::PACKAGE_TYPE => {
                 DefinedStruct::FormPackage(HiiFormPackageHdr::from_undef(item))
             }
-            Ok(HiiStringPackageHdr::PACKAGE_TYPE) => {
+            HiiStringPackageHdr::PACKAGE_TYPE => {
                 DefinedStruct::StringPackage(HiiStringPackageHdr::from_undef(item))
             }
-            Ok(HiiFontPackageHdr::PACKAGE_TYPE) => {
+            HiiFontPackageHdr::PACKAGE_TYPE => {
                 DefinedStruct::FontPackage(HiiFontPackageHdr::from_undef(item))
             }
-            Ok(HiiEndPackageHdr::PACKAGE_TYPE) => {
+            HiiImagePackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::ImagePackage(HiiImagePackageHdr::from_undef(item))
+            }
+            HiiSimpleFontPackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::SimpleFontPackage(HiiSimpleFontPackageHdr::from_undef(item))
+            }
+            HiiDevicePathPackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::DevicePathPackage(HiiDevicePathPackageHdr::from_undef(item))
+            }
+            HiiKeyboardLayoutPackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::KeyboardLayoutPackage(HiiKeyboardLayoutPackageHdr::from_undef(item))
+            }
+            HiiAnimationPackageHdr::PACKAGE_TYPE => {
+                DefinedStruct::AnimationPackage(HiiAnimationPackageHdr::from_undef(item))
+            }
+            HiiEndPackageHdr::PACKAGE_TYPE => {
                 DefinedStruct::EndPackage(HiiEndPackageHdr::from_undef(item))
             }
+            HiiPackageType::System(_) => {
+                DefinedStruct::SystemPackage(HiiSystemPackageHdr::from_undef(item))
+            }
             _ => DefinedStruct::Undefined(*item),
         }
     }
 }
 
+impl DefinedStruct {
+    /// Same as [`DefinedStruct::from`], but GUID packages with a decoder in
+    /// `registry` are returned as [`DefinedStruct::VendorPackage`].
+    pub fn with_registry(item: &UndefineHiiPackageHdr, registry: &GuidPackageRegistry) -> Self {
+        match Self::from(item) {
+            DefinedStruct::GuidPackage(guid_pack) => match guid_pack.decode(registry) {
+                Some(view) => DefinedStruct::VendorPackage(view),
+                None => DefinedStruct::GuidPackage(guid_pack),
+            },
+            other => other,
+        }
+    }
+}
+
+impl Display for DefinedStruct {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinedStruct::GuidPackage(pack) => match pack.guid() {
+                Some(guid) => write!(
+                    f,
+                    "GUID size=0x{:02X} guid={}",
+                    pack.header().length(),
+                    guid
+                ),
+                None => write!(f, "GUID size=0x{:02X} truncated", pack.header().length()),
+            },
+            DefinedStruct::VendorPackage(view) => write!(f, "GUID guid={} {}", view.guid(), view),
+            DefinedStruct::FormPackage(pack) => write!(
+                f,
+                "FORMS size=0x{:02X} opcodes={}",
+                pack.header().length(),
+                unsafe { EfiOpHeaderIter::from_slice(pack.as_data()) }.count()
+            ),
+            DefinedStruct::StringPackage(pack) => write!(
+                f,
+                "STRINGS size=0x{:02X} language={} strings={}",
+                pack.header().length(),
+                pack.str_language(),
+                pack.count_strings()
+            ),
+            DefinedStruct::FontPackage(pack) => {
+                write!(f, "FONTS size=0x{:02X}", pack.header().length())
+            }
+            DefinedStruct::ImagePackage(pack) => {
+                match (pack.image_info_offset(), pack.palette_info_offset()) {
+                    (Some(image_info), Some(palette_info)) => write!(
+                        f,
+                        "IMAGES size=0x{:02X} image_info=0x{:X} palette_info=0x{:X}",
+                        pack.header().length(),
+                        image_info,
+                        palette_info
+                    ),
+                    _ => write!(f, "IMAGES size=0x{:02X} truncated", pack.header().length()),
+                }
+            }
+            DefinedStruct::SimpleFontPackage(pack) => {
+                match (pack.number_of_narrow_glyphs(), pack.number_of_wide_glyphs()) {
+                    (Some(narrow), Some(wide)) => write!(
+                        f,
+                        "SIMPLE_FONTS size=0x{:02X} narrow={} wide={}",
+                        pack.header().length(),
+                        narrow,
+                        wide
+                    ),
+                    _ => write!(
+                        f,
+                        "SIMPLE_FONTS size=0x{:02X} truncated",
+                        pack.header().length()
+                    ),
+                }
+            }
+            DefinedStruct::DevicePathPackage(pack) => write!(
+                f,
+                "DEVICE_PATH size=0x{:02X} nodes={}",
+                pack.header().length(),
+                pack.device_path().map_or(0, |dp| dp.node_iter().count())
+            ),
+            DefinedStruct::KeyboardLayoutPackage(pack) => match pack.layout_count() {
+                Some(layouts) => write!(
+                    f,
+                    "KEYBOARD_LAYOUT size=0x{:02X} layouts={}",
+                    pack.header().length(),
+                    layouts
+                ),
+                None => write!(
+                    f,
+                    "KEYBOARD_LAYOUT size=0x{:02X} truncated",
+                    pack.header().length()
+                ),
+            },
+            DefinedStruct::AnimationPackage(pack) => match pack.animation_info_offset() {
+                Some(animation_info) => write!(
+                    f,
+                    "ANIMATIONS size=0x{:02X} animation_info=0x{:X}",
+                    pack.header().length(),
+                    animation_info
+                ),
+                None => write!(f, "ANIMATIONS size=0x{:02X} truncated", pack.header().length()),
+            },
+            DefinedStruct::EndPackage(pack) => {
+                write!(f, "END size=0x{:02X}", pack.header().length())
+            }
+            DefinedStruct::SystemPackage(pack) => write!(
+                f,
+                "SYSTEM(0x{:02X}) size=0x{:02X}",
+                pack.raw_type(),
+                pack.header().length()
+            ),
+            DefinedStruct::Undefined(pack) => write!(
+                f,
+                "{} size=0x{:02X} type=0x{:02X}",
+                pack.package_type(),
+                pack.len(),
+                pack.header.r#type
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 /// Iterator for events in  [`UndefineHiiPackageIter`].
 pub struct UndefineHiiPackageIter {
@@ -162,7 +388,7 @@ impl UndefineHiiPackageIter {
         if string_id != 0 {
             for sph in self
                 .into_iter()
-                .filter(|head| head.header.get_type() == Ok(HiiStringPackageHdr::PACKAGE_TYPE))
+                .filter(|head| HiiStringPackageHdr::matches(head.header.get_type()))
                 .map(|t| HiiStringPackageHdr::from_undef(&t))
             {
                 // Возвращаем первую найденную строку
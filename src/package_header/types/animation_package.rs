@@ -0,0 +1,25 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+pub struct HiiAnimationPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiAnimationPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::Animations;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiAnimationPackageHdr {
+    /// `None` if the package is too short to hold the field
+    pub fn animation_info_offset(&self) -> Option<u32> {
+        self.parts.read(4)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+use core::ptr::{self};
+use uefi::CString16;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+
+pub struct HiiDevicePathPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiDevicePathPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::DevicePath;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiDevicePathPackageHdr {
+    /// Raw bytes of the device path that follow the package header
+    pub fn as_data(&self) -> &[u8] {
+        unsafe {
+            let data = self.parts.location.byte_offset(4);
+            let size_data = self.parts.len().saturating_sub(4);
+            &*ptr::slice_from_raw_parts(data, size_data as usize)
+        }
+    }
+
+    /// Device path of the controller the package list belongs to
+    pub fn device_path(&self) -> Option<&DevicePath> {
+        <&DevicePath>::try_from(self.as_data()).ok()
+    }
+
+    /// Text representation of the device path (requires DevicePathToText protocol)
+    pub fn to_text(&self) -> Option<CString16> {
+        self.device_path()?
+            .to_string(DisplayOnly(false), AllowShortcuts(false))
+            .ok()
+    }
+}
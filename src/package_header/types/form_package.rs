@@ -1,6 +1,7 @@
 use crate::HiiPackageType;
 use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
 use crate::HiiPackage;
+use crate::ifr::IfrOpIter;
 
 extern crate alloc;
 use alloc::vec::Vec;
@@ -37,6 +38,11 @@ impl HiiFormPackageHdr {
         }
     }
 
+    /// Decode the opcodes of the package
+    pub fn ops(&self) -> IfrOpIter<'_> {
+        IfrOpIter::new(self.as_data())
+    }
+
     pub fn count_op_codes(self) -> usize {
         unsafe { EfiOpHeaderIter::from_slice(self.as_data()) }.count()
     }
@@ -130,9 +136,14 @@ impl Iterator for EfiOpHeaderIter {
 
     fn next(&mut self) -> Option<Self::Item> {
         unsafe {
+            if self.size <= self.count + 1 {
+                return None;
+            }
             // Читаем заголовок
             let header = EfiOpHeader::new(self.location);
-            if self.size == self.count {
+            // A zero length would never advance
+            if header.len() == 0 || self.count + header.len() > self.size {
+                self.count = self.size;
                 return None;
             }
 
@@ -157,7 +168,7 @@ pub mod ifr_parse {
     use core::mem::transmute;
 
     #[repr(u8)]
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
     pub enum EfiIfrOpCode {
         FormOp = 0x01,
         SubtitleOp = 0x02,
@@ -261,7 +272,7 @@ pub mod ifr_parse {
         Match2Op = 0x64,
     }
 
-    fn is_expression_op_code(operand: u8) -> bool {
+    pub fn is_expression_op_code(operand: u8) -> bool {
         if (operand >= EfiIfrOpCode::EqIdValOp as u8) && (operand <= EfiIfrOpCode::NotOp as u8)
             || (operand >= EfiIfrOpCode::MatchOp as u8) && (operand <= EfiIfrOpCode::SetOp as u8)
             || (operand >= EfiIfrOpCode::EqualOp as u8) && (operand <= EfiIfrOpCode::SpanOp as u8)
@@ -278,15 +289,15 @@ pub mod ifr_parse {
         false
     }
 
-    fn is_know_op_code(operand: u8) -> bool {
-        if operand > EfiIfrOpCode::Match2Op as u8 {
-            true
-        } else {
-            false
-        }
+    /// Opcode is defined by the specification
+    pub fn is_known_op_code(operand: u8) -> bool {
+        EfiIfrOpCode::try_from(operand).is_ok()
     }
 
-    /// Calculate number of Statemens(Questions) and Expression OpCodes.
+    /// Calculate number of Expression and other OpCodes (EFI_IFR_END included).
+    #[deprecated(
+        note = "use `IfrStats::from_package`, `expressions` and `total_op_codes()` give the same counts by name"
+    )]
     pub fn count_op_codes(package: &HiiFormPackageHdr) -> (usize, usize) {
         let mut count: (usize, usize) = (0, 0);
 
@@ -297,12 +308,19 @@ pub mod ifr_parse {
                 count.1 += 1;
             }
         }
-        return count;
+        count
     }
 
-    impl From<u8> for EfiIfrOpCode {
-        fn from(value: u8) -> Self {
-            unsafe { transmute(value) }
+    impl TryFrom<u8> for EfiIfrOpCode {
+        type Error = u8;
+
+        fn try_from(value: u8) -> Result<Self, Self::Error> {
+            if (EfiIfrOpCode::FormOp as u8..=EfiIfrOpCode::Match2Op as u8).contains(&value) {
+                // Opcodes are contiguous from FormOp to Match2Op
+                Ok(unsafe { transmute::<u8, EfiIfrOpCode>(value) })
+            } else {
+                Err(value)
+            }
         }
     }
 }
@@ -0,0 +1,155 @@
+use crate::HiiPackageType;
+use crate::HiiPackage;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use core::any::Any;
+use core::fmt;
+use core::mem;
+use core::ptr::{self};
+use uefi::Guid;
+
+pub struct HiiGuidPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiGuidPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::TypeGuid;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+const GUID_OFFSET: usize = mem::size_of::<EfiHiiPackageHeader>();
+const DATA_OFFSET: usize = GUID_OFFSET + mem::size_of::<Guid>();
+
+impl HiiGuidPackageHdr {
+    /// Creates a new HII (Human Interface Infrastructure) GUID package.
+    ///
+    /// # Example usage:
+    /// ```
+    /// use uefi::guid;
+    /// use hii_database::package_header::HiiGuidPackageHdr;
+    ///
+    /// let hii_pack = HiiGuidPackageHdr::create(guid!("ce4f5b0c-dc00-4a32-97ed-2966981c7725"), &[0x01, 0x02]);
+    /// ```
+    pub fn create(guid: Guid, data: &[u8]) -> HiiPackage {
+        let head = EfiHiiPackageHeader::new((DATA_OFFSET + data.len()) as u32, Self::PACKAGE_TYPE);
+
+        let mut guid_pack = Vec::with_capacity(head.length() as usize);
+        guid_pack.extend_from_slice(&head.to_bytes());
+        guid_pack.extend_from_slice(&guid.to_bytes());
+        guid_pack.extend_from_slice(data);
+        HiiPackage::new(guid_pack)
+    }
+
+    /// GUID identifying the vendor format of the package, `None` if the package
+    /// is too short to hold one
+    pub fn guid(&self) -> Option<Guid> {
+        if (self.parts.len() as usize) < DATA_OFFSET {
+            return None;
+        }
+        let guid = unsafe {
+            ptr::read_unaligned(self.parts.as_ptr().byte_add(GUID_OFFSET) as *const Guid)
+        };
+        Some(guid)
+    }
+
+    /// Vendor data that follows the GUID
+    pub fn as_data(&self) -> &[u8] {
+        unsafe {
+            let data = self.parts.location.byte_add(DATA_OFFSET);
+            let size_data = (self.parts.len() as usize).saturating_sub(DATA_OFFSET);
+            &*ptr::slice_from_raw_parts(data, size_data)
+        }
+    }
+
+    /// Decode the package with a decoder registered for its GUID
+    pub fn decode(&self, registry: &GuidPackageRegistry) -> Option<Box<dyn GuidPackageView>> {
+        registry.decode(self.guid()?, self.as_data())
+    }
+}
+
+/// Vendor-specific typed view of a GUID package.
+pub trait GuidPackageView: fmt::Display {
+    /// GUID of the package format the view was decoded from
+    fn guid(&self) -> Guid;
+
+    /// Allows downcasting to the concrete view type
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Decoder for the data of a GUID package, `None` if the data is malformed.
+pub type GuidPackageDecoder = fn(data: &[u8]) -> Option<Box<dyn GuidPackageView>>;
+
+/// Set of GUID package decoders keyed by package GUID.
+#[derive(Default)]
+pub struct GuidPackageRegistry {
+    decoders: Vec<(Guid, GuidPackageDecoder)>,
+}
+
+impl GuidPackageRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a decoder, replacing any decoder already registered for `guid`
+    pub fn register(&mut self, guid: Guid, decoder: GuidPackageDecoder) -> &mut Self {
+        match self.decoders.iter_mut().find(|(g, _)| *g == guid) {
+            Some(entry) => entry.1 = decoder,
+            None => self.decoders.push((guid, decoder)),
+        }
+        self
+    }
+
+    pub fn decoder(&self, guid: Guid) -> Option<GuidPackageDecoder> {
+        self.decoders
+            .iter()
+            .find(|(g, _)| *g == guid)
+            .map(|(_, decoder)| *decoder)
+    }
+
+    pub fn decode(&self, guid: Guid, data: &[u8]) -> Option<Box<dyn GuidPackageView>> {
+        self.decoder(guid).and_then(|decoder| decoder(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uefi::guid;
+
+    fn package(data: &[u8]) -> HiiGuidPackageHdr {
+        let header = EfiHiiPackageHeader::new(data.len() as u32, HiiPackageType::TypeGuid);
+        HiiGuidPackageHdr::from_undef(&UndefineHiiPackageHdr {
+            header,
+            location: data.as_ptr(),
+        })
+    }
+
+    #[test]
+    fn guid_of_package() {
+        let guid = guid!("ce4f5b0c-dc00-4a32-97ed-2966981c7725");
+        let data = HiiGuidPackageHdr::create(guid, &[0x01, 0x02]);
+        let pack = package(data.as_slice());
+        assert_eq!(pack.guid(), Some(guid));
+        assert_eq!(pack.as_data(), [0x01, 0x02]);
+    }
+
+    #[test]
+    fn truncated_package_has_no_guid() {
+        let data = [0x04, 0x00, 0x00, 0x01];
+        let pack = package(&data);
+        assert_eq!(pack.guid(), None);
+        assert!(pack.as_data().is_empty());
+        assert!(pack.decode(&GuidPackageRegistry::new()).is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+pub struct HiiImagePackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiImagePackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::Images;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiImagePackageHdr {
+    /// `None` if the package is too short to hold the field
+    pub fn image_info_offset(&self) -> Option<u32> {
+        self.parts.read(4)
+    }
+
+    /// `None` if the package is too short to hold the field
+    pub fn palette_info_offset(&self) -> Option<u32> {
+        self.parts.read(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_header::DefinedStruct;
+
+    fn package(data: &[u8]) -> HiiImagePackageHdr {
+        let header = EfiHiiPackageHeader::new(data.len() as u32, HiiPackageType::Images);
+        HiiImagePackageHdr::from_undef(&UndefineHiiPackageHdr {
+            header,
+            location: data.as_ptr(),
+        })
+    }
+
+    #[test]
+    fn offsets_of_package() {
+        let data = [
+            0x0C, 0x00, 0x00, 0x06, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let pack = package(&data);
+        assert_eq!(pack.image_info_offset(), Some(0x0C));
+        assert_eq!(pack.palette_info_offset(), Some(0));
+    }
+
+    #[test]
+    fn truncated_package_has_no_offsets() {
+        let data = [0x04, 0x00, 0x00, 0x06];
+        let pack = package(&data);
+        assert_eq!(pack.image_info_offset(), None);
+        assert_eq!(pack.palette_info_offset(), None);
+        assert_eq!(
+            alloc::format!("{}", DefinedStruct::ImagePackage(pack)),
+            "IMAGES size=0x04 truncated"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use core::ptr::{self};
+use uefi::Guid;
+
+pub struct HiiKeyboardLayoutPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiKeyboardLayoutPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::KeyboardLayout;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiKeyboardLayoutPackageHdr {
+    /// `None` if the package is too short to hold the field
+    pub fn layout_count(&self) -> Option<u16> {
+        self.parts.read(4)
+    }
+
+    /// GUIDs of the keyboard layouts contained in the package
+    pub fn layout_guids(&self) -> Vec<Guid> {
+        let count = self.layout_count().unwrap_or(0);
+        let mut guids = Vec::with_capacity(count as usize);
+        // The first EFI_HII_KEYBOARD_LAYOUT follows the LayoutCount field
+        let mut offset: u32 = 6;
+
+        for _ in 0..count {
+            // LayoutLength (u16) + Guid
+            if offset + 18 > self.parts.len() {
+                break;
+            }
+            unsafe {
+                let layout = self.parts.as_ptr().byte_add(offset as usize);
+                let layout_length = ptr::read_unaligned(layout as *const u16);
+                guids.push(ptr::read_unaligned(layout.byte_add(2) as *const Guid));
+
+                if layout_length == 0 {
+                    break;
+                }
+                offset += layout_length as u32;
+            }
+        }
+        guids
+    }
+}
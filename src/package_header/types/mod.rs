@@ -7,6 +7,27 @@ pub use font_package::*;
 mod string_package;
 pub use string_package::*;
 
+mod image_package;
+pub use image_package::*;
+
+mod simple_font_package;
+pub use simple_font_package::*;
+
+mod device_path_package;
+pub use device_path_package::*;
+
+mod keyboard_layout_package;
+pub use keyboard_layout_package::*;
+
+mod animation_package;
+pub use animation_package::*;
+
+mod guid_package;
+pub use guid_package::*;
+
+mod system_package;
+pub use system_package::*;
+
 mod end_package;
 pub use end_package::*;
 
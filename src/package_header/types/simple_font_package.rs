@@ -0,0 +1,30 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+
+pub struct HiiSimpleFontPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiSimpleFontPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::SimpleFonts;
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiSimpleFontPackageHdr {
+    /// `None` if the package is too short to hold the field
+    pub fn number_of_narrow_glyphs(&self) -> Option<u16> {
+        self.parts.read(4)
+    }
+
+    /// `None` if the package is too short to hold the field
+    pub fn number_of_wide_glyphs(&self) -> Option<u16> {
+        self.parts.read(6)
+    }
+}
@@ -163,25 +163,134 @@ impl HiiStringPackageHdr {
         }
     }
 
-    // Get string from package 
+    // Get string from package, `string_id` is the index of the string (STRING_ID - 1)
     pub fn get_string(&self, string_id: u16, language: &CStr8) -> Option<String> {
         if self.language() != language.as_bytes() {
             return None;
         }
 
-        self.blocks()
-            .nth(string_id as usize)
-            .and_then(|un_str_block| un_str_block.get_string().map(|s| s.to_string()))
+        self.to_table()
+            .get(string_id.checked_add(1)?)
+            .map(|s| s.to_string())
     }
 
-    // Get count strings
+    // Get count strings, skipped string IDs included
     pub fn count_strings(&self) -> usize {
-        self.blocks().count()
+        self.blocks().map(|block| block.data().string_ids()).sum()
+    }
+
+    /// Copy all strings of the package into a [`StringTable`], string IDs
+    /// are numbered across every string block up to EFI_HII_SIBT_END
+    pub fn to_table(&self) -> StringTable {
+        let mut table = StringTable::new(self.str_language());
+        let mut string_id: u16 = 1;
+        for block in self.blocks() {
+            let data = block.data();
+            match &data {
+                StringBlockData::Strings(strings) => {
+                    for (index, string) in strings.iter().enumerate() {
+                        table.insert(string_id.wrapping_add(index as u16), string.clone());
+                    }
+                }
+                StringBlockData::Duplicate(original) => {
+                    if let Some(string) = table.get(*original).map(String::from) {
+                        table.insert(string_id, string);
+                    }
+                }
+                StringBlockData::Skip(_) | StringBlockData::Other => {}
+            }
+            string_id = string_id.wrapping_add(data.string_ids() as u16);
+        }
+        table
+    }
+}
+
+/// Strings of one language indexed by STRING_ID (the first string has ID 1).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringTable {
+    language: String,
+    strings: Vec<Option<String>>,
+}
+
+impl StringTable {
+    pub fn new(language: String) -> Self {
+        Self {
+            language,
+            strings: Vec::new(),
+        }
+    }
+
+    /// Table for `language` built from the first matching package,
+    /// or from the first package if none matches
+    pub fn from_packages(packages: &[HiiStringPackageHdr], language: &str) -> Self {
+        packages
+            .iter()
+            .find(|sph| sph.str_language() == language)
+            .or(packages.first())
+            .map(|sph| sph.to_table())
+            .unwrap_or_default()
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn insert(&mut self, string_id: u16, string: String) {
+        if string_id == 0 {
+            return;
+        }
+        let index = string_id as usize - 1;
+        if self.strings.len() <= index {
+            self.strings.resize(index + 1, None);
+        }
+        self.strings[index] = Some(string);
+    }
+
+    pub fn get(&self, string_id: u16) -> Option<&str> {
+        let index = (string_id as usize).checked_sub(1)?;
+        self.strings.get(index)?.as_deref()
+    }
+
+    pub fn contains(&self, string_id: u16) -> bool {
+        self.get(string_id).is_some()
+    }
+
+    /// Find the ID of a string by its text
+    pub fn find(&self, string: &str) -> Option<u16> {
+        self.strings
+            .iter()
+            .position(|s| s.as_deref() == Some(string))
+            .map(|index| index as u16 + 1)
     }
 }
 
 type HiiStringBlock = u8;
 
+/// What a string block gives to the string IDs following it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringBlockData {
+    /// Strings of the next string IDs, SCSU strings are read as Latin-1
+    Strings(Vec<String>),
+    /// Next string ID has the text of this string ID
+    Duplicate(u16),
+    /// Number of string IDs without a string
+    Skip(u16),
+    /// Extended blocks, no string ID
+    Other,
+}
+
+impl StringBlockData {
+    /// Number of string IDs used by the block
+    pub fn string_ids(&self) -> usize {
+        match self {
+            StringBlockData::Strings(strings) => strings.len(),
+            StringBlockData::Duplicate(_) => 1,
+            StringBlockData::Skip(count) => *count as usize,
+            StringBlockData::Other => 0,
+        }
+    }
+}
+
 pub struct UndefineHiiStringBlock {
     location: *const u8,
 }
@@ -213,6 +322,114 @@ impl UndefineHiiStringBlock {
             None
         }
     }
+
+    /// Strings, duplicate or skip count of the block
+    pub fn data(&self) -> StringBlockData {
+        let (scsu, ucs2) = (Self::scsu_string, Self::ucs2_string);
+        unsafe {
+            match self.block_type() {
+                0x10 => StringBlockData::Strings(vec![self.scsu_string(1).0]),
+                0x11 => StringBlockData::Strings(vec![self.scsu_string(2).0]),
+                0x12 => StringBlockData::Strings(self.strings(3, self.u16_at(1), scsu)),
+                0x13 => StringBlockData::Strings(self.strings(4, self.u16_at(2), scsu)),
+                0x14 => StringBlockData::Strings(vec![self.ucs2_string(1).0]),
+                0x15 => StringBlockData::Strings(vec![self.ucs2_string(2).0]),
+                0x16 => StringBlockData::Strings(self.strings(3, self.u16_at(1), ucs2)),
+                0x17 => StringBlockData::Strings(self.strings(4, self.u16_at(2), ucs2)),
+                0x20 => StringBlockData::Duplicate(self.u16_at(1)),
+                0x21 => StringBlockData::Skip(self.u16_at(1)),
+                0x22 => StringBlockData::Skip(*self.location.byte_offset(1) as u16),
+                _ => StringBlockData::Other,
+            }
+        }
+    }
+
+    /// Size of the block in bytes, `None` for EFI_HII_SIBT_END and unknown blocks
+    pub fn size(&self) -> Option<usize> {
+        let (scsu, ucs2) = (Self::scsu_string, Self::ucs2_string);
+        unsafe {
+            let size = match self.block_type() {
+                0x10 => 1 + self.scsu_string(1).1,
+                0x11 => 2 + self.scsu_string(2).1,
+                0x12 => 3 + self.strings_size(3, self.u16_at(1), scsu),
+                0x13 => 4 + self.strings_size(4, self.u16_at(2), scsu),
+                0x14 => 1 + self.ucs2_string(1).1,
+                0x15 => 2 + self.ucs2_string(2).1,
+                0x16 => 3 + self.strings_size(3, self.u16_at(1), ucs2),
+                0x17 => 4 + self.strings_size(4, self.u16_at(2), ucs2),
+                0x20 | 0x21 => 3,
+                0x22 => 2,
+                // Length of extended blocks includes the header
+                0x30 => *self.location.byte_offset(2) as usize,
+                0x31 => self.u16_at(2) as usize,
+                0x32 => ptr::read_unaligned(self.location.byte_offset(2) as *const u32) as usize,
+                _ => return None,
+            };
+            // Malformed extended blocks would never advance
+            (size > 0).then_some(size)
+        }
+    }
+
+    unsafe fn u16_at(&self, offset: usize) -> u16 {
+        unsafe { ptr::read_unaligned(self.location.add(offset) as *const u16) }
+    }
+
+    /// Null terminated UCS-2 string at `offset` and its size with the terminator
+    unsafe fn ucs2_string(&self, offset: usize) -> (String, usize) {
+        unsafe {
+            let ucs2_string = self.location.add(offset) as *const u16;
+            let mut len = 0;
+            while ptr::read_unaligned(ucs2_string.add(len)) != 0 {
+                len += 1
+            }
+            let text = (0..len)
+                .map(|index| ptr::read_unaligned(ucs2_string.add(index)))
+                .collect::<Vec<u16>>();
+            (String::from_utf16_lossy(&text), (len + 1) * 2)
+        }
+    }
+
+    /// Null terminated SCSU string at `offset` and its size with the terminator
+    unsafe fn scsu_string(&self, offset: usize) -> (String, usize) {
+        unsafe {
+            let scsu_string = self.location.add(offset);
+            let mut len = 0;
+            while *scsu_string.add(len) != 0 {
+                len += 1
+            }
+            let text = (0..len)
+                .map(|index| *scsu_string.add(index) as char)
+                .collect();
+            (text, len + 1)
+        }
+    }
+
+    /// `count` consecutive strings starting at `offset`
+    unsafe fn strings(
+        &self,
+        offset: usize,
+        count: u16,
+        read: unsafe fn(&Self, usize) -> (String, usize),
+    ) -> Vec<String> {
+        let mut strings = Vec::with_capacity(count as usize);
+        let mut offset = offset;
+        for _ in 0..count {
+            let (string, size) = unsafe { read(self, offset) };
+            strings.push(string);
+            offset += size;
+        }
+        strings
+    }
+
+    /// Size of `count` consecutive strings starting at `offset`
+    unsafe fn strings_size(
+        &self,
+        offset: usize,
+        count: u16,
+        read: unsafe fn(&Self, usize) -> (String, usize),
+    ) -> usize {
+        (0..count).fold(0, |size, _| size + unsafe { read(self, offset + size) }.1)
+    }
 }
 
 pub struct HiiStringBlockIter {
@@ -228,27 +445,12 @@ impl HiiStringBlockIter {
 impl Iterator for HiiStringBlockIter {
     type Item = UndefineHiiStringBlock;
 
+    // Every block up to EFI_HII_SIBT_END, stops at blocks of unknown size
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            let block = ptr::read_unaligned(self.location as *const HiiStringBlock);
-            match block {
-                0x14 => {
-                    // EFI_HII_SIBT_STRING_UCS2
-                    let ucs2_string = self.location.byte_offset(1) as *const Char16;
-                    let mut len = 0;
-                    while *ucs2_string.add(len) != Char16::from_u16_unchecked(0) {
-                        len += 1
-                    }
-                    len = ((len + 1) * 2) + 1;
-                    // Return the current block
-                    let block = UndefineHiiStringBlock::new(self.location);
-                    // Moving the iterator to the next block
-                    self.location = self.location.byte_offset(len as isize);
-
-                    Some(block)
-                }
-                _ => None,
-            }
-        }
+        let block = UndefineHiiStringBlock::new(self.location);
+        let size = block.size()?;
+        // Moving the iterator to the next block
+        self.location = unsafe { self.location.byte_offset(size as isize) };
+        Some(block)
     }
 }
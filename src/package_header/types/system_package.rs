@@ -0,0 +1,95 @@
+use crate::HiiPackageType;
+use crate::package_header::{EfiHiiPackageHeader, PackageHeader, UndefineHiiPackageHdr};
+use core::ptr::{self};
+
+/// Package in the system range EFI_HII_PACKAGE_TYPE_SYSTEM_BEGIN..=EFI_HII_PACKAGE_TYPE_SYSTEM_END
+pub struct HiiSystemPackageHdr {
+    parts: UndefineHiiPackageHdr,
+}
+
+impl PackageHeader for HiiSystemPackageHdr {
+    const PACKAGE_TYPE: HiiPackageType = HiiPackageType::System(HiiPackageType::SYSTEM_BEGIN);
+
+    fn matches(ty: HiiPackageType) -> bool {
+        matches!(ty, HiiPackageType::System(_))
+    }
+
+    fn from_undef(pack_head: &UndefineHiiPackageHdr) -> Self {
+        Self { parts: *pack_head }
+    }
+
+    fn header(&self) -> EfiHiiPackageHeader {
+        self.parts.header
+    }
+}
+
+impl HiiSystemPackageHdr {
+    /// Raw package type in the system range
+    pub fn raw_type(&self) -> u8 {
+        self.parts.header.to_bytes()[3]
+    }
+
+    /// System-defined data that follows the package header
+    pub fn as_data(&self) -> &[u8] {
+        unsafe {
+            let data = self.parts.location.byte_offset(4);
+            let size_data = self.parts.len().saturating_sub(4);
+            &*ptr::slice_from_raw_parts(data, size_data as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_header::UndefineHiiPackageIter;
+    use uefi::guid;
+
+    extern crate alloc;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn matches_whole_system_range() {
+        assert!(HiiSystemPackageHdr::matches(HiiPackageType::from_raw(0xE0)));
+        assert!(HiiSystemPackageHdr::matches(HiiPackageType::from_raw(0xE5)));
+        assert!(HiiSystemPackageHdr::matches(HiiPackageType::from_raw(0xFF)));
+        assert!(!HiiSystemPackageHdr::matches(HiiPackageType::End));
+    }
+
+    #[test]
+    fn try_from_rejects_undefined_types() {
+        assert_eq!(
+            HiiPackageType::try_from(0xE5),
+            Ok(HiiPackageType::System(0xE5))
+        );
+        assert_eq!(HiiPackageType::try_from(0x04), Ok(HiiPackageType::Strings));
+        assert_eq!(HiiPackageType::try_from(0x03), Err(0x03));
+        assert_eq!(HiiPackageType::try_from(0xDE), Err(0xDE));
+        assert_eq!(
+            HiiPackageType::from_raw(0xDE),
+            HiiPackageType::Unknown(0xDE)
+        );
+    }
+
+    #[test]
+    fn filters_system_package_of_list() {
+        let system = [0x06, 0x00, 0x00, 0xE5, 0xAA, 0xBB];
+        let end = EfiHiiPackageHeader::new(4, HiiPackageType::End).to_bytes();
+        // EFI_HII_PACKAGE_LIST_HEADER: PackageListGuid, PackageLength
+        let mut data = guid!("ce4f5b0c-dc00-4a32-97ed-2966981c7725")
+            .to_bytes()
+            .to_vec();
+        data.extend_from_slice(&((20 + system.len() + end.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&system);
+        data.extend_from_slice(&end);
+
+        let packages: Vec<HiiSystemPackageHdr> =
+            unsafe { UndefineHiiPackageIter::new(data.as_ptr()) }
+                .filter(|head| HiiSystemPackageHdr::matches(head.header.get_type()))
+                .map(|head| HiiSystemPackageHdr::from_undef(&head))
+                .collect();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].raw_type(), 0xE5);
+        assert_eq!(packages[0].as_data(), [0xAA, 0xBB]);
+    }
+}
@@ -1,7 +1,5 @@
-extern crate alloc;
-use alloc::string::String;
-
 use crate::package_header::EfiHiiPackageHeader;
+use core::mem;
 use core::ptr::{self};
 
 #[derive(Clone, Copy)]
@@ -13,23 +11,8 @@ pub struct UndefineHiiPackageHdr {
 }
 
 impl UndefineHiiPackageHdr {
-    pub fn package_type(&self) -> String {
-        match self.header.r#type {
-            0x00 => String::from("ALL"),
-            0x01 => String::from("GUID"),
-            0x02 => String::from("FORMS"),
-            0x04 => String::from("STRINGS"),
-            0x05 => String::from("FONTS"),
-            0x06 => String::from("IMAGES"),
-            0x07 => String::from("SIMPLE_FONTS"),
-            0x08 => String::from("DEVICE_PATH"),
-            0x09 => String::from("KEYBOARD_LAYOU"),
-            0x0A => String::from("ANIMATIONS"),
-            0xDF => String::from("END"),
-            0xE0 => String::from("SYSTEM_BEGIN"),
-            0xFF => String::from("SYSTEM_END"),
-            _ => String::from("UNKNOWN"),
-        }
+    pub fn package_type(&self) -> &'static str {
+        self.header.get_type().name()
     }
 
     pub fn len(&self) -> u32 {
@@ -43,4 +26,12 @@ impl UndefineHiiPackageHdr {
     pub fn get_slice(self) -> *const [u8] {
         ptr::slice_from_raw_parts(self.location, self.len() as usize)
     }
+
+    /// Field at `offset` of the package, `None` if it is past the package length
+    pub(crate) fn read<T: Copy>(&self, offset: usize) -> Option<T> {
+        if offset + mem::size_of::<T>() > self.len() as usize {
+            return None;
+        }
+        Some(unsafe { ptr::read_unaligned(self.location.byte_add(offset) as *const T) })
+    }
 }